@@ -11,18 +11,79 @@ mod utils;
 // Import required dependencies
 use log::{error, info, LevelFilter};
 use std::process;
+use tauri::{Manager, State};
+use utils::allowed_roots::AllowedRoots;
+use utils::app_config::ConfigState;
+use utils::audit::{AuditLog, DEFAULT_MAX_BACKUPS, DEFAULT_MAX_BYTES};
+use utils::degraded::{
+    init_optional, DegradedFeatures, CLIPBOARD_FEATURE, DIALOG_FEATURE, FS_FEATURE, SHELL_FEATURE,
+};
+use utils::error::{log_error, log_error_cat, AppError};
+use utils::file_lock::FileLockRegistry;
+use utils::fs_backend::default_backend;
+use utils::integrity::{spawn_integrity_monitor, IntegrityMonitor};
+use utils::memory_safe::{BoundaryValidator, ValidatorConfig};
+use utils::metadata_cache::MetadataCache;
+use utils::panic_hook::install_panic_hook;
+use utils::progress::OperationRegistry;
+use utils::redact::Redactor;
+use utils::sanitize::{SanitizerPipeline, Truncate};
+use utils::scan::ScanRegistry;
+use utils::scoped_access::ScopedAccess;
+use utils::secure_registry::global_registry;
+use utils::tempfile::TempFileRegistry;
+use utils::upload::WriteRegistry;
+use utils::vault::{spawn_vault_sweeper, SecretVault};
+use utils::watcher::WatcherRegistry;
+use utils::window_caps::{CapabilityToken, WindowCapabilities};
 
-// Security-focused error handling
+// Longest name echoed back by `greet`, in characters
+const MAX_GREETING_NAME_CHARS: usize = 64;
+
+// Security-focused error handling, tagging the entry with the frontend's error category
 #[tauri::command]
-fn handle_error(error_message: String) -> Result<(), String> {
-    error!("Application error: {}", error_message);
-    Err(error_message)
+fn handle_error(
+    error_message: String,
+    category: Option<String>,
+    redactor: State<'_, Redactor>,
+) -> Result<(), String> {
+    Err(log_frontend_error(
+        &redactor,
+        category.as_deref(),
+        &error_message,
+    ))
+}
+
+// Log a frontend error; categories that could forge log lines are dropped
+fn log_frontend_error(redactor: &Redactor, category: Option<&str>, message: &str) -> String {
+    let category = category
+        .filter(|c| !c.is_empty() && c.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    match category {
+        Some(category) => log_error_cat(redactor, category, message),
+        None => log_error(redactor, message),
+    }
 }
 
-// Greet command implementation
+// Greet command implementation, normalizing, validating and sanitizing the name before echoing it
 #[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
+fn greet(name: &str, config: State<'_, ConfigState>) -> Result<String, AppError> {
+    greet_with(name, &config.current().validator)
+}
+
+// Greet using the given Unicode policy
+fn greet_with(name: &str, validator: &ValidatorConfig) -> Result<String, AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::Validation("Name must not be empty".into()));
+    }
+    let name = BoundaryValidator::normalize_string(name, validator)
+        .map_err(|_| AppError::Validation("Invalid input detected".into()))?;
+
+    // Cap the echoed name, then encode it for HTML
+    let name = SanitizerPipeline::new()
+        .stage(Truncate(MAX_GREETING_NAME_CHARS))
+        .stage(|name: String| BoundaryValidator::sanitize_string(&name))
+        .apply(name);
+    Ok(format!("Hello, {}! You've been greeted from Rust!", name))
 }
 
 // Main entry point for the library
@@ -33,6 +94,9 @@ pub fn run() {
         .parse_default_env()
         .init();
 
+    // Log panics redacted instead of printing their raw messages
+    install_panic_hook(Redactor::default());
+
     info!("Starting application with enhanced security features");
 
     // Run the Tauri application with security features
@@ -50,20 +114,195 @@ fn run_app() -> Result<(), Box<dyn std::error::Error>> {
     // Build the Tauri application with security features
     tauri::Builder::default()
         // Register the security command handlers
-        .setup(|_app| {
+        .setup(|app| {
             info!("Setting up application with security enhancements");
+
+            // Register plugins here rather than on the builder, so one that fails
+            // to start leaves its feature unavailable instead of aborting startup
+            let degraded = DegradedFeatures::default();
+            let handle = app.handle();
+            init_optional(&degraded, DIALOG_FEATURE, || {
+                handle.plugin(tauri_plugin_dialog::init())
+            });
+            init_optional(&degraded, SHELL_FEATURE, || {
+                handle.plugin(tauri_plugin_shell::init())
+            });
+            init_optional(&degraded, FS_FEATURE, || {
+                handle.plugin(tauri_plugin_fs::init())
+            });
+            init_optional(&degraded, CLIPBOARD_FEATURE, || {
+                handle.plugin(tauri_plugin_clipboard_manager::init())
+            });
+            app.manage(degraded);
+
+            // Record security-relevant command invocations to a rolling audit log
+            let audit_path = app.path().app_log_dir()?.join("audit.log");
+            app.manage(AuditLog::new(
+                audit_path,
+                DEFAULT_MAX_BYTES,
+                DEFAULT_MAX_BACKUPS,
+            ));
+
+            // Load security settings; a malformed file falls back to defaults.
+            // Without configured roots, file commands are confined to the app's
+            // own data directory.
+            let data_dir = app.path().app_data_dir()?;
+            std::fs::create_dir_all(&data_dir)?;
+            let config_path = app.path().app_config_dir()?.join("config.json");
+            let config =
+                ConfigState::load_or_default(config_path).with_default_roots(vec![data_dir]);
+
+            // Confine file commands to the configured roots, then lock the allowlist
+            // against the frontend unless the config opts out; `reload_config`
+            // still applies new roots
+            let roots = AllowedRoots::new();
+            config.apply_roots(&roots);
+            if config.current().seal_allowed_roots {
+                roots.seal();
+            }
+            app.manage(roots);
+            app.manage(config);
+
+            // Only the main window may call privileged commands
+            let capabilities = WindowCapabilities::new();
+            capabilities.grant("main", CapabilityToken::PROCESS_PATHS);
+            capabilities.grant("main", CapabilityToken::WRITE_FILES);
+            capabilities.grant("main", CapabilityToken::RUN_COMMANDS);
+            app.manage(capabilities);
+
+            // Zero vault secrets once their time to live has passed
+            spawn_vault_sweeper(app.handle().clone());
+
+            // Re-hash monitored files and report any that were tampered with
+            spawn_integrity_monitor(app.handle().clone());
+
             Ok(())
         })
-        // Register security plugins
-        .plugin(tauri_plugin_dialog::init())
-        .plugin(tauri_plugin_shell::init())
-        .plugin(tauri_plugin_fs::init())
+        // File commands reach the disk through this backend
+        .manage(default_backend())
+        // Hold advisory file locks until they are released
+        .manage(FileLockRegistry::default())
+        // Keep the digests of files monitored for tampering
+        .manage(IntegrityMonitor::default())
+        // Reuse file metadata until the file's mtime or size changes
+        .manage(MetadataCache::default())
+        // Scrub secrets from messages before they are logged
+        .manage(Redactor::default())
+        // Track in-flight directory scans so they can be cancelled
+        .manage(ScanRegistry::default())
+        // Let windows read what they picked in a dialog until the grant expires
+        .manage(ScopedAccess::default())
+        // Track in-flight copies and deletes so they can be cancelled
+        .manage(OperationRegistry::default())
+        // Share the secure allocation registry with commands
+        .manage(global_registry())
+        // Hold temporary files handed to the frontend until they are released
+        .manage(TempFileRegistry::default())
+        // Hold short-lived secrets for the frontend until they expire
+        .manage(SecretVault::default())
+        // Keep active file watchers alive until they are unwatched
+        .manage(WatcherRegistry::default())
+        // Hold chunked file writes until they finish or expire
+        .manage(WriteRegistry::default())
         // Register our security commands
         .invoke_handler(tauri::generate_handler![
             handle_error,
             greet,
+            utils::allowed_roots::add_allowed_root,
+            utils::allowed_roots::path_is_allowed,
+            utils::allowed_roots::remove_allowed_root,
+            #[cfg(feature = "debug_diagnostics")]
+            utils::app_config::dump_effective_config,
+            utils::app_config::reload_config,
+            utils::archive::extract_archive,
+            utils::clipboard::copy_secret_to_clipboard,
+            utils::clipboard::set_clipboard,
+            utils::compression::compress_file,
+            utils::compression::decompress_file,
+            utils::config::read_config_interpolated,
+            utils::crypto::decrypt_file,
+            utils::crypto::encrypt_file,
+            utils::crypto::find_duplicates,
+            utils::crypto::generate_secure_token,
+            utils::crypto::hash_password,
+            utils::crypto::verify_file_hash,
+            utils::crypto::verify_password,
+            utils::degraded::degraded_features,
+            utils::dialog::select_directory,
+            utils::dialog::select_files,
+            utils::dialog::select_files_filtered,
+            utils::encoding::decode_bytes,
+            utils::encoding::encode_bytes,
+            utils::entropy::file_entropy,
+            utils::file_lock::acquire_file_lock,
+            utils::file_lock::release_file_lock,
+            utils::file_ops::copy_file,
+            utils::file_ops::create_directory,
+            utils::file_ops::delete_directory,
+            utils::file_ops::rename_file,
+            utils::file_ops::set_file_times,
+            utils::file_system::canonicalize_path,
+            utils::file_system::diff_directories,
+            utils::file_system::directory_size,
+            utils::file_system::find_case_collisions,
+            utils::file_system::get_file_info,
+            utils::file_system::get_file_info_batch,
+            utils::file_system::get_file_info_raw,
+            utils::file_system::get_file_info_timeout,
+            utils::file_system::list_directory,
+            utils::file_system::list_directory_glob,
+            utils::file_system::path_kind,
+            utils::file_system::read_file_range,
+            utils::file_system::relativize_path,
+            utils::file_system::search_directory,
+            utils::file_system::tail_file,
+            utils::integrity::monitor_file,
+            utils::integrity::pause_integrity_monitor,
+            utils::integrity::resume_integrity_monitor,
+            utils::integrity::set_integrity_interval,
+            utils::integrity::unmonitor_file,
             utils::memory_safe::handle_sensitive_data,
             utils::memory_safe::validate_and_process_path,
+            utils::memory_safe::validate_inputs_batch,
+            utils::metadata_cache::clear_cache,
+            utils::metadata_cache::invalidate_path,
+            utils::onetime_secret::read_onetime_secret,
+            utils::onetime_secret::write_onetime_secret,
+            utils::opener::open_with_default,
+            #[cfg(feature = "debug_diagnostics")]
+            utils::panic_hook::recent_panic_reports,
+            utils::permissions::enforce_secure_permissions,
+            utils::preview::generate_preview,
+            utils::process::run_allowed_command,
+            utils::progress::cancel_operation,
+            utils::scan::cancel_scan,
+            utils::scan::start_directory_scan,
+            utils::scan::start_directory_stream,
+            utils::secrets::read_env_allowed,
+            #[cfg(feature = "debug_diagnostics")]
+            utils::secure_registry::secure_memory_stats,
+            #[cfg(feature = "debug_diagnostics")]
+            utils::self_check::list_capabilities,
+            #[cfg(feature = "debug_diagnostics")]
+            utils::self_check::security_self_check,
+            utils::shortcut::inspect_shortcut,
+            utils::shred::secure_delete_file,
+            utils::signing::sign_file,
+            utils::signing::verify_file,
+            utils::tempfile::create_temp_file,
+            utils::tempfile::release_temp_file,
+            utils::upload::begin_file_write,
+            utils::upload::finish_file_write,
+            utils::upload::write_file_chunk,
+            utils::vault::vault_clear_all,
+            utils::vault::vault_get,
+            utils::vault::vault_put,
+            utils::volumes::disk_space_for,
+            utils::volumes::list_volumes,
+            utils::watcher::unwatch_directory,
+            utils::watcher::unwatch_file,
+            utils::watcher::watch_directory,
+            utils::watcher::watch_file,
         ])
         .run(tauri::generate_context!())
         .map_err(|e| {
@@ -71,3 +310,60 @@ fn run_app() -> Result<(), Box<dyn std::error::Error>> {
             e.into()
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_greet_normal_name() {
+        assert_eq!(
+            greet_with("Ada", &ValidatorConfig::default()).unwrap(),
+            "Hello, Ada! You've been greeted from Rust!"
+        );
+    }
+
+    #[test]
+    fn test_greet_sanitizes_ampersand() {
+        assert_eq!(
+            greet_with("Tom & Jerry", &ValidatorConfig::default()).unwrap(),
+            "Hello, Tom &amp; Jerry! You've been greeted from Rust!"
+        );
+        let long_name = "a".repeat(MAX_GREETING_NAME_CHARS + 10);
+        assert_eq!(
+            greet_with(&long_name, &ValidatorConfig::default()).unwrap(),
+            format!(
+                "Hello, {}! You've been greeted from Rust!",
+                "a".repeat(MAX_GREETING_NAME_CHARS)
+            )
+        );
+    }
+
+    #[test]
+    fn test_frontend_error_is_returned_unchanged() {
+        let redactor = Redactor::default();
+        for category in [None, Some("VALIDATION"), Some("x]\n[forged")] {
+            assert_eq!(
+                log_frontend_error(&redactor, category, "token sk_live_123 rejected"),
+                "token sk_live_123 rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_greet_rejects_injection_and_empty() {
+        assert!(matches!(
+            greet_with("<img src=x onerror=alert(1)>", &ValidatorConfig::default()),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            greet_with("   ", &ValidatorConfig::default()),
+            Err(AppError::Validation(_))
+        ));
+        // A zero-width space can't hide the injection from the validator
+        assert!(matches!(
+            greet_with("<scr\u{200B}ipt>", &ValidatorConfig::default()),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
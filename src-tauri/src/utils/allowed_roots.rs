@@ -0,0 +1,319 @@
+//! Central path allowlist for Tauri applications
+//!
+//! File commands may only touch paths under a set of allowed root directories:
+//! 1. `AllowedRoots` lives in managed state and holds canonical root paths
+//! 2. Queried paths are canonicalized too, so `..`, symlinks and sibling
+//!    prefixes such as `/data-evil` next to `/data` can't escape a root
+//! 3. Roots are seeded during setup, after which the registry is sealed and
+//!    the `add_allowed_root`/`remove_allowed_root` commands are refused, unless
+//!    the app config sets `seal_allowed_roots` to `false`
+//! 4. Rust code can still change the roots through the inherent methods, as
+//!    `reload_config` does when the configured roots change
+
+use super::error::AppError;
+use log::{error, info};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use tauri::State;
+
+/// Canonical root directories that file commands are confined to
+#[derive(Debug, Default)]
+pub struct AllowedRoots {
+    roots: RwLock<Vec<PathBuf>>,
+    sealed: AtomicBool,
+}
+
+/// Resolve `path` to its canonical form
+///
+/// Paths that don't exist yet resolve through their parent directory, so a
+/// new file can be checked before it is created.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    path.canonicalize().ok().or_else(|| {
+        let name = path.file_name()?;
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        parent.canonicalize().ok().map(|p| p.join(name))
+    })
+}
+
+/// Resolve `path` to its canonical parent joined with its own name
+///
+/// Unlike `resolve`, a final symlink is not followed.
+fn resolve_entry(path: &Path) -> Option<PathBuf> {
+    match path.file_name() {
+        Some(name) => {
+            let parent = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            parent.canonicalize().ok().map(|p| p.join(name))
+        }
+        None => path.canonicalize().ok(),
+    }
+}
+
+impl AllowedRoots {
+    /// Create an empty, unsealed allowlist; nothing is allowed until a root is added
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow paths under the directory `root`, returning its canonical form
+    pub fn add(&self, root: &Path) -> Result<PathBuf, AppError> {
+        let canonical = root.canonicalize().map_err(|e| {
+            AppError::Validation(format!("Failed to resolve {}: {}", root.display(), e))
+        })?;
+        if !canonical.is_dir() {
+            return Err(AppError::Validation(format!(
+                "Allowed root is not a directory: {}",
+                root.display()
+            )));
+        }
+
+        let mut roots = self.roots.write().unwrap_or_else(|e| e.into_inner());
+        if !roots.contains(&canonical) {
+            info!("Allowing file access under {}", canonical.display());
+            roots.push(canonical.clone());
+        }
+        Ok(canonical)
+    }
+
+    /// Replace every root with `roots`, skipping ones that can't be resolved
+    ///
+    /// The new list is swapped in at once, so a check never sees a mix of the
+    /// old and new roots. Returns the canonical roots now in effect.
+    pub fn replace(&self, roots: &[PathBuf]) -> Vec<PathBuf> {
+        let mut resolved: Vec<PathBuf> = Vec::with_capacity(roots.len());
+        for root in roots {
+            match root.canonicalize() {
+                Ok(canonical) if canonical.is_dir() => {
+                    if !resolved.contains(&canonical) {
+                        resolved.push(canonical);
+                    }
+                }
+                Ok(_) => error!("Skipping allowed root {}: not a directory", root.display()),
+                Err(e) => error!("Skipping allowed root {}: {}", root.display(), e),
+            }
+        }
+
+        info!("Allowing file access under {} root(s)", resolved.len());
+        *self.roots.write().unwrap_or_else(|e| e.into_inner()) = resolved.clone();
+        resolved
+    }
+
+    /// Stop allowing paths under `root`, returning whether it was present
+    pub fn remove(&self, root: &Path) -> bool {
+        let canonical = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let mut roots = self.roots.write().unwrap_or_else(|e| e.into_inner());
+        let before = roots.len();
+        roots.retain(|r| *r != canonical);
+        roots.len() != before
+    }
+
+    /// Refuse further changes from the frontend
+    pub fn seal(&self) {
+        self.sealed.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether `seal` has been called
+    pub fn is_sealed(&self) -> bool {
+        self.sealed.load(Ordering::SeqCst)
+    }
+
+    /// Snapshot of the current roots
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.roots.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Whether `path` resolves to a location under one of the roots
+    pub fn is_allowed(&self, path: &Path) -> bool {
+        self.check(path).is_ok()
+    }
+
+    /// Ensure `path` resolves under one of the roots, returning its canonical form
+    ///
+    /// Act on the returned path rather than on `path`, so a symlink swapped in
+    /// after the check can't redirect the operation.
+    pub fn check(&self, path: &Path) -> Result<PathBuf, AppError> {
+        self.confine(path, resolve(path))
+    }
+
+    /// Like `check`, but a final symlink is not followed
+    ///
+    /// The returned path names the entry itself under its canonical parent,
+    /// so a link can be described without checking where it points.
+    pub fn check_entry(&self, path: &Path) -> Result<PathBuf, AppError> {
+        self.confine(path, resolve_entry(path))
+    }
+
+    /// Return `resolved` if it lies under one of the roots
+    fn confine(&self, path: &Path, resolved: Option<PathBuf>) -> Result<PathBuf, AppError> {
+        let denied = || {
+            AppError::Authorization(format!(
+                "Path is outside the allowed roots: {}",
+                path.display()
+            ))
+        };
+        let resolved = resolved.ok_or_else(denied)?;
+
+        let roots = self.roots.read().unwrap_or_else(|e| e.into_inner());
+        if roots.iter().any(|root| resolved.starts_with(root)) {
+            Ok(resolved)
+        } else {
+            Err(denied())
+        }
+    }
+
+    /// Fail with `Authorization` once the registry has been sealed
+    fn ensure_unsealed(&self) -> Result<(), AppError> {
+        if self.is_sealed() {
+            return Err(AppError::Authorization(
+                "Allowed roots can no longer be changed".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// `add`, refused once the registry has been sealed
+    pub(crate) fn try_add(&self, root: &Path) -> Result<PathBuf, AppError> {
+        self.ensure_unsealed()?;
+        self.add(root)
+    }
+
+    /// `remove`, refused once the registry has been sealed
+    pub(crate) fn try_remove(&self, root: &Path) -> Result<bool, AppError> {
+        self.ensure_unsealed()?;
+        Ok(self.remove(root))
+    }
+}
+
+/// Check whether file commands may access `path`
+#[tauri::command]
+pub fn path_is_allowed(roots: State<'_, AllowedRoots>, path: String) -> bool {
+    roots.is_allowed(Path::new(&path))
+}
+
+/// Add an allowed root; refused once setup has sealed the allowlist
+#[tauri::command]
+pub fn add_allowed_root(roots: State<'_, AllowedRoots>, path: String) -> Result<String, AppError> {
+    roots
+        .try_add(Path::new(&path))
+        .map(|root| root.to_string_lossy().into_owned())
+}
+
+/// Remove an allowed root; refused once setup has sealed the allowlist
+#[tauri::command]
+pub fn remove_allowed_root(roots: State<'_, AllowedRoots>, path: String) -> Result<bool, AppError> {
+    roots.try_remove(Path::new(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_allowed_path() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("data")).unwrap();
+        fs::write(dir.path().join("data").join("a.txt"), "x").unwrap();
+
+        let roots = AllowedRoots::new();
+        assert!(!roots.is_allowed(&dir.path().join("data").join("a.txt")));
+
+        roots.add(&dir.path().join("data")).unwrap();
+        assert!(roots.is_allowed(&dir.path().join("data").join("a.txt")));
+        // Files that don't exist yet are checked through their parent
+        assert!(roots.is_allowed(&dir.path().join("data").join("new.txt")));
+        assert!(!roots.is_allowed(dir.path()));
+
+        assert!(roots.remove(&dir.path().join("data")));
+        assert!(!roots.is_allowed(&dir.path().join("data").join("a.txt")));
+    }
+
+    #[test]
+    fn test_sibling_prefix_is_not_allowed() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("data")).unwrap();
+        fs::create_dir(dir.path().join("data-evil")).unwrap();
+        fs::write(dir.path().join("data-evil").join("x.txt"), "x").unwrap();
+
+        let roots = AllowedRoots::new();
+        roots.add(&dir.path().join("data")).unwrap();
+
+        let spoof = dir.path().join("data-evil").join("x.txt");
+        assert!(matches!(
+            roots.check(&spoof),
+            Err(AppError::Authorization(_))
+        ));
+        let dotted = dir.path().join("data").join("..").join("data-evil");
+        assert!(!roots.is_allowed(&dotted));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_escape_is_not_allowed() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("secret.txt"), "x").unwrap();
+        fs::create_dir(dir.path().join("data")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("data").join("link")).unwrap();
+
+        let roots = AllowedRoots::new();
+        roots.add(&dir.path().join("data")).unwrap();
+
+        let escaped = dir.path().join("data").join("link").join("secret.txt");
+        assert!(!roots.is_allowed(&escaped));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_entry_does_not_follow_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path().join("data");
+        fs::create_dir(&data).unwrap();
+        fs::write(data.join("target.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(data.join("target.txt"), data.join("link")).unwrap();
+
+        let roots = AllowedRoots::new();
+        let root = roots.add(&data).unwrap();
+        assert_eq!(
+            roots.check(&data.join("link")).unwrap(),
+            root.join("target.txt")
+        );
+        assert_eq!(
+            roots.check_entry(&data.join("link")).unwrap(),
+            root.join("link")
+        );
+        assert!(roots.check_entry(&dir.path().join("data-other")).is_err());
+    }
+
+    #[test]
+    fn test_sealed_roots_refuse_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("data")).unwrap();
+        let roots = AllowedRoots::new();
+
+        // Until sealed, the commands change the roots
+        let data = roots.try_add(&dir.path().join("data")).unwrap();
+        assert!(roots.is_allowed(&data.join("a.txt")));
+        assert!(roots.try_remove(&data).unwrap());
+        assert!(!roots.is_allowed(&data.join("a.txt")));
+
+        roots.seal();
+        assert!(matches!(
+            roots.try_add(&data),
+            Err(AppError::Authorization(_))
+        ));
+        assert!(matches!(
+            roots.try_remove(&data),
+            Err(AppError::Authorization(_))
+        ));
+        // Rust code keeps working after sealing
+        roots.add(dir.path()).unwrap();
+        assert!(roots.is_allowed(dir.path()));
+    }
+}
@@ -0,0 +1,462 @@
+//! Safe zip extraction for Tauri applications
+//!
+//! Archives come from untrusted sources, so every entry is checked before it is written:
+//! 1. Entry names with absolute paths, drive prefixes or `..` components are rejected,
+//!    and so are components that aren't valid file names on every platform
+//! 2. Every directory on an entry's path must be a real directory inside the
+//!    destination, so a symlink already there can't redirect a write (zip-slip)
+//! 3. Symlink entries are rejected, and existing files are never overwritten
+//! 4. Total uncompressed size and the compression ratio are capped before
+//!    extraction starts, and the bytes actually written are capped as well
+//! 5. Every entry gets a result; rejected entries don't stop the others
+
+use super::allowed_roots::AllowedRoots;
+use super::error::AppError;
+use super::memory_safe::BoundaryValidator;
+use super::window_caps::{require_capability, CapabilityToken};
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Manager, Window};
+use zip::ZipArchive;
+
+/// Largest total size an archive may extract to (1 GiB)
+pub const MAX_EXTRACTED_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Most entries an archive may contain
+pub const MAX_ARCHIVE_ENTRIES: usize = 10_000;
+
+/// Highest ratio of uncompressed size to archive size that is accepted
+pub const MAX_COMPRESSION_RATIO: u64 = 100;
+
+/// File type bits of a Unix mode that mark a symlink
+const UNIX_SYMLINK_MODE: u32 = 0o120000;
+
+/// Mask for the file type bits of a Unix mode
+const UNIX_FILE_TYPE_MASK: u32 = 0o170000;
+
+/// Limits applied by `extract_zip`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExtractLimits {
+    /// Largest total uncompressed size, declared or actual
+    pub max_total_bytes: u64,
+    /// Most entries in the archive
+    pub max_entries: usize,
+    /// Highest ratio of declared uncompressed size to archive size
+    pub max_ratio: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_total_bytes: MAX_EXTRACTED_BYTES,
+            max_entries: MAX_ARCHIVE_ENTRIES,
+            max_ratio: MAX_COMPRESSION_RATIO,
+        }
+    }
+}
+
+/// What happened to an archive entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStatus {
+    /// The file was written
+    Extracted,
+    /// The directory was created
+    Directory,
+    /// The entry was not written; see `reason`
+    Rejected,
+}
+
+/// The outcome of extracting one archive entry
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ArchiveEntryResult {
+    /// The entry's name as stored in the archive
+    pub name: String,
+    /// What happened to the entry
+    pub status: EntryStatus,
+    /// Where the entry was written, if it was
+    pub path: Option<String>,
+    /// Bytes written for the entry
+    pub bytes_written: u64,
+    /// Why the entry was rejected
+    pub reason: Option<String>,
+}
+
+impl ArchiveEntryResult {
+    fn rejected(name: &str, reason: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: EntryStatus::Rejected,
+            path: None,
+            bytes_written: 0,
+            reason: Some(reason.into()),
+        }
+    }
+}
+
+/// Turn an entry name into a relative path made only of normal components
+///
+/// Backslashes are treated as separators, since archives made on Windows
+/// may use them.
+fn entry_relative_path(name: &str) -> Result<PathBuf, String> {
+    if name.contains('\0') {
+        return Err("Entry name contains a NUL byte".into());
+    }
+
+    let name = name.replace('\\', "/");
+    let mut relative = PathBuf::new();
+    for component in Path::new(&name).components() {
+        match component {
+            Component::Normal(part) if part.to_string_lossy().contains(':') => {
+                return Err("Entry name contains a drive or stream separator".into());
+            }
+            Component::Normal(part) => {
+                BoundaryValidator::validate_filename(&part.to_string_lossy())
+                    .map_err(|e| format!("Entry name is not a valid file name: {}", e))?;
+                relative.push(part);
+            }
+            Component::CurDir => {}
+            Component::ParentDir => return Err("Entry name contains a `..` component".into()),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err("Entry name is an absolute path".into());
+            }
+        }
+    }
+    if relative.as_os_str().is_empty() {
+        return Err("Entry name is empty".into());
+    }
+    Ok(relative)
+}
+
+/// Create each directory of `relative` under `dest` in turn
+///
+/// Every step must be a real directory, not a symlink or a file, so the
+/// result can't resolve outside `dest` and nothing is created outside it.
+fn create_dirs_within(dest: &Path, relative: &Path) -> Result<(), String> {
+    let mut current = dest.to_path_buf();
+    for part in relative.components() {
+        current.push(part);
+        match fs::symlink_metadata(&current) {
+            Ok(metadata) if metadata.is_dir() => {}
+            Ok(_) => return Err("Entry path passes through a symlink or file".into()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => fs::create_dir(&current)
+                .map_err(|e| format!("Failed to create directory: {}", e))?,
+            Err(e) => return Err(format!("Failed to inspect directory: {}", e)),
+        }
+    }
+    Ok(())
+}
+
+/// Extract the zip archive at `archive_path` into the directory `dest_dir`
+///
+/// Fails as a whole when the archive can't be read or breaks `limits`;
+/// entries that are unsafe on their own are rejected individually.
+pub fn extract_zip(
+    archive_path: &Path,
+    dest_dir: &Path,
+    limits: ExtractLimits,
+) -> Result<Vec<ArchiveEntryResult>, AppError> {
+    let dest = dest_dir.canonicalize().map_err(|e| {
+        AppError::Validation(format!("Failed to resolve {}: {}", dest_dir.display(), e))
+    })?;
+    if !dest.is_dir() {
+        return Err(AppError::Validation(format!(
+            "Not a directory: {}",
+            dest_dir.display()
+        )));
+    }
+
+    let file = File::open(archive_path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => AppError::NotFound(archive_path.display().to_string()),
+        _ => AppError::Io(format!("Failed to open {}: {}", archive_path.display(), e)),
+    })?;
+    let archive_len = file
+        .metadata()
+        .map_err(|e| AppError::Io(format!("Failed to read metadata: {}", e)))?
+        .len();
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| AppError::Validation(format!("Invalid zip archive: {}", e)))?;
+
+    if archive.len() > limits.max_entries {
+        return Err(AppError::Validation(format!(
+            "Archive has {} entries; at most {} are allowed",
+            archive.len(),
+            limits.max_entries
+        )));
+    }
+
+    // Declared sizes can lie, but checking them first stops honest bombs cheaply
+    let mut declared: u64 = 0;
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index(index)
+            .map_err(|e| AppError::Validation(format!("Invalid zip entry: {}", e)))?;
+        declared = declared.saturating_add(entry.size());
+    }
+    if declared > limits.max_total_bytes {
+        return Err(AppError::Validation(format!(
+            "Archive expands to {} bytes; at most {} are allowed",
+            declared, limits.max_total_bytes
+        )));
+    }
+    if declared > archive_len.max(1).saturating_mul(limits.max_ratio) {
+        return Err(AppError::Validation(format!(
+            "Archive compression ratio exceeds {}:1",
+            limits.max_ratio
+        )));
+    }
+
+    let mut written_total: u64 = 0;
+    let mut results = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| AppError::Validation(format!("Invalid zip entry: {}", e)))?;
+        let name = entry.name().to_string();
+
+        let relative = match entry_relative_path(&name) {
+            Ok(relative) => relative,
+            Err(reason) => {
+                results.push(ArchiveEntryResult::rejected(&name, reason));
+                continue;
+            }
+        };
+        if entry
+            .unix_mode()
+            .is_some_and(|mode| mode & UNIX_FILE_TYPE_MASK == UNIX_SYMLINK_MODE)
+        {
+            results.push(ArchiveEntryResult::rejected(
+                &name,
+                "Symlink entries are not extracted",
+            ));
+            continue;
+        }
+
+        let target = dest.join(&relative);
+        if entry.is_dir() {
+            results.push(match create_dirs_within(&dest, &relative) {
+                Ok(()) => ArchiveEntryResult {
+                    name,
+                    status: EntryStatus::Directory,
+                    path: Some(target.to_string_lossy().into_owned()),
+                    bytes_written: 0,
+                    reason: None,
+                },
+                Err(reason) => ArchiveEntryResult::rejected(&name, reason),
+            });
+            continue;
+        }
+
+        let parent = relative.parent().unwrap_or(Path::new(""));
+        if let Err(reason) = create_dirs_within(&dest, parent) {
+            results.push(ArchiveEntryResult::rejected(&name, reason));
+            continue;
+        }
+        // `create_new` also refuses to follow a symlink at the target itself
+        let mut output = match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&target)
+        {
+            Ok(output) => output,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                results.push(ArchiveEntryResult::rejected(&name, "File already exists"));
+                continue;
+            }
+            Err(e) => {
+                results.push(ArchiveEntryResult::rejected(
+                    &name,
+                    format!("Failed to create file: {}", e),
+                ));
+                continue;
+            }
+        };
+
+        // Read one byte past the remaining budget to detect sizes that lied
+        let remaining = limits.max_total_bytes - written_total;
+        let copied = io::copy(&mut (&mut entry).take(remaining + 1), &mut output);
+        drop(output);
+        match copied {
+            Ok(written) if written > remaining => {
+                let _ = fs::remove_file(&target);
+                return Err(AppError::Validation(format!(
+                    "Archive expands past {} bytes",
+                    limits.max_total_bytes
+                )));
+            }
+            Ok(written) => {
+                written_total += written;
+                results.push(ArchiveEntryResult {
+                    name,
+                    status: EntryStatus::Extracted,
+                    path: Some(target.to_string_lossy().into_owned()),
+                    bytes_written: written,
+                    reason: None,
+                });
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&target);
+                results.push(ArchiveEntryResult::rejected(
+                    &name,
+                    format!("Failed to extract: {}", e),
+                ));
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// Extract a zip archive into a directory, both within the allowed roots
+#[tauri::command]
+pub async fn extract_archive(
+    app: AppHandle,
+    window: Window,
+    archive_path: String,
+    dest_dir: String,
+) -> Result<Vec<ArchiveEntryResult>, AppError> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let roots = app.state::<AllowedRoots>();
+        let archive = roots.check(Path::new(&archive_path))?;
+        let dest = roots.check(Path::new(&dest_dir))?;
+        extract_zip(&archive, &dest, ExtractLimits::default())
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Extraction failed: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, data) in entries {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_benign_archive_is_extracted() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("benign.zip");
+        let dest = dir.path().join("out");
+        fs::create_dir(&dest).unwrap();
+        write_zip(
+            &archive,
+            &[("readme.txt", b"hello"), ("docs/./guide.md", b"# Guide")],
+        );
+
+        let results = extract_zip(&archive, &dest, ExtractLimits::default()).unwrap();
+        assert!(results.iter().all(|r| r.status == EntryStatus::Extracted));
+        assert_eq!(results[0].bytes_written, 5);
+        assert_eq!(fs::read(dest.join("readme.txt")).unwrap(), b"hello");
+        assert_eq!(
+            fs::read(dest.join("docs").join("guide.md")).unwrap(),
+            b"# Guide"
+        );
+
+        // A second extraction never overwrites what is there
+        let again = extract_zip(&archive, &dest, ExtractLimits::default()).unwrap();
+        assert!(again.iter().all(|r| r.status == EntryStatus::Rejected));
+    }
+
+    #[test]
+    fn test_zip_slip_entries_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("slip.zip");
+        let dest = dir.path().join("out");
+        fs::create_dir(&dest).unwrap();
+        write_zip(
+            &archive,
+            &[
+                ("../evil.txt", b"owned"),
+                ("nested/../../evil.txt", b"owned"),
+                ("/tmp/absolute.txt", b"owned"),
+                ("..\\windows.txt", b"owned"),
+                ("safe.txt", b"fine"),
+            ],
+        );
+
+        let results = extract_zip(&archive, &dest, ExtractLimits::default()).unwrap();
+        let statuses: Vec<_> = results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                EntryStatus::Rejected,
+                EntryStatus::Rejected,
+                EntryStatus::Rejected,
+                EntryStatus::Rejected,
+                EntryStatus::Extracted,
+            ]
+        );
+        assert!(!dir.path().join("evil.txt").exists());
+        assert!(!dir.path().join("windows.txt").exists());
+        assert_eq!(fs::read(dest.join("safe.txt")).unwrap(), b"fine");
+    }
+
+    #[test]
+    fn test_unportable_entry_names_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("names.zip");
+        let dest = dir.path().join("out");
+        fs::create_dir(&dest).unwrap();
+        write_zip(
+            &archive,
+            &[
+                ("CON", b"device"),
+                ("docs/aux.txt", b"device"),
+                ("notes.txt.", b"trailing dot"),
+                ("docs/report.txt", b"fine"),
+            ],
+        );
+
+        let results = extract_zip(&archive, &dest, ExtractLimits::default()).unwrap();
+        let statuses: Vec<_> = results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                EntryStatus::Rejected,
+                EntryStatus::Rejected,
+                EntryStatus::Rejected,
+                EntryStatus::Extracted,
+            ]
+        );
+        assert_eq!(fs::read_dir(&dest).unwrap().count(), 1);
+        assert_eq!(
+            fs::read(dest.join("docs").join("report.txt")).unwrap(),
+            b"fine"
+        );
+    }
+
+    #[test]
+    fn test_zip_bomb_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("bomb.zip");
+        let dest = dir.path().join("out");
+        fs::create_dir(&dest).unwrap();
+        let zeros = vec![0u8; 8 * 1024 * 1024];
+        write_zip(&archive, &[("zeros.bin", &zeros)]);
+
+        assert!(matches!(
+            extract_zip(&archive, &dest, ExtractLimits::default()),
+            Err(AppError::Validation(_))
+        ));
+        let small_cap = ExtractLimits {
+            max_total_bytes: 1024,
+            max_ratio: u64::MAX,
+            ..ExtractLimits::default()
+        };
+        assert!(extract_zip(&archive, &dest, small_cap).is_err());
+        assert_eq!(fs::read_dir(&dest).unwrap().count(), 0);
+    }
+}
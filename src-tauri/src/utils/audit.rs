@@ -0,0 +1,210 @@
+//! Audit logging for security-relevant command invocations
+//!
+//! This module records which validation commands ran and whether they passed:
+//! 1. Timestamped, structured entries written as JSON lines
+//! 2. Redaction of sensitive arguments via `SecureString`
+//! 3. Size-bounded log files with rotation
+
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default maximum size of the active audit log before it is rotated (1 MiB)
+pub const DEFAULT_MAX_BYTES: u64 = 1024 * 1024;
+
+/// Default number of rotated audit log files to keep
+pub const DEFAULT_MAX_BACKUPS: usize = 3;
+
+/// The result of a command invocation as recorded in the audit log
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// The command accepted its input
+    Accepted,
+    /// The command rejected its input
+    Rejected { reason: String },
+}
+
+/// A single structured audit log entry
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    /// Name of the command that was invoked
+    pub command: String,
+    /// Whether the command accepted or rejected its input
+    pub outcome: AuditOutcome,
+    /// Command arguments, with sensitive values already redacted
+    pub redacted_args: Vec<String>,
+}
+
+/// An append-only audit log backed by a rolling file
+pub struct AuditLog {
+    /// Path of the active log file
+    path: PathBuf,
+
+    /// Maximum size of the active log file before rotation
+    max_bytes: u64,
+
+    /// Number of rotated files to keep (`audit.log.1`, `audit.log.2`, ...)
+    max_backups: usize,
+
+    /// Serializes writes and rotations
+    lock: Mutex<()>,
+}
+
+impl AuditLog {
+    /// Create a new audit log writing to `path`
+    pub fn new(path: impl Into<PathBuf>, max_bytes: u64, max_backups: usize) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes,
+            max_backups,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Get the path of the active log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record a command invocation
+    ///
+    /// Callers are responsible for passing only redacted arguments; wrap any
+    /// secret in a `SecureString` and pass its `Display` output.
+    /// Failures to write are logged rather than propagated so that auditing
+    /// never changes the result of the command being audited.
+    pub fn record(&self, command: &str, outcome: AuditOutcome, redacted_args: Vec<String>) {
+        let entry = AuditEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            command: command.to_string(),
+            outcome,
+            redacted_args,
+        };
+
+        if let Err(e) = self.write_entry(&entry) {
+            error!("Failed to write audit log entry: {}", e);
+        }
+    }
+
+    /// Serialize and append an entry, rotating the file first if needed
+    fn write_entry(&self, entry: &AuditEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+
+        let current_len = fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if current_len > 0 && current_len + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(line.as_bytes())
+    }
+
+    /// Shift `audit.log.N-1` to `audit.log.N`, dropping the oldest file
+    fn rotate(&self) -> io::Result<()> {
+        if self.max_backups == 0 {
+            return fs::remove_file(&self.path);
+        }
+
+        for index in (1..self.max_backups).rev() {
+            let from = self.backup_path(index);
+            if from.exists() {
+                fs::rename(&from, self.backup_path(index + 1))?;
+            }
+        }
+
+        fs::rename(&self.path, self.backup_path(1))
+    }
+
+    /// Path of the `index`-th rotated file
+    fn backup_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::memory_safe::{process_path, process_sensitive_data};
+
+    fn read_entries(path: &Path) -> Vec<AuditEntry> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_entries_written_on_accept_and_reject() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = AuditLog::new(dir.path().join("audit.log"), DEFAULT_MAX_BYTES, 1);
+
+        assert!(process_path(&audit, "documents/report.txt".into()).is_ok());
+        assert!(process_path(&audit, "../../../etc/passwd".into()).is_err());
+        assert!(process_sensitive_data(&audit, "hunter2".into()).is_ok());
+        assert!(process_sensitive_data(&audit, "<script>hunter2".into()).is_err());
+
+        let entries = read_entries(audit.path());
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].command, "validate_and_process_path");
+        assert_eq!(entries[0].outcome, AuditOutcome::Accepted);
+        assert!(matches!(entries[1].outcome, AuditOutcome::Rejected { .. }));
+        assert_eq!(entries[2].command, "handle_sensitive_data");
+        assert_eq!(entries[2].outcome, AuditOutcome::Accepted);
+        assert!(matches!(
+            &entries[3].outcome,
+            AuditOutcome::Rejected { reason } if reason.starts_with("Script injection")
+        ));
+    }
+
+    #[test]
+    fn test_sensitive_args_are_redacted() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = AuditLog::new(dir.path().join("audit.log"), DEFAULT_MAX_BYTES, 1);
+
+        let _ = process_sensitive_data(&audit, "CorrectHorseBatteryStaple".into());
+        let _ = process_sensitive_data(&audit, "javascript:CorrectHorse".into());
+
+        let contents = fs::read_to_string(audit.path()).unwrap();
+        assert!(!contents.contains("CorrectHorse"));
+        assert!(contents.contains("***REDACTED***"));
+    }
+
+    #[test]
+    fn test_rotation_on_max_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = AuditLog::new(dir.path().join("audit.log"), 200, 2);
+
+        for _ in 0..10 {
+            audit.record("greet", AuditOutcome::Accepted, vec!["x".repeat(50)]);
+        }
+
+        assert!(fs::metadata(audit.path()).unwrap().len() <= 200);
+        assert!(audit.backup_path(1).exists());
+        assert!(audit.backup_path(2).exists());
+        assert!(!audit.backup_path(3).exists());
+    }
+}
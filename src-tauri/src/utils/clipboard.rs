@@ -0,0 +1,322 @@
+//! Secure clipboard handling for Tauri applications
+//!
+//! Secrets copied to the clipboard should not linger there:
+//! 1. The secret is held in a `SecureString` and zeroed once cleared
+//! 2. The clipboard is cleared after a timeout between `MIN_CLEAR_AFTER_MS`
+//!    and `MAX_CLEAR_AFTER_MS`
+//! 3. The clipboard is only cleared if it still holds our secret
+//!
+//! Ordinary writes go through `set_clipboard`, which caps their size and
+//! encodes HTML so pasting it can't inject markup. HTML is also stripped of
+//! control characters and bidirectional overrides first. Both commands fail
+//! with `Unavailable` if the clipboard plugin didn't start.
+
+use super::degraded::{DegradedFeatures, CLIPBOARD_FEATURE};
+use super::error::AppError;
+use super::memory_safe::{SanitizeContext, SecureString};
+use super::sanitize::{Encode, NormalizeUnicode, SanitizerPipeline, StripControl};
+use constant_time_eq::constant_time_eq;
+use log::{error, info};
+use serde::Deserialize;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+/// Shortest delay before a copied secret is cleared (1 second)
+pub const MIN_CLEAR_AFTER_MS: u64 = 1000;
+
+/// Longest delay before a copied secret is cleared (5 minutes)
+pub const MAX_CLEAR_AFTER_MS: u64 = 5 * 60 * 1000;
+
+/// Minimal clipboard interface so the clearing logic can be tested
+pub trait Clipboard {
+    /// Read the current clipboard text, if any
+    fn read_text(&self) -> Result<Option<String>, String>;
+
+    /// Replace the clipboard contents with `text`
+    fn write_text(&self, text: &str) -> Result<(), String>;
+
+    /// Replace the clipboard contents with `html`, and `alt_text` for plain-text targets
+    fn write_html(&self, html: &str, alt_text: &str) -> Result<(), String>;
+
+    /// Remove the clipboard contents
+    fn clear(&self) -> Result<(), String>;
+}
+
+impl<R: Runtime> Clipboard for AppHandle<R> {
+    fn read_text(&self) -> Result<Option<String>, String> {
+        // The plugin reports an empty or non-text clipboard as an error
+        Ok(self.clipboard().read_text().ok())
+    }
+
+    fn write_text(&self, text: &str) -> Result<(), String> {
+        self.clipboard()
+            .write_text(text)
+            .map_err(|e| format!("Failed to write clipboard: {}", e))
+    }
+
+    fn write_html(&self, html: &str, alt_text: &str) -> Result<(), String> {
+        self.clipboard()
+            .write_html(html, Some(alt_text))
+            .map_err(|e| format!("Failed to write clipboard: {}", e))
+    }
+
+    fn clear(&self) -> Result<(), String> {
+        self.clipboard()
+            .clear()
+            .map_err(|e| format!("Failed to clear clipboard: {}", e))
+    }
+}
+
+/// Clear the clipboard only if it still contains `expected`
+///
+/// Returns whether the clipboard was cleared. If the user copied something
+/// else in the meantime, it is left untouched.
+pub fn clear_if_unchanged<C: Clipboard + ?Sized>(
+    clipboard: &C,
+    expected: &SecureString,
+) -> Result<bool, String> {
+    let current = match clipboard.read_text()? {
+        Some(text) => SecureString::new(text),
+        None => return Ok(false),
+    };
+
+    if !constant_time_eq(current.as_str().as_bytes(), expected.as_str().as_bytes()) {
+        return Ok(false);
+    }
+
+    clipboard.clear()?;
+    Ok(true)
+}
+
+/// How `set_clipboard` should place content on the clipboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardFormat {
+    /// Plain text, written as is
+    Text,
+    /// HTML, encoded with `SanitizeContext::Html` before it is written
+    Html,
+}
+
+/// Write `content` to `clipboard` in `format`, refusing payloads over `max_bytes`
+///
+/// The limit applies to what is written, so HTML is measured after encoding.
+pub fn write_clipboard<C: Clipboard + ?Sized>(
+    clipboard: &C,
+    content: &str,
+    format: ClipboardFormat,
+    max_bytes: usize,
+) -> Result<(), String> {
+    let too_large = |len: usize| {
+        format!(
+            "Clipboard content is {} bytes; the limit is {}",
+            len, max_bytes
+        )
+    };
+
+    match format {
+        ClipboardFormat::Text => {
+            if content.len() > max_bytes {
+                return Err(too_large(content.len()));
+            }
+            clipboard.write_text(content)
+        }
+        ClipboardFormat::Html => {
+            let html = SanitizerPipeline::new()
+                .stage(NormalizeUnicode)
+                .stage(StripControl)
+                .stage(Encode(SanitizeContext::Html))
+                .apply(content.to_string());
+            if html.len() > max_bytes {
+                return Err(too_large(html.len()));
+            }
+            clipboard.write_html(&html, content)
+        }
+    }
+}
+
+/// Write text or HTML to the clipboard, up to `max_bytes`
+#[tauri::command]
+pub fn set_clipboard(
+    app: AppHandle,
+    content: String,
+    format: ClipboardFormat,
+    max_bytes: usize,
+) -> Result<(), String> {
+    app.state::<DegradedFeatures>()
+        .ensure_available(CLIPBOARD_FEATURE)?;
+    write_clipboard(&app, &content, format, max_bytes)
+}
+
+/// Check that `clear_after_ms` lies between the minimum and maximum delays
+pub(crate) fn clear_delay(clear_after_ms: u64) -> Result<Duration, AppError> {
+    if !(MIN_CLEAR_AFTER_MS..=MAX_CLEAR_AFTER_MS).contains(&clear_after_ms) {
+        return Err(AppError::Validation(format!(
+            "Clipboard clear delay must be between {} and {} ms",
+            MIN_CLEAR_AFTER_MS, MAX_CLEAR_AFTER_MS
+        )));
+    }
+    Ok(Duration::from_millis(clear_after_ms))
+}
+
+/// Copy a secret to the clipboard and clear it again after `clear_after_ms`
+#[tauri::command]
+pub fn copy_secret_to_clipboard(
+    app: AppHandle,
+    secret: String,
+    clear_after_ms: u64,
+) -> Result<(), String> {
+    let mut secret = SecureString::new(secret);
+    let checked = app
+        .state::<DegradedFeatures>()
+        .ensure_available(CLIPBOARD_FEATURE)
+        .and_then(|_| clear_delay(clear_after_ms));
+    let delay = match checked {
+        Ok(delay) => delay,
+        Err(e) => {
+            secret.clear();
+            return Err(e.into());
+        }
+    };
+    if let Err(e) = app.write_text(secret.as_str()) {
+        secret.clear();
+        return Err(e);
+    }
+
+    thread::spawn(move || {
+        thread::sleep(delay);
+
+        match clear_if_unchanged(&app, &secret) {
+            Ok(true) => info!("Cleared secret from clipboard"),
+            Ok(false) => info!("Clipboard changed since copy; leaving it untouched"),
+            Err(e) => error!("Failed to clear secret from clipboard: {}", e),
+        }
+
+        secret.clear();
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockClipboard {
+        contents: Mutex<Option<String>>,
+        html: Mutex<Option<String>>,
+    }
+
+    impl Clipboard for MockClipboard {
+        fn read_text(&self) -> Result<Option<String>, String> {
+            Ok(self.contents.lock().unwrap().clone())
+        }
+
+        fn write_text(&self, text: &str) -> Result<(), String> {
+            *self.contents.lock().unwrap() = Some(text.to_string());
+            Ok(())
+        }
+
+        fn write_html(&self, html: &str, alt_text: &str) -> Result<(), String> {
+            *self.html.lock().unwrap() = Some(html.to_string());
+            self.write_text(alt_text)
+        }
+
+        fn clear(&self) -> Result<(), String> {
+            *self.contents.lock().unwrap() = None;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_clears_when_unchanged() {
+        let clipboard = MockClipboard::default();
+        let secret = SecureString::new("hunter2");
+        clipboard.write_text(secret.as_str()).unwrap();
+
+        assert!(clear_if_unchanged(&clipboard, &secret).unwrap());
+        assert_eq!(clipboard.read_text().unwrap(), None);
+    }
+
+    #[test]
+    fn test_does_not_clobber_new_contents() {
+        let clipboard = MockClipboard::default();
+        let secret = SecureString::new("hunter2");
+        clipboard.write_text(secret.as_str()).unwrap();
+        clipboard.write_text("something the user copied").unwrap();
+
+        assert!(!clear_if_unchanged(&clipboard, &secret).unwrap());
+        assert_eq!(
+            clipboard.read_text().unwrap().as_deref(),
+            Some("something the user copied")
+        );
+    }
+
+    #[test]
+    fn test_empty_clipboard_is_left_alone() {
+        let clipboard = MockClipboard::default();
+        let secret = SecureString::new("hunter2");
+
+        assert!(!clear_if_unchanged(&clipboard, &secret).unwrap());
+    }
+
+    #[test]
+    fn test_clear_delay_is_bounded() {
+        assert_eq!(clear_delay(30_000).unwrap(), Duration::from_millis(30_000));
+        assert!(clear_delay(MIN_CLEAR_AFTER_MS).is_ok());
+        assert!(clear_delay(MAX_CLEAR_AFTER_MS).is_ok());
+        for ms in [0, MIN_CLEAR_AFTER_MS - 1, MAX_CLEAR_AFTER_MS + 1, u64::MAX] {
+            assert!(matches!(clear_delay(ms), Err(AppError::Validation(_))));
+        }
+    }
+
+    #[test]
+    fn test_text_is_written_as_is() {
+        let clipboard = MockClipboard::default();
+        write_clipboard(&clipboard, "a < b & c", ClipboardFormat::Text, 64).unwrap();
+
+        assert_eq!(clipboard.read_text().unwrap().as_deref(), Some("a < b & c"));
+        assert_eq!(*clipboard.html.lock().unwrap(), None);
+    }
+
+    #[test]
+    fn test_html_is_sanitized() {
+        let clipboard = MockClipboard::default();
+        let content = "<img src=x onerror=alert(1)>";
+        write_clipboard(&clipboard, content, ClipboardFormat::Html, 1024).unwrap();
+
+        assert_eq!(
+            clipboard.html.lock().unwrap().as_deref(),
+            Some("&lt;img src=x onerror=alert(1)&gt;")
+        );
+        assert_eq!(clipboard.read_text().unwrap().as_deref(), Some(content));
+
+        // A right-to-left override can't disguise the pasted text
+        write_clipboard(
+            &clipboard,
+            "invoice\u{202E}fdp.exe",
+            ClipboardFormat::Html,
+            64,
+        )
+        .unwrap();
+        assert_eq!(
+            clipboard.html.lock().unwrap().as_deref(),
+            Some("invoicefdp.exe")
+        );
+    }
+
+    #[test]
+    fn test_oversized_content_is_rejected() {
+        let clipboard = MockClipboard::default();
+        assert!(write_clipboard(&clipboard, "12345", ClipboardFormat::Text, 4).is_err());
+
+        // Encoding `<` and `>` grows this past the limit
+        let result = write_clipboard(&clipboard, "<b>", ClipboardFormat::Html, 8);
+        assert!(result.unwrap_err().contains("limit is 8"));
+        assert_eq!(clipboard.read_text().unwrap(), None);
+    }
+}
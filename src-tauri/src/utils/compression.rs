@@ -0,0 +1,302 @@
+//! Streaming file compression for Tauri applications
+//!
+//! Files are compressed and decompressed without loading them into memory:
+//! 1. Data streams through `flate2` (gzip) or `zstd` encoders and decoders
+//! 2. Levels outside an algorithm's range are rejected up front
+//! 3. Output is written to a temporary file and renamed into place, so a
+//!    truncated or corrupt input never leaves partial output behind
+//! 4. Decompressed output is capped, so a small bomb can't fill the disk
+//! 5. The output must be named like any new file, see `resolve_new_in_roots`
+
+use super::allowed_roots::AllowedRoots;
+use super::audit::AuditLog;
+use super::error::AppError;
+use super::file_ops::{resolve_new_in_roots, temp_path_for};
+use super::guard::CommandGuard;
+use super::window_caps::{require_capability, CapabilityToken};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use tauri::{AppHandle, Manager, Window};
+
+/// Largest output `decompress_file` may produce (4 GiB)
+pub const MAX_DECOMPRESSED_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// A supported compression format
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionAlgo {
+    /// gzip, as produced by `gzip` and most HTTP servers
+    Gzip,
+    /// Zstandard
+    Zstd,
+}
+
+impl CompressionAlgo {
+    /// Levels accepted for this algorithm
+    pub fn levels(self) -> RangeInclusive<i32> {
+        match self {
+            Self::Gzip => 0..=9,
+            Self::Zstd => zstd::compression_level_range(),
+        }
+    }
+}
+
+/// Sizes reported by `compress_file` and `decompress_file`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct CompressionReport {
+    /// Size of the uncompressed data in bytes
+    pub original_bytes: u64,
+    /// Size of the compressed data in bytes
+    pub compressed_bytes: u64,
+}
+
+/// Run `fill` on a new temporary file next to `dest`, then rename it into place
+///
+/// The temporary file is removed if `fill` fails, so `dest` is either the
+/// complete output or untouched.
+fn write_via_temp<F>(dest: &Path, fill: F) -> Result<u64, AppError>
+where
+    F: FnOnce(File) -> Result<u64, AppError>,
+{
+    let temp = temp_path_for(dest);
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp)
+        .map_err(|e| AppError::io("create", &temp, e))?;
+
+    let written = fill(file).and_then(|written| {
+        fs::rename(&temp, dest)
+            .map(|_| written)
+            .map_err(|e| AppError::io("replace", dest, e))
+    });
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
+
+/// Compress `src` into `dest` at `level`
+pub(crate) fn compress_path(
+    src: &Path,
+    dest: &Path,
+    algorithm: CompressionAlgo,
+    level: i32,
+) -> Result<CompressionReport, AppError> {
+    if !algorithm.levels().contains(&level) {
+        let levels = algorithm.levels();
+        return Err(AppError::Validation(format!(
+            "Level {} is invalid for {:?}; use {} to {}",
+            level,
+            algorithm,
+            levels.start(),
+            levels.end()
+        )));
+    }
+
+    let mut input = File::open(src).map_err(|e| AppError::io("open", src, e))?;
+    let mut original_bytes = 0;
+    let compressed_bytes = write_via_temp(dest, |file| {
+        let compress_error = |e: io::Error| AppError::io("compress", src, e);
+        let file = match algorithm {
+            CompressionAlgo::Gzip => {
+                let mut encoder = GzEncoder::new(file, Compression::new(level as u32));
+                original_bytes = io::copy(&mut input, &mut encoder).map_err(compress_error)?;
+                encoder.finish().map_err(compress_error)?
+            }
+            CompressionAlgo::Zstd => {
+                let mut encoder = zstd::Encoder::new(file, level).map_err(compress_error)?;
+                original_bytes = io::copy(&mut input, &mut encoder).map_err(compress_error)?;
+                encoder.finish().map_err(compress_error)?
+            }
+        };
+        file.sync_all().map_err(compress_error)?;
+        file.metadata()
+            .map(|metadata| metadata.len())
+            .map_err(compress_error)
+    })?;
+
+    Ok(CompressionReport {
+        original_bytes,
+        compressed_bytes,
+    })
+}
+
+/// Decompress `src` into `dest`, producing at most `max_output` bytes
+pub(crate) fn decompress_path(
+    src: &Path,
+    dest: &Path,
+    algorithm: CompressionAlgo,
+    max_output: u64,
+) -> Result<CompressionReport, AppError> {
+    let input = File::open(src).map_err(|e| AppError::io("open", src, e))?;
+    let compressed_bytes = input
+        .metadata()
+        .map_err(|e| AppError::io("read metadata for", src, e))?
+        .len();
+    let mut decoder: Box<dyn Read> = match algorithm {
+        CompressionAlgo::Gzip => Box::new(MultiGzDecoder::new(input)),
+        CompressionAlgo::Zstd => {
+            Box::new(zstd::Decoder::new(input).map_err(|e| AppError::io("decompress", src, e))?)
+        }
+    };
+
+    let original_bytes = write_via_temp(dest, |mut file| {
+        let decompress_error = |e: io::Error| {
+            AppError::Validation(format!("Failed to decompress {}: {}", src.display(), e))
+        };
+        // Read one byte past the cap to tell "exactly at" from "over"
+        let written = io::copy(&mut decoder.by_ref().take(max_output + 1), &mut file)
+            .map_err(decompress_error)?;
+        if written > max_output {
+            return Err(AppError::Validation(format!(
+                "Decompressed data exceeds {} bytes",
+                max_output
+            )));
+        }
+        file.flush()
+            .and_then(|_| file.sync_all())
+            .map_err(|e| AppError::io("write", dest, e))?;
+        Ok(written)
+    })?;
+
+    Ok(CompressionReport {
+        original_bytes,
+        compressed_bytes,
+    })
+}
+
+/// Compress a file within the allowed roots
+#[tauri::command]
+pub async fn compress_file(
+    app: AppHandle,
+    window: Window,
+    src: String,
+    dest: String,
+    algorithm: CompressionAlgo,
+    level: i32,
+) -> Result<CompressionReport, AppError> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let roots = app.state::<AllowedRoots>();
+        let audit = app.state::<AuditLog>();
+        let guard = CommandGuard::new(&audit, "compress_file");
+        let src = guard.confined_path(&src, &roots)?;
+        let dest = guard.resolved_path(&dest, |path| {
+            resolve_new_in_roots(path, roots.inner())
+                .map_err(|e| AppError::Validation(e.to_string()))
+        })?;
+        compress_path(&src, &dest, algorithm, level)
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Compression failed: {}", e)))?
+}
+
+/// Decompress a file within the allowed roots
+#[tauri::command]
+pub async fn decompress_file(
+    app: AppHandle,
+    window: Window,
+    src: String,
+    dest: String,
+    algorithm: CompressionAlgo,
+) -> Result<CompressionReport, AppError> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let roots = app.state::<AllowedRoots>();
+        let audit = app.state::<AuditLog>();
+        let guard = CommandGuard::new(&audit, "decompress_file");
+        let src = guard.confined_path(&src, &roots)?;
+        let dest = guard.resolved_path(&dest, |path| {
+            resolve_new_in_roots(path, roots.inner())
+                .map_err(|e| AppError::Validation(e.to_string()))
+        })?;
+        decompress_path(&src, &dest, algorithm, MAX_DECOMPRESSED_BYTES)
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Decompression failed: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compressible text large enough to span several encoder buffers
+    fn sample() -> Vec<u8> {
+        (0..20_000)
+            .flat_map(|i| format!("line {} of the sample\n", i % 97).into_bytes())
+            .collect()
+    }
+
+    fn round_trip(algorithm: CompressionAlgo, level: i32) {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = dir.path().join("data.txt");
+        let packed = dir.path().join("data.txt.z");
+        let unpacked = dir.path().join("data.out");
+        fs::write(&plain, sample()).unwrap();
+
+        let packed_report = compress_path(&plain, &packed, algorithm, level).unwrap();
+        assert_eq!(packed_report.original_bytes, sample().len() as u64);
+        assert_eq!(
+            packed_report.compressed_bytes,
+            fs::metadata(&packed).unwrap().len()
+        );
+        assert!(packed_report.compressed_bytes < packed_report.original_bytes / 4);
+
+        let unpacked_report =
+            decompress_path(&packed, &unpacked, algorithm, MAX_DECOMPRESSED_BYTES).unwrap();
+        assert_eq!(unpacked_report, packed_report);
+        assert_eq!(fs::read(&unpacked).unwrap(), sample());
+
+        let level = algorithm.levels().end() + 1;
+        assert!(matches!(
+            compress_path(&plain, &packed, algorithm, level),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_gzip_round_trip() {
+        round_trip(CompressionAlgo::Gzip, 6);
+    }
+
+    #[test]
+    fn test_zstd_round_trip() {
+        round_trip(CompressionAlgo::Zstd, 3);
+    }
+
+    #[test]
+    fn test_corrupt_input_leaves_nothing_behind() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = dir.path().join("data.txt");
+        fs::write(&plain, sample()).unwrap();
+
+        for algorithm in [CompressionAlgo::Gzip, CompressionAlgo::Zstd] {
+            let packed = dir.path().join("data.z");
+            let unpacked = dir.path().join("data.out");
+            compress_path(&plain, &packed, algorithm, 1).unwrap();
+            let bytes = fs::read(&packed).unwrap();
+            fs::write(&packed, &bytes[..bytes.len() / 2]).unwrap();
+
+            let result = decompress_path(&packed, &unpacked, algorithm, MAX_DECOMPRESSED_BYTES);
+            assert!(
+                matches!(result, Err(AppError::Validation(_))),
+                "{:?}",
+                algorithm
+            );
+            assert!(!unpacked.exists());
+            fs::remove_file(&packed).unwrap();
+        }
+
+        // Only the original is left; no temporary files linger
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,270 @@
+//! Configuration file utilities for Tauri applications
+//!
+//! This module reads JSON or TOML configuration files safely:
+//! 1. `${VAR}` and `${VAR:-default}` references are interpolated from the environment
+//! 2. References to sensitive variables are rejected unless allowlisted
+//! 3. Undefined variables without a default are reported as errors
+//! 4. Only variables in the app config's `allowed_env_vars` are read, through
+//!    the same lookup as `read_env_allowed`
+
+use super::allowed_roots::AllowedRoots;
+use super::app_config::ConfigState;
+use super::audit::AuditLog;
+use super::guard::CommandGuard;
+use super::secrets::allowed_env_lookup;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tauri::State;
+
+/// Maximum size of a configuration file we are willing to read (1 MiB)
+const MAX_CONFIG_SIZE: u64 = 1024 * 1024;
+
+/// Fragments of variable names that usually hold secrets
+const SENSITIVE_VAR_MARKERS: [&str; 8] = [
+    "SECRET",
+    "TOKEN",
+    "PASSWORD",
+    "PASSWD",
+    "API_KEY",
+    "PRIVATE_KEY",
+    "CREDENTIAL",
+    "AWS_",
+];
+
+/// Sensitive-looking variables that configuration files may still reference
+pub const ALLOWED_SENSITIVE_VARS: &[&str] = &[];
+
+/// Check whether a variable name looks like it holds a secret
+fn is_sensitive_var(name: &str) -> bool {
+    let upper = name.to_uppercase();
+    SENSITIVE_VAR_MARKERS
+        .iter()
+        .any(|marker| upper.contains(marker))
+}
+
+/// Check that a variable name is `[A-Za-z_][A-Za-z0-9_]*`
+fn is_valid_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Interpolate `${VAR}` and `${VAR:-default}` references in a string
+///
+/// `lookup` resolves variable names and may refuse them; an empty value counts
+/// as unset so the default applies, matching shell semantics.
+pub fn interpolate_str<F>(input: &str, lookup: &F, allowlist: &[&str]) -> Result<String, String>
+where
+    F: Fn(&str) -> Result<Option<String>, String>,
+{
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("Unterminated variable reference in {:?}", input))?;
+        let reference = &after[..end];
+
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (reference, None),
+        };
+
+        if !is_valid_var_name(name) {
+            return Err(format!("Invalid variable name: {:?}", name));
+        }
+        if is_sensitive_var(name) && !allowlist.contains(&name) {
+            return Err(format!(
+                "Reference to sensitive variable {} is not allowed",
+                name
+            ));
+        }
+
+        match lookup(name)?.filter(|value| !value.is_empty()) {
+            Some(value) => output.push_str(&value),
+            None => match default {
+                Some(default) => output.push_str(default),
+                None => return Err(format!("Undefined variable: {}", name)),
+            },
+        }
+
+        rest = &after[end + 1..];
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// Interpolate every string value in a JSON tree
+pub fn interpolate_value<F>(value: &mut Value, lookup: &F, allowlist: &[&str]) -> Result<(), String>
+where
+    F: Fn(&str) -> Result<Option<String>, String>,
+{
+    match value {
+        Value::String(s) => *s = interpolate_str(s, lookup, allowlist)?,
+        Value::Array(items) => {
+            for item in items {
+                interpolate_value(item, lookup, allowlist)?;
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                interpolate_value(item, lookup, allowlist)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Parse a configuration file as JSON or TOML depending on its extension
+pub(crate) fn parse_config(path: &Path) -> Result<Value, String> {
+    let metadata = fs::metadata(path).map_err(|e| format!("Failed to read config: {}", e))?;
+    if metadata.len() > MAX_CONFIG_SIZE {
+        return Err("Config file is too large".into());
+    }
+
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read config: {}", e))?;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => {
+            serde_json::from_str(&contents).map_err(|e| format!("Invalid JSON config: {}", e))
+        }
+        Some("toml") => {
+            toml::from_str(&contents).map_err(|e| format!("Invalid TOML config: {}", e))
+        }
+        _ => Err("Unsupported config format; expected .json or .toml".into()),
+    }
+}
+
+/// Read a JSON or TOML config file, interpolating environment variables
+#[tauri::command]
+pub fn read_config_interpolated(
+    config: State<'_, ConfigState>,
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    file_path: String,
+) -> Result<Value, String> {
+    let guard = CommandGuard::new(&audit, "read_config_interpolated");
+    let path = guard.confined_path(&file_path, &roots)?;
+    let config = config.current();
+    let lookup = allowed_env_lookup(&config)?;
+    read_interpolated(&path, &|name: &str| lookup(name).map_err(String::from))
+}
+
+/// Parse the config at `path` and interpolate variables resolved by `lookup`
+pub(crate) fn read_interpolated<F>(path: &Path, lookup: &F) -> Result<Value, String>
+where
+    F: Fn(&str) -> Result<Option<String>, String>,
+{
+    let mut value = parse_config(path)?;
+    interpolate_value(&mut value, lookup, ALLOWED_SENSITIVE_VARS)?;
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::app_config::AppConfig;
+    use serde_json::json;
+
+    fn lookup(name: &str) -> Result<Option<String>, String> {
+        Ok(match name {
+            "HOME" => Some("/Users/alice".into()),
+            "EMPTY" => Some(String::new()),
+            "API_TOKEN" => Some("s3cr3t".into()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_interpolation() {
+        assert_eq!(
+            interpolate_str("${HOME}/data", &lookup, &[]).unwrap(),
+            "/Users/alice/data"
+        );
+        assert_eq!(interpolate_str("no refs", &lookup, &[]).unwrap(), "no refs");
+
+        let mut value = json!({ "paths": ["${HOME}/a", { "b": "${HOME}" }], "n": 1 });
+        interpolate_value(&mut value, &lookup, &[]).unwrap();
+        assert_eq!(
+            value,
+            json!({ "paths": ["/Users/alice/a", { "b": "/Users/alice" }], "n": 1 })
+        );
+    }
+
+    #[test]
+    fn test_default_fallback() {
+        assert_eq!(
+            interpolate_str("${MISSING:-/tmp}/cache", &lookup, &[]).unwrap(),
+            "/tmp/cache"
+        );
+        assert_eq!(interpolate_str("${EMPTY:-x}", &lookup, &[]).unwrap(), "x");
+        assert_eq!(
+            interpolate_str("${HOME:-x}", &lookup, &[]).unwrap(),
+            "/Users/alice"
+        );
+    }
+
+    #[test]
+    fn test_undefined_and_sensitive_errors() {
+        let err = interpolate_str("${MISSING}/data", &lookup, &[]).unwrap_err();
+        assert!(err.contains("Undefined variable: MISSING"));
+
+        assert!(interpolate_str("${API_TOKEN}", &lookup, &[]).is_err());
+        assert_eq!(
+            interpolate_str("${API_TOKEN}", &lookup, &["API_TOKEN"]).unwrap(),
+            "s3cr3t"
+        );
+
+        assert!(interpolate_str("${UNCLOSED", &lookup, &[]).is_err());
+        assert!(interpolate_str("${bad-name}", &lookup, &[]).is_err());
+    }
+
+    #[test]
+    fn test_read_config_interpolated_toml() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.toml");
+        fs::write(
+            &path,
+            "cache = \"${TSB_CONFIG_TEST_UNSET:-fallback}/cache\"\n[window]\nwidth = 800\n",
+        )
+        .unwrap();
+
+        let value = read_interpolated(&path, &lookup).unwrap();
+        assert_eq!(value["cache"], "fallback/cache");
+        assert_eq!(value["window"]["width"], 800);
+    }
+
+    #[test]
+    fn test_interpolation_uses_env_allowlist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.json");
+        let config = AppConfig {
+            allowed_env_vars: vec!["TSB_CONFIG_TEST_UNSET".into()],
+            ..AppConfig::default()
+        };
+        let allowed = allowed_env_lookup(&config).unwrap();
+        let lookup = |name: &str| allowed(name).map_err(String::from);
+
+        fs::write(
+            &path,
+            r#"{ "cache": "${TSB_CONFIG_TEST_UNSET:-fallback}" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            read_interpolated(&path, &lookup).unwrap()["cache"],
+            "fallback"
+        );
+
+        // Refused even with a default, or the default would reveal whether it is set
+        fs::write(&path, r#"{ "home": "${HOME:-/nowhere}" }"#).unwrap();
+        let err = read_interpolated(&path, &lookup).unwrap_err();
+        assert!(err.contains("not allowed"), "{}", err);
+    }
+}
@@ -0,0 +1,985 @@
+//! Cryptographic helpers for Tauri applications
+//!
+//! This module provides cryptographic primitives for commands:
+//! 1. Random tokens drawn from the operating system's CSPRNG
+//! 2. Raw key material held in `SecureBytes` and zeroed after use
+//! 3. Streaming digest checks for downloaded or updated files
+//! 4. Password-based file encryption with Argon2id and XChaCha20-Poly1305
+//! 5. Duplicate detection that only hashes files sharing a size
+//! 6. Argon2id password hashes stored as PHC strings
+
+use super::allowed_roots::AllowedRoots;
+use super::audit::AuditLog;
+use super::error::AppError;
+use super::file_ops::{resolve_new_in_roots, temp_path_for};
+use super::guard::CommandGuard;
+use super::memory_safe::{consume_into_secure, SecureBytes, SecureKey, SecureString};
+use super::scoped_access::ScopedAccess;
+use super::window_caps::{require_capability, CapabilityToken};
+use argon2::password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use constant_time_eq::constant_time_eq;
+use data_encoding::{BASE32_NOPAD, HEXLOWER, HEXLOWER_PERMISSIVE};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State, Window};
+use thiserror::Error;
+
+/// Largest token we are willing to generate (1 MiB of random bytes)
+pub const MAX_TOKEN_BYTES: usize = 1024 * 1024;
+
+/// Text encoding for generated tokens
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenEncoding {
+    /// Lowercase hexadecimal
+    Hex,
+    /// URL-safe base64 without padding
+    Base64Url,
+    /// RFC 4648 base32 without padding
+    Base32,
+}
+
+impl TokenEncoding {
+    /// Encode `bytes` in this encoding
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Hex => HEXLOWER.encode(bytes),
+            Self::Base64Url => URL_SAFE_NO_PAD.encode(bytes),
+            Self::Base32 => BASE32_NOPAD.encode(bytes),
+        }
+    }
+}
+
+/// Fill a `SecureBytes` buffer with `len` bytes from the OS random source
+pub fn random_bytes(len: usize) -> Result<SecureBytes, String> {
+    let mut bytes = SecureBytes::new(vec![0u8; len]);
+    OsRng
+        .try_fill_bytes(bytes.as_mut_bytes())
+        .map_err(|e| format!("Failed to gather randomness: {}", e))?;
+    Ok(bytes)
+}
+
+/// Generate a random token of `byte_len` bytes, e.g. for CSRF tokens or file names
+#[tauri::command]
+pub fn generate_secure_token(byte_len: usize, encoding: TokenEncoding) -> Result<String, String> {
+    if byte_len == 0 || byte_len > MAX_TOKEN_BYTES {
+        return Err(format!(
+            "Token length must be between 1 and {} bytes",
+            MAX_TOKEN_BYTES
+        ));
+    }
+
+    // The raw bytes are zeroed when `bytes` is dropped
+    let bytes = random_bytes(byte_len)?;
+    Ok(encoding.encode(bytes.as_bytes()))
+}
+
+/// Size of the buffer used to stream files through a digest
+pub(crate) const HASH_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Digest algorithm used to check file integrity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// SHA-256
+    Sha256,
+    /// SHA-512
+    Sha512,
+    /// BLAKE3 with its default 32-byte output
+    Blake3,
+}
+
+/// Incremental state of one of the supported digests
+enum FileHasher {
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl HashAlgorithm {
+    /// Length of the digest in bytes
+    pub fn digest_len(self) -> usize {
+        match self {
+            Self::Sha256 | Self::Blake3 => 32,
+            Self::Sha512 => 64,
+        }
+    }
+
+    fn hasher(self) -> FileHasher {
+        match self {
+            Self::Sha256 => FileHasher::Sha256(Sha256::new()),
+            Self::Sha512 => FileHasher::Sha512(Sha512::new()),
+            Self::Blake3 => FileHasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+}
+
+impl FileHasher {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(h) => h.update(data),
+            Self::Sha512(h) => h.update(data),
+            Self::Blake3(h) => {
+                h.update(data);
+            }
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha256(h) => h.finalize().to_vec(),
+            Self::Sha512(h) => h.finalize().to_vec(),
+            Self::Blake3(h) => h.finalize().as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Feed the file at `path` to `update` in chunks, never loading it at once
+pub(crate) fn stream_file<F: FnMut(&[u8])>(path: &Path, mut update: F) -> Result<(), AppError> {
+    let mut file = File::open(path).map_err(|e| AppError::io("read", path, e))?;
+    let mut buffer = vec![0u8; HASH_CHUNK_BYTES];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| AppError::io("read", path, e))?;
+        if read == 0 {
+            return Ok(());
+        }
+        update(&buffer[..read]);
+    }
+}
+
+/// Digest the file at `path` without loading it into memory at once
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> Result<Vec<u8>, AppError> {
+    let mut hasher = algorithm.hasher();
+    stream_file(path, |chunk| hasher.update(chunk))?;
+    Ok(hasher.finalize())
+}
+
+/// Check the file at `path` against a hex digest, comparing in constant time
+///
+/// A malformed `expected_hex` is a `Validation` error rather than a mismatch.
+pub fn file_hash_matches(
+    path: &Path,
+    expected_hex: &str,
+    algorithm: HashAlgorithm,
+) -> Result<bool, AppError> {
+    let expected = parse_digest(expected_hex, algorithm)?;
+    let actual = hash_file(path, algorithm)?;
+    Ok(constant_time_eq(&actual, &expected))
+}
+
+/// Decode a hex digest, checking its length against `algorithm`
+pub(crate) fn parse_digest(hex: &str, algorithm: HashAlgorithm) -> Result<Vec<u8>, AppError> {
+    let hex = hex.trim();
+    if hex.len() != algorithm.digest_len() * 2 {
+        return Err(AppError::Validation(format!(
+            "Expected digest must be {} hex characters",
+            algorithm.digest_len() * 2
+        )));
+    }
+    HEXLOWER_PERMISSIVE
+        .decode(hex.as_bytes())
+        .map_err(|e| AppError::Validation(format!("Invalid expected digest: {}", e)))
+}
+
+/// Verify that a downloaded or updated file has the expected digest
+#[tauri::command]
+pub fn verify_file_hash(
+    roots: State<'_, AllowedRoots>,
+    scope: State<'_, ScopedAccess>,
+    audit: State<'_, AuditLog>,
+    window: Window,
+    path: String,
+    expected_hex: String,
+    algorithm: HashAlgorithm,
+) -> Result<bool, AppError> {
+    let guard = CommandGuard::new(&audit, "verify_file_hash");
+    let path = guard.scoped_path(&path, &roots, &scope, window.label())?;
+    file_hash_matches(&path, &expected_hex, algorithm)
+}
+
+/// Most paths accepted by a single `find_duplicates` call
+pub const MAX_DUPLICATE_PATHS: usize = 10_000;
+
+/// Files with identical contents
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DuplicateGroup {
+    /// Size of each file in bytes
+    pub size: u64,
+    /// Lowercase hex digest shared by the files
+    pub digest: String,
+    /// Canonical paths of the files, sorted
+    pub paths: Vec<String>,
+}
+
+/// A path that could not be compared
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct UnreadableFile {
+    /// The path as given
+    pub path: String,
+    /// Why it was skipped
+    pub error: String,
+}
+
+/// Result of `find_duplicates`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DuplicateReport {
+    /// Groups of two or more identical files, largest files first
+    pub groups: Vec<DuplicateGroup>,
+    /// Paths that were outside the allowed roots, missing or unreadable
+    pub unreadable: Vec<UnreadableFile>,
+}
+
+impl DuplicateReport {
+    fn skip(&mut self, path: &str, error: impl ToString) {
+        self.unreadable.push(UnreadableFile {
+            path: path.to_string(),
+            error: error.to_string(),
+        });
+    }
+}
+
+/// Group identical files among `paths`, as read by the window labelled `label`
+///
+/// Files are first grouped by size, which is cheap; only files whose size is
+/// shared with another file are hashed. A path listed twice is counted once.
+pub(crate) fn duplicate_groups(
+    guard: &CommandGuard,
+    roots: &AllowedRoots,
+    scope: &ScopedAccess,
+    label: &str,
+    paths: &[String],
+    algorithm: HashAlgorithm,
+) -> Result<DuplicateReport, AppError> {
+    if paths.len() > MAX_DUPLICATE_PATHS {
+        return Err(AppError::Validation(format!(
+            "At most {} paths can be compared at once",
+            MAX_DUPLICATE_PATHS
+        )));
+    }
+
+    let mut report = DuplicateReport::default();
+    let mut seen = HashSet::new();
+    let mut by_size: BTreeMap<u64, Vec<(&str, PathBuf)>> = BTreeMap::new();
+    for raw in paths {
+        let canonical = match guard.scoped_path(raw, roots, scope, label) {
+            Ok(canonical) => canonical,
+            Err(e) => {
+                report.skip(raw, e);
+                continue;
+            }
+        };
+        match std::fs::metadata(&canonical) {
+            Ok(metadata) if metadata.is_file() => {
+                if seen.insert(canonical.clone()) {
+                    by_size
+                        .entry(metadata.len())
+                        .or_default()
+                        .push((raw.as_str(), canonical));
+                }
+            }
+            Ok(_) => report.skip(raw, "Not a regular file"),
+            Err(e) => report.skip(raw, e),
+        }
+    }
+
+    for (size, candidates) in by_size.into_iter().rev() {
+        if candidates.len() < 2 {
+            continue;
+        }
+        let mut by_digest: BTreeMap<Vec<u8>, Vec<String>> = BTreeMap::new();
+        for (raw, canonical) in candidates {
+            match hash_file(&canonical, algorithm) {
+                Ok(digest) => by_digest
+                    .entry(digest)
+                    .or_default()
+                    .push(canonical.to_string_lossy().into_owned()),
+                Err(e) => report.skip(raw, e),
+            }
+        }
+        for (digest, mut paths) in by_digest {
+            if paths.len() > 1 {
+                paths.sort();
+                report.groups.push(DuplicateGroup {
+                    size,
+                    digest: HEXLOWER.encode(&digest),
+                    paths,
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Find files with identical contents among `paths`
+///
+/// Candidates are hashed off the IPC thread.
+#[tauri::command]
+pub async fn find_duplicates(
+    app: AppHandle,
+    window: Window,
+    paths: Vec<String>,
+    algorithm: HashAlgorithm,
+) -> Result<DuplicateReport, AppError> {
+    let label = window.label().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let roots = app.state::<AllowedRoots>();
+        let scope = app.state::<ScopedAccess>();
+        let audit = app.state::<AuditLog>();
+        let guard = CommandGuard::new(&audit, "find_duplicates");
+        duplicate_groups(
+            &guard,
+            roots.inner(),
+            scope.inner(),
+            &label,
+            &paths,
+            algorithm,
+        )
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Duplicate search failed: {}", e)))?
+}
+
+/// Magic bytes at the start of an encrypted file
+const ENCRYPTED_MAGIC: &[u8; 6] = b"TSBENC";
+
+/// Version of the encrypted file format
+const ENCRYPTED_VERSION: u8 = 1;
+
+/// Length of the Argon2id salt in bytes
+const SALT_LEN: usize = 16;
+
+/// Length of an XChaCha20-Poly1305 nonce in bytes
+const NONCE_LEN: usize = 24;
+
+/// Length of the header: magic, version, three KDF parameters, salt and nonce
+const HEADER_LEN: usize = ENCRYPTED_MAGIC.len() + 1 + 3 * 4 + SALT_LEN + NONCE_LEN;
+
+/// Length of the derived key in bytes
+const KEY_LEN: usize = 32;
+
+/// Largest file accepted for encryption or decryption (512 MiB)
+pub const MAX_ENCRYPTED_FILE_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Largest Argon2 memory cost accepted from a file header, in KiB (1 GiB)
+///
+/// The header is untrusted, so a crafted file must not make us allocate
+/// unbounded memory before the password can even be checked.
+const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+
+/// Largest Argon2 iteration count accepted from a file header
+const MAX_KDF_ITERATIONS: u32 = 16;
+
+/// Largest Argon2 parallelism accepted from a file header
+const MAX_KDF_PARALLELISM: u32 = 16;
+
+/// Errors produced by file encryption and decryption
+#[derive(Debug, Error)]
+pub enum FileCryptoError {
+    /// The password is wrong or the file was modified
+    #[error("DecryptAuthFailed: wrong password or the file has been tampered with")]
+    DecryptAuthFailed,
+    /// The input is not usable, e.g. an empty password or a malformed header
+    #[error("InvalidInput: {0}")]
+    InvalidInput(String),
+    /// The underlying file system operation failed
+    #[error("Io: {0}")]
+    Io(String),
+}
+
+impl FileCryptoError {
+    fn io(action: &str, path: &Path, error: io::Error) -> Self {
+        Self::Io(format!(
+            "Failed to {} {}: {}",
+            action,
+            path.display(),
+            error
+        ))
+    }
+}
+
+/// Argon2id cost parameters stored in the file header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    /// Number of iterations
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// The OWASP recommended minimum for Argon2id
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl KdfParams {
+    /// Derive a key from `password` and `salt` into a `SecureKey`
+    fn derive_key(
+        self,
+        password: &SecureString,
+        salt: &[u8],
+    ) -> Result<SecureKey<KEY_LEN>, FileCryptoError> {
+        if self.memory_kib > MAX_KDF_MEMORY_KIB
+            || self.iterations > MAX_KDF_ITERATIONS
+            || self.parallelism > MAX_KDF_PARALLELISM
+        {
+            return Err(FileCryptoError::InvalidInput(
+                "Key derivation parameters are out of range".into(),
+            ));
+        }
+
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(KEY_LEN),
+        )
+        .map_err(|e| FileCryptoError::InvalidInput(format!("Invalid KDF parameters: {}", e)))?;
+
+        let mut key = SecureKey::zeroed();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password.as_str().as_bytes(), salt, key.as_mut_array())
+            .map_err(|e| FileCryptoError::InvalidInput(format!("Key derivation failed: {}", e)))?;
+        Ok(key)
+    }
+}
+
+/// Build the header, which is also authenticated as associated data
+fn encode_header(params: KdfParams, salt: &[u8], nonce: &[u8]) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(ENCRYPTED_MAGIC);
+    header.push(ENCRYPTED_VERSION);
+    header.extend_from_slice(&params.memory_kib.to_le_bytes());
+    header.extend_from_slice(&params.iterations.to_le_bytes());
+    header.extend_from_slice(&params.parallelism.to_le_bytes());
+    header.extend_from_slice(salt);
+    header.extend_from_slice(nonce);
+    header
+}
+
+/// Split a header into its KDF parameters, salt and nonce
+fn decode_header(header: &[u8]) -> Result<(KdfParams, &[u8], &[u8]), FileCryptoError> {
+    let invalid = || FileCryptoError::InvalidInput("Not an encrypted file".into());
+    if header.len() != HEADER_LEN || !header.starts_with(ENCRYPTED_MAGIC) {
+        return Err(invalid());
+    }
+
+    let rest = &header[ENCRYPTED_MAGIC.len()..];
+    if rest[0] != ENCRYPTED_VERSION {
+        return Err(FileCryptoError::InvalidInput(format!(
+            "Unsupported encrypted file version {}",
+            rest[0]
+        )));
+    }
+
+    let word = |i: usize| {
+        let start = 1 + i * 4;
+        u32::from_le_bytes(rest[start..start + 4].try_into().unwrap_or_default())
+    };
+    let params = KdfParams {
+        memory_kib: word(0),
+        iterations: word(1),
+        parallelism: word(2),
+    };
+    let salt = &rest[13..13 + SALT_LEN];
+    let nonce = &rest[13 + SALT_LEN..];
+    Ok((params, salt, nonce))
+}
+
+/// Read a whole file, refusing anything over `MAX_ENCRYPTED_FILE_BYTES`
+fn read_bounded(path: &Path) -> Result<Vec<u8>, FileCryptoError> {
+    let metadata = fs::metadata(path).map_err(|e| FileCryptoError::io("read", path, e))?;
+    if metadata.len() > MAX_ENCRYPTED_FILE_BYTES {
+        return Err(FileCryptoError::InvalidInput(format!(
+            "File exceeds the maximum of {} bytes",
+            MAX_ENCRYPTED_FILE_BYTES
+        )));
+    }
+    fs::read(path).map_err(|e| FileCryptoError::io("read", path, e))
+}
+
+/// Write `data` to a private temporary file and rename it over `dest`
+fn write_replacing(dest: &Path, data: &[u8]) -> Result<(), FileCryptoError> {
+    let temp = temp_path_for(dest);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let written = options
+        .open(&temp)
+        .and_then(|mut file| file.write_all(data).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::rename(&temp, dest));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(FileCryptoError::io("write", dest, e));
+    }
+    Ok(())
+}
+
+/// Encrypt `src` into `dest` with a key derived from `password`
+pub fn encrypt_path(
+    src: &Path,
+    dest: &Path,
+    password: &SecureString,
+    params: KdfParams,
+) -> Result<(), FileCryptoError> {
+    if password.as_str().is_empty() {
+        return Err(FileCryptoError::InvalidInput(
+            "Password must not be empty".into(),
+        ));
+    }
+
+    // Move the plaintext into `SecureBytes` so it is zeroed once encrypted
+    let plaintext = SecureBytes::new(read_bounded(src)?);
+
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng
+        .try_fill_bytes(&mut salt)
+        .and_then(|_| OsRng.try_fill_bytes(&mut nonce))
+        .map_err(|e| FileCryptoError::Io(format!("Failed to gather randomness: {}", e)))?;
+
+    let key = params.derive_key(password, &salt)?;
+    let cipher = XChaCha20Poly1305::new_from_slice(key.as_array())
+        .map_err(|_| FileCryptoError::InvalidInput("Invalid key length".into()))?;
+
+    let mut output = encode_header(params, &salt, &nonce);
+    let ciphertext = cipher
+        .encrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext.as_bytes(),
+                aad: &output,
+            },
+        )
+        .map_err(|_| FileCryptoError::Io("Encryption failed".into()))?;
+    output.extend_from_slice(&ciphertext);
+
+    write_replacing(dest, &output)
+}
+
+/// Decrypt `src`, written by `encrypt_path`, into `dest`
+///
+/// A wrong password and a modified file both yield `DecryptAuthFailed`;
+/// `dest` is left untouched in that case.
+pub fn decrypt_path(
+    src: &Path,
+    dest: &Path,
+    password: &SecureString,
+) -> Result<(), FileCryptoError> {
+    let data = read_bounded(src)?;
+    if data.len() < HEADER_LEN {
+        return Err(FileCryptoError::InvalidInput(
+            "Not an encrypted file".into(),
+        ));
+    }
+    let (header, ciphertext) = data.split_at(HEADER_LEN);
+    let (params, salt, nonce) = decode_header(header)?;
+
+    let key = params.derive_key(password, salt)?;
+    let cipher = XChaCha20Poly1305::new_from_slice(key.as_array())
+        .map_err(|_| FileCryptoError::InvalidInput("Invalid key length".into()))?;
+
+    let plaintext = cipher
+        .decrypt(
+            XNonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map(SecureBytes::new)
+        .map_err(|_| FileCryptoError::DecryptAuthFailed)?;
+
+    write_replacing(dest, plaintext.as_bytes())
+}
+
+/// Encrypt a file at rest with a password
+///
+/// The password's buffer is moved into a `SecureString` and zeroed after use.
+#[tauri::command]
+pub fn encrypt_file(
+    window: Window,
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    src: String,
+    dest: String,
+    password: String,
+) -> Result<(), String> {
+    let password = consume_into_secure(password);
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let guard = CommandGuard::new(&audit, "encrypt_file");
+    let src = guard.confined_path(&src, &roots)?;
+    let dest = guard
+        .resolved_path(&dest, |path| resolve_new_in_roots(path, &roots))
+        .map_err(|e| e.to_string())?;
+    encrypt_path(&src, &dest, &password, KdfParams::default()).map_err(|e| e.to_string())
+}
+
+/// Decrypt a file written by `encrypt_file`
+#[tauri::command]
+pub fn decrypt_file(
+    window: Window,
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    src: String,
+    dest: String,
+    password: String,
+) -> Result<(), String> {
+    let password = consume_into_secure(password);
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let guard = CommandGuard::new(&audit, "decrypt_file");
+    let src = guard.confined_path(&src, &roots)?;
+    let dest = guard
+        .resolved_path(&dest, |path| resolve_new_in_roots(path, &roots))
+        .map_err(|e| e.to_string())?;
+    decrypt_path(&src, &dest, &password).map_err(|e| e.to_string())
+}
+
+/// Longest password accepted for hashing or verification, in bytes
+pub const MAX_PASSWORD_BYTES: usize = 4096;
+
+/// Reject empty and oversized passwords before spending time in Argon2
+fn check_password(password: &SecureString) -> Result<(), AppError> {
+    let len = password.as_str().len();
+    if len == 0 {
+        return Err(AppError::Validation("Password must not be empty".into()));
+    }
+    if len > MAX_PASSWORD_BYTES {
+        return Err(AppError::Validation(format!(
+            "Password must be at most {} bytes",
+            MAX_PASSWORD_BYTES
+        )));
+    }
+    Ok(())
+}
+
+/// Hash `password` into an Argon2id PHC string with a fresh random salt
+pub(crate) fn hash_password_with(
+    password: &SecureString,
+    params: KdfParams,
+) -> Result<String, AppError> {
+    check_password(password)?;
+    let params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        None,
+    )
+    .map_err(|e| AppError::Validation(format!("Invalid KDF parameters: {}", e)))?;
+    let salt = random_bytes(SALT_LEN).map_err(AppError::Io)?;
+    let salt = SaltString::encode_b64(salt.as_bytes())
+        .map_err(|e| AppError::Io(format!("Failed to encode salt: {}", e)))?;
+
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password(password.as_str().as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::Io(format!("Password hashing failed: {}", e)))
+}
+
+/// Check `password` against a PHC string produced by `hash_password_with`
+///
+/// A wrong password is `Ok(false)`; a stored hash that can't be parsed, or
+/// whose cost parameters exceed the KDF limits, is a `Validation` error. The
+/// final digest comparison is constant-time.
+pub(crate) fn verify_password_hash(
+    password: &SecureString,
+    stored_phc: &str,
+) -> Result<bool, AppError> {
+    check_password(password)?;
+    let malformed =
+        |e: password_hash::Error| AppError::Validation(format!("Malformed password hash: {}", e));
+    let hash = PasswordHash::new(stored_phc).map_err(malformed)?;
+
+    // The stored hash is untrusted, like an encrypted file's header
+    let params = Params::try_from(&hash).map_err(malformed)?;
+    if params.m_cost() > MAX_KDF_MEMORY_KIB
+        || params.t_cost() > MAX_KDF_ITERATIONS
+        || params.p_cost() > MAX_KDF_PARALLELISM
+    {
+        return Err(AppError::Validation(
+            "Password hash parameters are out of range".into(),
+        ));
+    }
+
+    match Argon2::default().verify_password(password.as_str().as_bytes(), &hash) {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(malformed(e)),
+    }
+}
+
+/// Hash a password for storage
+///
+/// The password's buffer is moved into a `SecureString` and zeroed after use.
+/// Argon2 runs off the IPC thread, since it is deliberately slow.
+#[tauri::command]
+pub async fn hash_password(input: String) -> Result<String, AppError> {
+    let input = consume_into_secure(input);
+    tauri::async_runtime::spawn_blocking(move || hash_password_with(&input, KdfParams::default()))
+        .await
+        .map_err(|e| AppError::Io(format!("Password hashing failed: {}", e)))?
+}
+
+/// Check a password against a hash from `hash_password`
+///
+/// The password's buffer is moved into a `SecureString` and zeroed after use.
+/// Argon2 runs off the IPC thread, since it is deliberately slow.
+#[tauri::command]
+pub async fn verify_password(input: String, stored_phc: String) -> Result<bool, AppError> {
+    let input = consume_into_secure(input);
+    tauri::async_runtime::spawn_blocking(move || verify_password_hash(&input, &stored_phc))
+        .await
+        .map_err(|e| AppError::Io(format!("Password verification failed: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::guard::TestAudit;
+
+    #[test]
+    fn test_token_lengths() {
+        let hex = generate_secure_token(32, TokenEncoding::Hex).unwrap();
+        assert_eq!(hex.len(), 64);
+        assert!(hex.chars().all(|c| c.is_ascii_hexdigit()));
+
+        let b64 = generate_secure_token(32, TokenEncoding::Base64Url).unwrap();
+        assert_eq!(b64.len(), 43);
+        assert!(!b64.contains('+') && !b64.contains('/') && !b64.contains('='));
+
+        let b32 = generate_secure_token(10, TokenEncoding::Base32).unwrap();
+        assert_eq!(b32.len(), 16);
+    }
+
+    #[test]
+    fn test_tokens_differ() {
+        let a = generate_secure_token(16, TokenEncoding::Hex).unwrap();
+        let b = generate_secure_token(16, TokenEncoding::Hex).unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_rejects_absurd_lengths() {
+        assert!(generate_secure_token(0, TokenEncoding::Hex).is_err());
+        assert!(generate_secure_token(MAX_TOKEN_BYTES + 1, TokenEncoding::Hex).is_err());
+    }
+
+    /// SHA-256 of `b"hello world"`
+    const HELLO_SHA256: &str = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+
+    #[test]
+    fn test_file_hash_matches_correct_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("update.bin");
+        std::fs::write(&path, "hello world").unwrap();
+
+        assert!(file_hash_matches(&path, HELLO_SHA256, HashAlgorithm::Sha256).unwrap());
+        // Hex is accepted in either case
+        let upper = HELLO_SHA256.to_uppercase();
+        assert!(file_hash_matches(&path, &upper, HashAlgorithm::Sha256).unwrap());
+
+        let blake = blake3::hash(b"hello world").to_hex();
+        assert!(file_hash_matches(&path, blake.as_str(), HashAlgorithm::Blake3).unwrap());
+    }
+
+    #[test]
+    fn test_file_hash_matches_wrong_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("update.bin");
+        // Larger than one chunk so streaming is exercised
+        std::fs::write(&path, vec![7u8; HASH_CHUNK_BYTES * 2 + 1]).unwrap();
+
+        assert!(!file_hash_matches(&path, HELLO_SHA256, HashAlgorithm::Sha256).unwrap());
+        assert!(matches!(
+            file_hash_matches(
+                &dir.path().join("missing"),
+                HELLO_SHA256,
+                HashAlgorithm::Sha256
+            ),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_file_hash_rejects_malformed_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("update.bin");
+        std::fs::write(&path, "hello world").unwrap();
+
+        let short = &HELLO_SHA256[..62];
+        let non_hex = "z".repeat(64);
+        for (expected, algorithm) in [
+            (short, HashAlgorithm::Sha256),
+            (non_hex.as_str(), HashAlgorithm::Sha256),
+            // Valid hex, but the wrong length for SHA-512
+            (HELLO_SHA256, HashAlgorithm::Sha512),
+        ] {
+            assert!(matches!(
+                file_hash_matches(&path, expected, algorithm),
+                Err(AppError::Validation(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_find_duplicates_groups_identical_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = AllowedRoots::new();
+        roots.add(dir.path()).unwrap();
+        let write = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, contents).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let original = write("photo.jpg", "same bytes");
+        let copy = write("photo (1).jpg", "same bytes");
+        // Same size as the others, so it is hashed, but the contents differ
+        let other = write("other.jpg", "diff bytes");
+        let missing = dir.path().join("gone.jpg").to_string_lossy().into_owned();
+
+        let paths = vec![original.clone(), copy, other, original, missing.clone()];
+        let scope = ScopedAccess::default();
+        let audit = TestAudit::new();
+        let report = duplicate_groups(
+            &audit.guard(),
+            &roots,
+            &scope,
+            "main",
+            &paths,
+            HashAlgorithm::Blake3,
+        )
+        .unwrap();
+
+        assert_eq!(report.groups.len(), 1);
+        let group = &report.groups[0];
+        assert_eq!(group.size, 10);
+        let names: Vec<_> = group
+            .paths
+            .iter()
+            .map(|p| Path::new(p).file_name().unwrap().to_string_lossy())
+            .collect();
+        assert_eq!(names, vec!["photo (1).jpg", "photo.jpg"]);
+
+        assert_eq!(report.unreadable.len(), 1);
+        assert_eq!(report.unreadable[0].path, missing);
+    }
+
+    /// Cheap parameters so the tests don't spend their time in Argon2
+    const TEST_KDF: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn test_encrypt_then_decrypt_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = dir.path().join("notes.txt");
+        let sealed = dir.path().join("notes.txt.enc");
+        let opened = dir.path().join("notes.out.txt");
+        std::fs::write(&plain, "dear diary").unwrap();
+
+        let password = SecureString::new("correct horse battery staple");
+        encrypt_path(&plain, &sealed, &password, TEST_KDF).unwrap();
+        let sealed_bytes = std::fs::read(&sealed).unwrap();
+        assert!(sealed_bytes.starts_with(ENCRYPTED_MAGIC));
+        assert!(!sealed_bytes
+            .windows(b"dear diary".len())
+            .any(|w| w == b"dear diary"));
+
+        decrypt_path(&sealed, &opened, &password).unwrap();
+        assert_eq!(std::fs::read_to_string(&opened).unwrap(), "dear diary");
+    }
+
+    #[test]
+    fn test_decrypt_with_wrong_password_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = dir.path().join("notes.txt");
+        let sealed = dir.path().join("notes.enc");
+        let opened = dir.path().join("notes.out");
+        std::fs::write(&plain, "dear diary").unwrap();
+
+        encrypt_path(&plain, &sealed, &SecureString::new("right"), TEST_KDF).unwrap();
+        let result = decrypt_path(&sealed, &opened, &SecureString::new("wrong"));
+        assert!(matches!(result, Err(FileCryptoError::DecryptAuthFailed)));
+        assert!(!opened.exists());
+    }
+
+    #[test]
+    fn test_decrypt_corrupted_ciphertext_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = dir.path().join("notes.txt");
+        let sealed = dir.path().join("notes.enc");
+        let opened = dir.path().join("notes.out");
+        std::fs::write(&plain, "dear diary").unwrap();
+
+        let password = SecureString::new("right");
+        encrypt_path(&plain, &sealed, &password, TEST_KDF).unwrap();
+
+        let mut bytes = std::fs::read(&sealed).unwrap();
+        bytes[HEADER_LEN + 3] ^= 0x01;
+        std::fs::write(&sealed, &bytes).unwrap();
+
+        let result = decrypt_path(&sealed, &opened, &password);
+        assert!(matches!(result, Err(FileCryptoError::DecryptAuthFailed)));
+        assert!(!opened.exists());
+    }
+
+    #[test]
+    fn test_verify_password_accepts_correct_password() {
+        let hash = hash_password_with(&SecureString::new("hunter2"), TEST_KDF).unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+        assert!(verify_password_hash(&SecureString::new("hunter2"), &hash).unwrap());
+
+        // Every hash gets its own salt
+        let again = hash_password_with(&SecureString::new("hunter2"), TEST_KDF).unwrap();
+        assert_ne!(hash, again);
+    }
+
+    #[test]
+    fn test_verify_password_rejects_wrong_password() {
+        let hash = hash_password_with(&SecureString::new("hunter2"), TEST_KDF).unwrap();
+        assert!(!verify_password_hash(&SecureString::new("hunter3"), &hash).unwrap());
+        assert!(verify_password_hash(&SecureString::new(""), &hash).is_err());
+    }
+
+    #[test]
+    fn test_verify_password_rejects_malformed_hash() {
+        let password = SecureString::new("hunter2");
+        for stored in [
+            "",
+            "hunter2",
+            "$argon2id$v=19$m=abc,t=1,p=1$c2FsdHNhbHQ$aGFzaA",
+            "$pbkdf2-sha256$i=1000$c2FsdHNhbHQ$aGFzaGhhc2hoYXNo",
+            "$argon2id$v=19$m=4194304,t=1,p=1$c2FsdHNhbHQ$aGFzaGhhc2hoYXNo",
+        ] {
+            let result = verify_password_hash(&password, stored);
+            assert!(matches!(result, Err(AppError::Validation(_))), "{}", stored);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Graceful degradation for Tauri applications
+//!
+//! A plugin that fails to start shouldn't take the whole app down with it:
+//! 1. `init_optional` runs one initializer and logs a failure instead of panicking
+//! 2. Failures are recorded in `DegradedFeatures`, kept in managed state
+//! 3. The remaining initializers still run, so the app starts with reduced functionality
+//! 4. `degraded_features` lets the frontend hide or explain what is unavailable
+//! 5. Commands backed by a plugin call `ensure_available` first, so a missing
+//!    plugin fails with `Unavailable` rather than a panic or an opaque error
+
+use super::error::AppError;
+use log::error;
+use serde::Serialize;
+use std::fmt::Display;
+use std::sync::{Mutex, MutexGuard};
+use tauri::State;
+
+/// The dialog plugin, behind the file picker commands
+pub const DIALOG_FEATURE: &str = "dialog";
+
+/// The shell plugin, behind `open_with_default`
+pub const SHELL_FEATURE: &str = "shell";
+
+/// The fs plugin
+pub const FS_FEATURE: &str = "fs";
+
+/// The clipboard manager plugin, behind the clipboard commands
+pub const CLIPBOARD_FEATURE: &str = "clipboard-manager";
+
+/// A feature that failed to initialize
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DegradedFeature {
+    /// Name of the feature, e.g. the plugin name
+    pub feature: String,
+    /// Why initialization failed
+    pub error: String,
+}
+
+/// Features that failed to initialize, in Tauri managed state
+#[derive(Debug, Default)]
+pub struct DegradedFeatures {
+    features: Mutex<Vec<DegradedFeature>>,
+}
+
+impl DegradedFeatures {
+    fn lock_features(&self) -> MutexGuard<'_, Vec<DegradedFeature>> {
+        self.features.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record that `feature` is unavailable because of `error`
+    pub fn record(&self, feature: &str, error: impl Display) {
+        self.lock_features().push(DegradedFeature {
+            feature: feature.to_string(),
+            error: error.to_string(),
+        });
+    }
+
+    /// Whether `feature` failed to initialize
+    pub fn is_degraded(&self, feature: &str) -> bool {
+        self.lock_features()
+            .iter()
+            .any(|degraded| degraded.feature == feature)
+    }
+
+    /// Fail with `Unavailable` if `feature` failed to initialize
+    pub fn ensure_available(&self, feature: &str) -> Result<(), AppError> {
+        if self.is_degraded(feature) {
+            return Err(AppError::Unavailable(format!(
+                "{} failed to initialize at startup",
+                feature
+            )));
+        }
+        Ok(())
+    }
+
+    /// Every feature that failed to initialize, in the order they failed
+    pub fn list(&self) -> Vec<DegradedFeature> {
+        self.lock_features().clone()
+    }
+}
+
+/// Run `init` for `feature`, recording a failure instead of aborting startup
+///
+/// Returns whether the feature is available.
+pub fn init_optional<E, F>(degraded: &DegradedFeatures, feature: &str, init: F) -> bool
+where
+    E: Display,
+    F: FnOnce() -> Result<(), E>,
+{
+    match init() {
+        Ok(()) => true,
+        Err(e) => {
+            error!(
+                "Failed to initialize {}; continuing without it: {}",
+                feature, e
+            );
+            degraded.record(feature, e);
+            false
+        }
+    }
+}
+
+/// List the features that failed to initialize
+#[tauri::command]
+pub fn degraded_features(degraded: State<'_, DegradedFeatures>) -> Vec<DegradedFeature> {
+    degraded.list()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failing_initializer_is_recorded_and_startup_continues() {
+        let degraded = DegradedFeatures::default();
+        let mut started = Vec::new();
+
+        let initializers: [(&str, Result<(), &str>); 3] = [
+            ("dialog", Ok(())),
+            ("shell", Err("plugin config is invalid")),
+            ("fs", Ok(())),
+        ];
+        for (feature, outcome) in initializers {
+            if init_optional(&degraded, feature, || outcome) {
+                started.push(feature);
+            }
+        }
+
+        assert_eq!(started, vec!["dialog", "fs"]);
+        assert!(degraded.is_degraded("shell"));
+        assert!(!degraded.is_degraded("fs"));
+        assert_eq!(
+            degraded.list(),
+            vec![DegradedFeature {
+                feature: "shell".into(),
+                error: "plugin config is invalid".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_nothing_is_degraded_when_every_initializer_succeeds() {
+        let degraded = DegradedFeatures::default();
+        assert!(init_optional(&degraded, "dialog", || Ok::<(), String>(())));
+        assert!(degraded.list().is_empty());
+    }
+
+    #[test]
+    fn test_degraded_feature_is_unavailable() {
+        let degraded = DegradedFeatures::default();
+        init_optional(&degraded, DIALOG_FEATURE, || Err("no display"));
+
+        assert!(matches!(
+            degraded.ensure_available(DIALOG_FEATURE),
+            Err(AppError::Unavailable(message)) if message.contains("dialog")
+        ));
+        assert_eq!(degraded.ensure_available(SHELL_FEATURE), Ok(()));
+    }
+}
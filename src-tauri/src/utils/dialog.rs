@@ -0,0 +1,220 @@
+//! File dialog commands for Tauri applications
+//!
+//! Dialog filters are only a hint, so selections are checked again here:
+//! 1. `select_files_filtered` opens a native multi-file picker
+//! 2. Every returned path is matched against an extension allowlist
+//! 3. Matching ignores case and a leading dot in the allowlist
+//! 4. Disallowed files are either dropped or fail the whole selection
+//! 5. Selections are granted to the calling window through `ScopedAccess`, so
+//!    it may read them for a while even outside the allowed roots
+//! 6. Every picker fails with `Unavailable` if the dialog plugin didn't start
+
+use super::app_config::ConfigState;
+use super::degraded::{DegradedFeatures, DIALOG_FEATURE};
+use super::error::AppError;
+use super::scoped_access::ScopedAccess;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Window};
+use tauri_plugin_dialog::{DialogExt, FilePath};
+
+/// What to do with a selected file whose extension is not allowed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisallowedSelection {
+    /// Leave the file out of the result
+    Drop,
+    /// Fail with a `Validation` error naming the file
+    Reject,
+}
+
+/// Normalize an allowlist to lowercase extensions without a leading dot
+fn normalize_extensions(allowed_extensions: &[String]) -> Result<Vec<String>, AppError> {
+    let normalized: Vec<String> = allowed_extensions
+        .iter()
+        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect();
+    if normalized.is_empty() {
+        return Err(AppError::Validation(
+            "At least one allowed extension is required".into(),
+        ));
+    }
+    Ok(normalized)
+}
+
+/// Whether `path` has one of the normalized `allowed` extensions
+fn has_allowed_extension(path: &Path, allowed: &[String]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| allowed.iter().any(|a| a.eq_ignore_ascii_case(ext)))
+        .unwrap_or(false)
+}
+
+/// Apply the extension allowlist to a selection
+///
+/// Files without an extension never match.
+pub(crate) fn filter_selection(
+    paths: Vec<PathBuf>,
+    allowed_extensions: &[String],
+    disallowed: DisallowedSelection,
+) -> Result<Vec<PathBuf>, AppError> {
+    let allowed = normalize_extensions(allowed_extensions)?;
+
+    let mut kept = Vec::with_capacity(paths.len());
+    for path in paths {
+        if has_allowed_extension(&path, &allowed) {
+            kept.push(path);
+        } else if disallowed == DisallowedSelection::Reject {
+            return Err(AppError::Validation(format!(
+                "File type is not allowed: {}",
+                path.display()
+            )));
+        }
+    }
+    Ok(kept)
+}
+
+/// Let `window` read `paths` for the configured scoped access TTL
+fn grant_selection(window: &Window, paths: &[PathBuf], recursive: bool) {
+    let config = window.state::<ConfigState>().current();
+    let ttl = Duration::from_secs(config.scoped_access_ttl_secs);
+    window
+        .state::<ScopedAccess>()
+        .grant(window.label(), paths.iter().cloned(), recursive, ttl);
+}
+
+/// Fail if the dialog plugin failed to initialize
+fn ensure_dialog(app: &AppHandle) -> Result<(), AppError> {
+    app.state::<DegradedFeatures>()
+        .ensure_available(DIALOG_FEATURE)
+}
+
+/// Turn the dialog's selection into local paths
+fn selection_paths(selected: Vec<FilePath>) -> Result<Vec<PathBuf>, AppError> {
+    selected
+        .into_iter()
+        .map(|file| {
+            file.into_path()
+                .map_err(|e| AppError::Validation(format!("Unsupported selection: {}", e)))
+        })
+        .collect()
+}
+
+/// Let the user pick any files
+///
+/// The calling window may read the selected files afterwards.
+#[tauri::command]
+pub async fn select_files(app: AppHandle, window: Window) -> Result<Vec<String>, AppError> {
+    ensure_dialog(&app)?;
+    let selected = app
+        .dialog()
+        .file()
+        .blocking_pick_files()
+        .unwrap_or_default();
+    let paths = selection_paths(selected)?;
+
+    grant_selection(&window, &paths, false);
+    Ok(paths
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Let the user pick files, keeping only those with an allowed extension
+///
+/// Async so the blocking dialog runs off the main thread. The calling
+/// window may read the kept files afterwards.
+#[tauri::command]
+pub async fn select_files_filtered(
+    app: AppHandle,
+    window: Window,
+    allowed_extensions: Vec<String>,
+    disallowed: DisallowedSelection,
+) -> Result<Vec<String>, AppError> {
+    ensure_dialog(&app)?;
+    let allowed = normalize_extensions(&allowed_extensions)?;
+    let filter: Vec<&str> = allowed.iter().map(String::as_str).collect();
+
+    let selected = app
+        .dialog()
+        .file()
+        .add_filter("Allowed files", &filter)
+        .blocking_pick_files()
+        .unwrap_or_default();
+    let paths = selection_paths(selected)?;
+
+    let kept = filter_selection(paths, &allowed, disallowed)?;
+    grant_selection(&window, &kept, false);
+    Ok(kept
+        .into_iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Let the user pick a directory, returning `None` if the dialog was cancelled
+///
+/// The calling window may read everything under the directory afterwards.
+#[tauri::command]
+pub async fn select_directory(app: AppHandle, window: Window) -> Result<Option<String>, AppError> {
+    ensure_dialog(&app)?;
+    let Some(selected) = app.dialog().file().blocking_pick_folder() else {
+        return Ok(None);
+    };
+    let path = selected
+        .into_path()
+        .map_err(|e| AppError::Validation(format!("Unsupported selection: {}", e)))?;
+
+    grant_selection(&window, std::slice::from_ref(&path), true);
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selection() -> Vec<PathBuf> {
+        [
+            "/tmp/report.PDF",
+            "/tmp/photo.jpeg",
+            "/tmp/run.exe",
+            "/tmp/README",
+            "/tmp/notes.pdf",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect()
+    }
+
+    #[test]
+    fn test_drop_disallowed_files() {
+        let allowed = vec![".pdf".to_string(), "JPEG".to_string()];
+        let kept = filter_selection(selection(), &allowed, DisallowedSelection::Drop).unwrap();
+        assert_eq!(
+            kept,
+            vec![
+                PathBuf::from("/tmp/report.PDF"),
+                PathBuf::from("/tmp/photo.jpeg"),
+                PathBuf::from("/tmp/notes.pdf"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reject_disallowed_files() {
+        let allowed = vec!["pdf".to_string(), "jpeg".to_string()];
+        let err = filter_selection(selection(), &allowed, DisallowedSelection::Reject).unwrap_err();
+        assert_eq!(
+            err,
+            AppError::Validation("File type is not allowed: /tmp/run.exe".into())
+        );
+
+        let pdfs = vec![PathBuf::from("/tmp/a.pdf"), PathBuf::from("/tmp/b.Pdf")];
+        assert_eq!(
+            filter_selection(pdfs.clone(), &allowed, DisallowedSelection::Reject).unwrap(),
+            pdfs
+        );
+        assert!(filter_selection(pdfs, &[" . ".to_string()], DisallowedSelection::Drop).is_err());
+    }
+}
@@ -0,0 +1,140 @@
+//! Binary encoding for Tauri applications
+//!
+//! Key material and similar binary data is encoded without stray copies:
+//! 1. `encode_bytes` moves its input into `SecureBytes` before encoding
+//! 2. `decode_to_secure` decodes straight into a `SecureBytes` buffer, and
+//!    `decode_bytes` exposes it to the frontend
+//! 3. The encoded text is zeroed once it has been decoded
+//! 4. Malformed characters, lengths and padding are rejected, never skipped
+
+use super::crypto::TokenEncoding;
+use super::error::AppError;
+use super::memory_safe::SecureBytes;
+use data_encoding::{Encoding, BASE32_NOPAD, BASE64URL_NOPAD, HEXLOWER_PERMISSIVE};
+use zeroize::Zeroize;
+
+/// Largest input accepted by `encode_bytes`, and output of `decode_bytes` (16 MiB)
+pub const MAX_ENCODE_BYTES: usize = 16 * 1024 * 1024;
+
+/// The strict decoder matching `encoding`
+///
+/// Hex accepts either case; the other encodings must be unpadded.
+fn decoder(encoding: TokenEncoding) -> &'static Encoding {
+    match encoding {
+        TokenEncoding::Hex => &HEXLOWER_PERMISSIVE,
+        TokenEncoding::Base64Url => &BASE64URL_NOPAD,
+        TokenEncoding::Base32 => &BASE32_NOPAD,
+    }
+}
+
+/// Decode `text` into a new `SecureBytes`, zeroing `text` afterwards
+///
+/// Bad characters, a truncated final group and padding are all reported as
+/// `Validation` errors.
+pub fn decode_to_secure(
+    mut text: String,
+    encoding: TokenEncoding,
+) -> Result<SecureBytes, AppError> {
+    let result = decode_str(&text, encoding);
+    text.zeroize();
+    result
+}
+
+/// Decode `text` into a buffer sized for the longest possible output
+fn decode_str(text: &str, encoding: TokenEncoding) -> Result<SecureBytes, AppError> {
+    let decoder = decoder(encoding);
+    let invalid = |e: data_encoding::DecodeError| {
+        AppError::Validation(format!("Invalid {:?} input: {}", encoding, e))
+    };
+
+    let max_len = decoder.decode_len(text.len()).map_err(invalid)?;
+    let mut decoded = SecureBytes::new(vec![0u8; max_len]);
+    let len = decoder
+        .decode_mut(text.as_bytes(), decoded.as_mut_bytes())
+        .map_err(|partial| invalid(partial.error))?;
+    decoded.truncate(len);
+    Ok(decoded)
+}
+
+/// Encode binary data from the frontend as text
+///
+/// The input buffer is held in `SecureBytes`, so it is zeroed once encoded.
+#[tauri::command]
+pub fn encode_bytes(data: Vec<u8>, encoding: TokenEncoding) -> Result<String, AppError> {
+    let data = SecureBytes::new(data);
+    if data.len() > MAX_ENCODE_BYTES {
+        return Err(AppError::Validation(format!(
+            "Input exceeds the maximum of {} bytes",
+            MAX_ENCODE_BYTES
+        )));
+    }
+    Ok(encoding.encode(data.as_bytes()))
+}
+
+/// Decode text from the frontend into binary data
+///
+/// The text is zeroed once decoded, and so is the intermediate buffer.
+#[tauri::command]
+pub fn decode_bytes(text: String, encoding: TokenEncoding) -> Result<Vec<u8>, AppError> {
+    let decoded = decode_to_secure(text, encoding)?;
+    if decoded.len() > MAX_ENCODE_BYTES {
+        return Err(AppError::Validation(format!(
+            "Output exceeds the maximum of {} bytes",
+            MAX_ENCODE_BYTES
+        )));
+    }
+    Ok(decoded.as_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_round_trip() {
+        let key = vec![0x00, 0x7f, 0x80, 0xff, 0x42];
+        let text = encode_bytes(key.clone(), TokenEncoding::Hex).unwrap();
+        assert_eq!(text, "007f80ff42");
+
+        assert_eq!(decode_bytes(text, TokenEncoding::Hex).unwrap(), key);
+        // Upper case hex is accepted too
+        let upper = decode_to_secure("007F80FF42".into(), TokenEncoding::Hex).unwrap();
+        assert_eq!(upper.as_bytes(), key.as_slice());
+    }
+
+    #[test]
+    fn test_base64url_round_trip() {
+        let key: Vec<u8> = (0..=255).collect();
+        let text = encode_bytes(key.clone(), TokenEncoding::Base64Url).unwrap();
+        assert!(!text.contains(['+', '/', '=']));
+
+        let decoded = decode_to_secure(text, TokenEncoding::Base64Url).unwrap();
+        assert_eq!(decoded.as_bytes(), key.as_slice());
+        assert!(decode_to_secure(String::new(), TokenEncoding::Base64Url)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        let cases = [
+            ("abc", TokenEncoding::Hex),
+            ("zz", TokenEncoding::Hex),
+            ("AQID+g", TokenEncoding::Base64Url),
+            ("AQI=", TokenEncoding::Base64Url),
+            ("A", TokenEncoding::Base64Url),
+            ("MZXW6===", TokenEncoding::Base32),
+        ];
+        for (text, encoding) in cases {
+            assert!(
+                matches!(
+                    decode_to_secure(text.into(), encoding),
+                    Err(AppError::Validation(_))
+                ),
+                "{:?} accepted as {:?}",
+                text,
+                encoding
+            );
+        }
+    }
+}
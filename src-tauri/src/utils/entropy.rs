@@ -0,0 +1,172 @@
+//! File entropy measurement for Tauri applications
+//!
+//! Encrypted, compressed and packed files look like random bytes:
+//! 1. `measure_entropy` computes the Shannon entropy of a file in bits per byte
+//! 2. The file is streamed in chunks, optionally stopping after a sample
+//! 3. The result is between 0 (one repeated byte) and 8 (uniformly random)
+//! 4. `is_high_entropy` applies a fixed threshold, as a heuristic only
+//!
+//! Plain compressed formats (ZIP, JPEG, PNG) are high-entropy too, so a flag
+//! is a reason to look closer, not evidence of malware.
+
+use super::allowed_roots::AllowedRoots;
+use super::error::AppError;
+use super::scoped_access::{check_scoped, ScopedAccess};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use tauri::{AppHandle, Manager, Window};
+
+/// Entropy in bits per byte at or above which a file counts as high-entropy
+pub const HIGH_ENTROPY_THRESHOLD: f64 = 7.2;
+
+/// Size of the buffer the file is read through
+const ENTROPY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Result of `file_entropy`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct EntropyReport {
+    /// Shannon entropy of the bytes read, from 0 to 8 bits per byte
+    pub entropy: f64,
+    /// Number of bytes the entropy was computed over
+    pub bytes_read: u64,
+    /// Whether `entropy` reaches `HIGH_ENTROPY_THRESHOLD`
+    pub is_high_entropy: bool,
+}
+
+/// Whether `entropy` bits per byte suggests encrypted or packed data
+pub fn is_high_entropy(entropy: f64) -> bool {
+    entropy >= HIGH_ENTROPY_THRESHOLD
+}
+
+/// Shannon entropy in bits per byte of data with these byte counts
+fn shannon_entropy(counts: &[u64; 256], total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    let total = total as f64;
+    let entropy: f64 = counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum();
+    // Rounding can leave a tiny negative value for single-byte data
+    entropy.clamp(0.0, 8.0)
+}
+
+/// Compute the entropy of `path`, reading at most `sample_bytes` if given
+pub fn measure_entropy(path: &Path, sample_bytes: Option<u64>) -> Result<EntropyReport, AppError> {
+    if sample_bytes == Some(0) {
+        return Err(AppError::Validation(
+            "Sample size must be at least one byte".into(),
+        ));
+    }
+    let io_error = |e: io::Error| match e.kind() {
+        io::ErrorKind::NotFound => AppError::NotFound(path.display().to_string()),
+        _ => AppError::Io(format!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    let file = File::open(path).map_err(io_error)?;
+    let mut reader = file.take(sample_bytes.unwrap_or(u64::MAX));
+    let mut counts = [0u64; 256];
+    let mut total = 0u64;
+    let mut buffer = vec![0u8; ENTROPY_CHUNK_SIZE];
+    loop {
+        let read = reader.read(&mut buffer).map_err(io_error)?;
+        if read == 0 {
+            break;
+        }
+        for &byte in &buffer[..read] {
+            counts[byte as usize] += 1;
+        }
+        total += read as u64;
+    }
+
+    let entropy = shannon_entropy(&counts, total);
+    Ok(EntropyReport {
+        entropy,
+        bytes_read: total,
+        is_high_entropy: is_high_entropy(entropy),
+    })
+}
+
+/// Compute the Shannon entropy of a file within the allowed roots
+///
+/// With `sample_bytes`, only that many bytes from the start are read.
+/// The file is read off the IPC thread.
+#[tauri::command]
+pub async fn file_entropy(
+    app: AppHandle,
+    window: Window,
+    path: String,
+    sample_bytes: Option<u64>,
+) -> Result<EntropyReport, AppError> {
+    let label = window.label().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let roots = app.state::<AllowedRoots>();
+        let scope = app.state::<ScopedAccess>();
+        let path = check_scoped(&roots, &scope, &label, Path::new(&path))?;
+        measure_entropy(&path, sample_bytes)
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Entropy measurement failed: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use rand::RngCore;
+    use std::fs;
+
+    #[test]
+    fn test_zeros_have_no_entropy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("zeros.bin");
+        fs::write(&path, vec![0u8; 100_000]).unwrap();
+
+        let report = measure_entropy(&path, None).unwrap();
+        assert_eq!(report.entropy, 0.0);
+        assert_eq!(report.bytes_read, 100_000);
+        assert!(!report.is_high_entropy);
+    }
+
+    #[test]
+    fn test_random_data_is_high_entropy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("random.bin");
+        let mut data = vec![0u8; 256 * 1024];
+        OsRng.fill_bytes(&mut data);
+        // A run of zeros up front that only a sample would see
+        data[..4096].fill(0);
+        fs::write(&path, &data).unwrap();
+
+        let report = measure_entropy(&path, None).unwrap();
+        assert!(report.entropy > 7.9, "{}", report.entropy);
+        assert!(report.entropy <= 8.0);
+        assert!(report.is_high_entropy);
+
+        let sample = measure_entropy(&path, Some(4096)).unwrap();
+        assert_eq!(sample.bytes_read, 4096);
+        assert_eq!(sample.entropy, 0.0);
+    }
+
+    #[test]
+    fn test_empty_file_has_no_entropy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty.bin");
+        fs::write(&path, b"").unwrap();
+
+        let report = measure_entropy(&path, Some(1024)).unwrap();
+        assert_eq!(report.entropy, 0.0);
+        assert_eq!(report.bytes_read, 0);
+        assert!(matches!(
+            measure_entropy(&path, Some(0)),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+//! Error type shared by Tauri commands
+//!
+//! Commands report failures to the frontend as strings:
+//! 1. Each variant mirrors an `ErrorType` in `src/utils/helpers/errorHandling.ts`
+//! 2. Messages are prefixed with the variant name so the frontend can classify them
+//! 3. `AppError` serializes as its message, so commands can return it directly
+//! 4. `log_error` records a failure through the `log` crate before returning it,
+//!    with secrets scrubbed by a `Redactor`
+//! 5. `AppError::io` describes a failed file operation, so a missing file is
+//!    reported as `NotFound` everywhere
+
+use super::redact::Redactor;
+use log::error;
+use serde::{Serialize, Serializer};
+use std::io;
+use std::path::Path;
+use thiserror::Error;
+
+/// An error returned from a command
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum AppError {
+    /// The input failed validation
+    #[error("Validation: {0}")]
+    Validation(String),
+    /// The input is valid but refers to something that is off limits
+    #[error("Authorization: {0}")]
+    Authorization(String),
+    /// The calling window lacks a capability the command requires
+    #[error("PermissionDenied: {0}")]
+    PermissionDenied(String),
+    /// The requested file or resource does not exist
+    #[error("NotFound: {0}")]
+    NotFound(String),
+    /// The underlying I/O operation failed
+    #[error("Io: {0}")]
+    Io(String),
+    /// The operation did not finish in time, e.g. on a stale network mount
+    #[error("Timeout: {0}")]
+    Timeout(String),
+    /// A feature the command needs failed to initialize at startup
+    #[error("Unavailable: {0}")]
+    Unavailable(String),
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl AppError {
+    /// Describe a failure to `action` the file at `path`
+    ///
+    /// A missing file becomes `NotFound`; anything else is `Io`.
+    pub fn io(action: &str, path: &Path, error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => AppError::NotFound(path.display().to_string()),
+            _ => AppError::Io(format!(
+                "Failed to {} {}: {}",
+                action,
+                path.display(),
+                error
+            )),
+        }
+    }
+}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Pass the redacted entry for `message` to `emit` and return `message` unchanged
+fn log_error_with(
+    redactor: &Redactor,
+    category: Option<&str>,
+    message: &str,
+    emit: impl FnOnce(&str),
+) -> String {
+    let redacted = redactor.redact(message);
+    match category {
+        Some(category) => emit(&format!("[{}] {}", category, redacted)),
+        None => emit(&redacted),
+    }
+    message.to_string()
+}
+
+/// Log `message` at error level and return it, for `Err(log_error(..))`
+///
+/// Goes through the `log` crate rather than stdout, so the configured
+/// `env_logger` level and format apply. Only the logged copy is redacted.
+pub fn log_error(redactor: &Redactor, message: &str) -> String {
+    log_error_with(redactor, None, message, |entry| error!("{}", entry))
+}
+
+/// Like `log_error`, but tags the entry with a category such as `audit`
+///
+/// The category is written as a `[category]` prefix so the redaction and
+/// audit pipeline can classify entries; the returned message omits it.
+pub fn log_error_cat(redactor: &Redactor, category: &str, message: &str) -> String {
+    log_error_with(redactor, Some(category), message, |entry| {
+        error!("{}", entry)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logged_entry_is_redacted() {
+        let redactor = Redactor::default();
+        let mut entries = Vec::new();
+
+        let message = "Login failed for alice@example.com";
+        let returned = log_error_with(&redactor, None, message, |entry| {
+            entries.push(entry.to_string())
+        });
+        assert_eq!(returned, message);
+        log_error_with(
+            &redactor,
+            Some("audit"),
+            "Bearer abc.def rejected",
+            |entry| entries.push(entry.to_string()),
+        );
+
+        assert_eq!(entries, ["Login failed for ***", "[audit] *** rejected"]);
+    }
+
+    #[test]
+    fn test_io_error_classifies_missing_files() {
+        let path = Path::new("/data/report.txt");
+        assert_eq!(
+            AppError::io("open", path, io::ErrorKind::NotFound.into()),
+            AppError::NotFound("/data/report.txt".into())
+        );
+        assert_eq!(
+            AppError::io("write", path, io::Error::other("disk full")),
+            AppError::Io("Failed to write /data/report.txt: disk full".into())
+        );
+    }
+
+    #[test]
+    fn test_log_error_returns_original_message() {
+        let redactor = Redactor::default();
+        assert_eq!(log_error(&redactor, "disk on fire"), "disk on fire");
+        assert_eq!(
+            log_error_cat(&redactor, "audit", "write failed for bob@example.com"),
+            "write failed for bob@example.com"
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Advisory file locks for Tauri applications
+//!
+//! Several app instances sharing data files need to take turns writing them:
+//! 1. `acquire_file_lock` takes a shared or exclusive `flock`-style lock
+//! 2. A contended lock fails with `WouldBlock`, or `Timeout` after waiting
+//! 3. Held locks are tracked in managed state under a lock id
+//! 4. Dropping a held lock unlocks it, so a forgotten id can't wedge the file
+
+use super::allowed_roots::AllowedRoots;
+use super::file_system::validated_path;
+use fs2::FileExt;
+use log::warn;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Longest `acquire_file_lock` may wait for a contended lock (5 minutes)
+pub const MAX_LOCK_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+
+/// Most locks that may be held through the registry at once
+pub const MAX_HELD_LOCKS: usize = 256;
+
+/// How often a contended lock is retried
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Errors produced while acquiring a file lock
+#[derive(Debug, Error)]
+pub enum FileLockError {
+    /// Another holder has the lock and no timeout was given
+    #[error("WouldBlock: {0}")]
+    WouldBlock(String),
+    /// Another holder kept the lock for the whole timeout
+    #[error("Timeout: {0}")]
+    Timeout(String),
+    /// The underlying file system operation failed
+    #[error("Io: {0}")]
+    Io(String),
+}
+
+/// A lock on an open file, released when dropped
+#[derive(Debug)]
+pub struct HeldLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl HeldLock {
+    /// The locked file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for HeldLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock too; unlocking first is explicit
+        if let Err(e) = FileExt::unlock(&self.file) {
+            warn!("Failed to unlock {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Whether `error` means the lock is held by someone else
+fn is_contended(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::WouldBlock
+        || error.raw_os_error() == fs2::lock_contended_error().raw_os_error()
+}
+
+/// Lock `path`, retrying a contended lock until `timeout` has passed
+///
+/// A zero timeout tries exactly once. Locks are advisory: they only exclude
+/// other holders of locks on the same file.
+pub fn lock_file(
+    path: &Path,
+    exclusive: bool,
+    timeout: Duration,
+) -> Result<HeldLock, FileLockError> {
+    let file = File::open(path)
+        .map_err(|e| FileLockError::Io(format!("Failed to open {}: {}", path.display(), e)))?;
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        // Called through the trait, since newer `File`s have inherent lock methods
+        let attempt = if exclusive {
+            FileExt::try_lock_exclusive(&file)
+        } else {
+            FileExt::try_lock_shared(&file)
+        };
+        match attempt {
+            Ok(()) => {
+                return Ok(HeldLock {
+                    file,
+                    path: path.to_path_buf(),
+                })
+            }
+            Err(e) if is_contended(&e) => {
+                if timeout.is_zero() {
+                    return Err(FileLockError::WouldBlock(path.display().to_string()));
+                }
+                if Instant::now() >= deadline {
+                    return Err(FileLockError::Timeout(format!(
+                        "{} is still locked after {} ms",
+                        path.display(),
+                        timeout.as_millis()
+                    )));
+                }
+                thread::sleep(LOCK_RETRY_INTERVAL);
+            }
+            Err(e) => {
+                return Err(FileLockError::Io(format!(
+                    "Failed to lock {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        }
+    }
+}
+
+/// Locks held for the frontend, in Tauri managed state
+#[derive(Default)]
+pub struct FileLockRegistry {
+    locks: Mutex<HashMap<String, HeldLock>>,
+}
+
+impl FileLockRegistry {
+    /// Whether another lock may be held
+    fn has_room(&self) -> bool {
+        self.locks.lock().unwrap_or_else(|e| e.into_inner()).len() < MAX_HELD_LOCKS
+    }
+
+    /// Hold `lock` until released, returning its id
+    pub fn insert(&self, lock: HeldLock) -> String {
+        let lock_id = Uuid::new_v4().to_string();
+        self.locks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(lock_id.clone(), lock);
+        lock_id
+    }
+
+    /// Release a held lock, returning whether it was held
+    pub fn release(&self, lock_id: &str) -> bool {
+        self.locks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(lock_id)
+            .is_some()
+    }
+}
+
+/// Take an advisory lock on a file within the allowed roots
+///
+/// Waits up to `timeout_ms` (capped at `MAX_LOCK_TIMEOUT_MS`) for a contended
+/// lock; with a timeout of zero a contended lock fails with `WouldBlock`.
+#[tauri::command]
+pub async fn acquire_file_lock(
+    app: AppHandle,
+    path: String,
+    exclusive: bool,
+    timeout_ms: u64,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let file = validated_path(&path)?;
+        let file = app.state::<AllowedRoots>().check(&file)?;
+        let locks = app.state::<FileLockRegistry>();
+        if !locks.has_room() {
+            return Err(format!("At most {} locks may be held", MAX_HELD_LOCKS));
+        }
+
+        let timeout = Duration::from_millis(timeout_ms.min(MAX_LOCK_TIMEOUT_MS));
+        let lock = lock_file(&file, exclusive, timeout).map_err(|e| e.to_string())?;
+        Ok(locks.insert(lock))
+    })
+    .await
+    .map_err(|e| format!("Lock attempt failed: {}", e))?
+}
+
+/// Release a lock taken by `acquire_file_lock`
+#[tauri::command]
+pub fn release_file_lock(
+    locks: State<'_, FileLockRegistry>,
+    lock_id: String,
+) -> Result<(), String> {
+    if locks.release(&lock_id) {
+        Ok(())
+    } else {
+        Err(format!("No held lock with id {}", lock_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_contended_lock_fails_until_released() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shared.db");
+        fs::write(&path, "data").unwrap();
+        let registry = FileLockRegistry::default();
+
+        let first = registry.insert(lock_file(&path, true, Duration::ZERO).unwrap());
+        assert!(matches!(
+            lock_file(&path, true, Duration::ZERO),
+            Err(FileLockError::WouldBlock(_))
+        ));
+        assert!(matches!(
+            lock_file(&path, false, Duration::from_millis(50)),
+            Err(FileLockError::Timeout(_))
+        ));
+
+        assert!(registry.release(&first));
+        assert!(!registry.release(&first));
+        let _second = lock_file(&path, true, Duration::ZERO).unwrap();
+        assert!(matches!(
+            lock_file(&path, false, Duration::ZERO),
+            Err(FileLockError::WouldBlock(_))
+        ));
+    }
+
+    #[test]
+    fn test_waiting_acquire_succeeds_after_release() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shared.db");
+        fs::write(&path, "data").unwrap();
+
+        let held = lock_file(&path, true, Duration::ZERO).unwrap();
+        let releaser = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            drop(held);
+        });
+
+        let started = Instant::now();
+        let lock = lock_file(&path, true, Duration::from_secs(5)).unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert_eq!(lock.path(), path);
+        releaser.join().unwrap();
+    }
+
+    #[test]
+    fn test_shared_locks_coexist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shared.db");
+        fs::write(&path, "data").unwrap();
+
+        let _a = lock_file(&path, false, Duration::ZERO).unwrap();
+        let _b = lock_file(&path, false, Duration::ZERO).unwrap();
+        assert!(matches!(
+            lock_file(&path, true, Duration::ZERO),
+            Err(FileLockError::WouldBlock(_))
+        ));
+    }
+}
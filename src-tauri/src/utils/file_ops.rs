@@ -0,0 +1,1209 @@
+//! File copy, rename and directory creation commands for Tauri applications
+//!
+//! Files are moved around without clobbering data or escaping allowed roots:
+//! 1. Source and destination must both lie within an allowed root
+//! 2. Existing destinations are only replaced when `overwrite` is set, and
+//!    the refusal is atomic rather than a check followed by a rename
+//! 3. Replacements are written to a temporary file and renamed into place
+//! 4. Renames across file systems fall back to copy and delete
+//! 5. Directories are created with an explicit mode on Unix
+//! 6. Directory trees can be previewed with a dry run before being deleted
+//! 7. Copies and deletes run in the background and report their progress
+//! 8. Access and modification times can be set, e.g. to preserve them on sync
+//! 9. New entries must have names that are valid on every platform
+
+use super::allowed_roots::AllowedRoots;
+use super::audit::AuditLog;
+use super::error::AppError;
+use super::file_system::FileInfo;
+use super::guard::CommandGuard;
+use super::memory_safe::BoundaryValidator;
+use super::progress::{spawn_operation, OperationOutcome, ProgressReporter};
+use super::retry::{retry, RetryPolicy};
+use super::window_caps::{require_capability, CapabilityToken};
+use filetime::FileTime;
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Manager, State, Window};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// OS error code reported when a rename crosses file systems
+const CROSS_DEVICE_ERROR: Option<i32> = if cfg!(windows) {
+    // ERROR_NOT_SAME_DEVICE
+    Some(17)
+} else if cfg!(unix) {
+    // EXDEV
+    Some(18)
+} else {
+    None
+};
+
+/// Errors produced by copy and rename operations
+#[derive(Debug, Error)]
+pub enum FileOpError {
+    /// The destination exists and `overwrite` was not set
+    #[error("AlreadyExists: {0}")]
+    AlreadyExists(String),
+    /// A path failed validation or is not a supported kind of entry
+    #[error("InvalidPath: {0}")]
+    InvalidPath(String),
+    /// A directory has entries and `recursive` was not set
+    #[error("NotEmpty: {0}")]
+    NotEmpty(String),
+    /// The underlying file system operation failed
+    #[error("Io: {0}")]
+    Io(String),
+    /// The operation was stopped by `cancel_operation`
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+}
+
+impl FileOpError {
+    fn io(action: &str, path: &Path, error: io::Error) -> Self {
+        Self::Io(format!(
+            "Failed to {} {}: {}",
+            action,
+            path.display(),
+            error
+        ))
+    }
+}
+
+impl From<AppError> for FileOpError {
+    fn from(error: AppError) -> Self {
+        Self::InvalidPath(error.to_string())
+    }
+}
+
+/// Resolve the parent directory of `path` inside the allowed roots
+///
+/// Only the parent is canonicalized, so the final component may not exist yet
+/// and a symlink at that position is treated as the link itself. `path` should
+/// come from `CommandGuard::resolved_path`, which validates it first.
+pub(crate) fn resolve_in_roots(path: &Path, roots: &AllowedRoots) -> Result<PathBuf, FileOpError> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| FileOpError::InvalidPath(format!("No file name in {}", path.display())))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let parent = roots
+        .check(parent)
+        .map_err(|e| FileOpError::InvalidPath(e.to_string()))?;
+    Ok(parent.join(name))
+}
+
+/// Check that `name` is safe for a new entry, see `BoundaryValidator::validate_filename`
+fn check_new_name(name: &OsStr) -> Result<(), FileOpError> {
+    BoundaryValidator::validate_filename(&name.to_string_lossy())
+        .map_err(|e| FileOpError::InvalidPath(e.to_string()))
+}
+
+/// Like `resolve_in_roots`, for an entry about to be created
+///
+/// The new entry's name must also be a valid file name on every platform.
+pub(crate) fn resolve_new_in_roots(
+    path: &Path,
+    roots: &AllowedRoots,
+) -> Result<PathBuf, FileOpError> {
+    let path = resolve_in_roots(path, roots)?;
+    if let Some(name) = path.file_name() {
+        check_new_name(name)?;
+    }
+    Ok(path)
+}
+
+/// Resolve `path` inside the allowed roots, allowing missing parents
+///
+/// The deepest existing ancestor is canonicalized and checked, and the missing
+/// components are appended to it, so a recursive create cannot be steered out
+/// of the roots through `..` or a symlinked ancestor. Every missing component
+/// must be a valid file name.
+fn resolve_new_dir_in_roots(path: &Path, roots: &AllowedRoots) -> Result<PathBuf, FileOpError> {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    while fs::symlink_metadata(existing).is_err() {
+        match (existing.parent(), existing.components().next_back()) {
+            (Some(parent), Some(Component::Normal(name))) => {
+                check_new_name(name)?;
+                missing.push(name);
+                existing = if parent.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    parent
+                };
+            }
+            _ => {
+                return Err(FileOpError::InvalidPath(format!(
+                    "Cannot resolve {}",
+                    path.display()
+                )))
+            }
+        }
+    }
+
+    let mut resolved = roots
+        .check(existing)
+        .map_err(|e| FileOpError::InvalidPath(e.to_string()))?;
+    resolved.extend(missing.into_iter().rev());
+    Ok(resolved)
+}
+
+/// A sibling of `dest` used to stage a replacement
+pub(crate) fn temp_path_for(dest: &Path) -> PathBuf {
+    let name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    dest.with_file_name(format!(".{}.{}.tmp", name, Uuid::new_v4()))
+}
+
+/// Size of the buffer used to copy file contents
+const COPY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Copy contents, mode bits and modification time into an already created file
+///
+/// Progress is reported after every chunk, and the copy stops with an
+/// `Interrupted` error once the reporter's operation is cancelled.
+fn fill_copy(
+    src: &Path,
+    metadata: &Metadata,
+    mut dest: File,
+    progress: &mut ProgressReporter,
+) -> io::Result<()> {
+    let mut source = File::open(src)?;
+    let mut buffer = vec![0u8; COPY_CHUNK_SIZE];
+    let mut copied = 0u64;
+    loop {
+        if progress.is_cancelled() {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+        }
+        let read = match source.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        dest.write_all(&buffer[..read])?;
+        copied += read as u64;
+        progress.report(copied, src);
+    }
+    progress.finish(copied, src);
+
+    dest.set_permissions(metadata.permissions())?;
+    filetime::set_file_handle_times(
+        &dest,
+        None,
+        Some(FileTime::from_last_modification_time(metadata)),
+    )?;
+    dest.sync_all()
+}
+
+/// Create `dest` exclusively and copy `src` into it, removing it again on failure
+fn copy_new(
+    src: &Path,
+    metadata: &Metadata,
+    dest: &Path,
+    progress: &mut ProgressReporter,
+) -> Result<(), FileOpError> {
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dest)
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => FileOpError::AlreadyExists(dest.display().to_string()),
+            _ => FileOpError::io("create", dest, e),
+        })?;
+
+    fill_copy(src, metadata, file, progress).map_err(|e| {
+        let _ = fs::remove_file(dest);
+        if progress.is_cancelled() {
+            FileOpError::Cancelled(dest.display().to_string())
+        } else {
+            FileOpError::io("copy to", dest, e)
+        }
+    })
+}
+
+/// Copy a regular file, preserving its mode bits and modification time
+pub(crate) fn copy_preserving(
+    src: &Path,
+    dest: &Path,
+    overwrite: bool,
+    progress: &mut ProgressReporter,
+) -> Result<(), FileOpError> {
+    let metadata = fs::metadata(src).map_err(|e| FileOpError::io("read", src, e))?;
+    if !metadata.is_file() {
+        return Err(FileOpError::InvalidPath(format!(
+            "Not a regular file: {}",
+            src.display()
+        )));
+    }
+    progress.set_total(metadata.len());
+
+    if !overwrite {
+        // `create_new` fails atomically if the destination appears meanwhile
+        return copy_new(src, &metadata, dest, progress);
+    }
+
+    // Stage the copy next to the destination so the final rename is atomic
+    let temp = temp_path_for(dest);
+    copy_new(src, &metadata, &temp, progress)?;
+    retry(&RetryPolicy::default(), || fs::rename(&temp, dest)).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        FileOpError::io("replace", dest, e)
+    })
+}
+
+/// Check whether an error means a rename crossed file systems
+fn is_cross_device(error: &io::Error) -> bool {
+    error.raw_os_error().is_some() && error.raw_os_error() == CROSS_DEVICE_ERROR
+}
+
+/// Rename `src` to `dest` using `rename`, falling back to copy and delete
+///
+/// The rename primitive is injectable so the cross-file-system fallback can
+/// be tested. Only regular files can be moved across file systems.
+pub(crate) fn rename_with<F>(
+    src: &Path,
+    dest: &Path,
+    overwrite: bool,
+    rename: F,
+) -> Result<(), FileOpError>
+where
+    F: Fn(&Path, &Path) -> io::Result<()>,
+{
+    let metadata = fs::symlink_metadata(src).map_err(|e| FileOpError::io("read", src, e))?;
+
+    match rename(src, dest) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            Err(FileOpError::AlreadyExists(dest.display().to_string()))
+        }
+        Err(e) if is_cross_device(&e) => {
+            if !metadata.is_file() {
+                return Err(FileOpError::InvalidPath(format!(
+                    "Only regular files can be moved across file systems: {}",
+                    src.display()
+                )));
+            }
+            copy_preserving(src, dest, overwrite, &mut ProgressReporter::silent())?;
+            fs::remove_file(src).map_err(|e| FileOpError::io("remove", src, e))
+        }
+        Err(e) => Err(FileOpError::io("rename", src, e)),
+    }
+}
+
+/// Move `src` to `dest`, failing with `AlreadyExists` instead of replacing it
+///
+/// Files are hard linked into place, which refuses an existing `dest`
+/// atomically, and then unlinked from `src`. On Unix a directory is renamed
+/// onto a freshly created empty placeholder, which `rename` only replaces
+/// while it is still empty.
+fn rename_noreplace(src: &Path, dest: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(src)?.is_dir() {
+        return rename_dir_noreplace(src, dest);
+    }
+    fs::hard_link(src, dest)?;
+    retry(&RetryPolicy::default(), || fs::remove_file(src)).map_err(|e| {
+        let _ = fs::remove_file(dest);
+        e
+    })
+}
+
+#[cfg(unix)]
+fn rename_dir_noreplace(src: &Path, dest: &Path) -> io::Result<()> {
+    fs::create_dir(dest)?;
+    fs::rename(src, dest).map_err(|e| {
+        let _ = fs::remove_dir(dest);
+        e
+    })
+}
+
+#[cfg(not(unix))]
+fn rename_dir_noreplace(src: &Path, dest: &Path) -> io::Result<()> {
+    // Windows never lets a directory replace an existing entry
+    fs::rename(src, dest)
+}
+
+/// Map the result of a background operation to its reported outcome
+fn outcome_of<T>(result: Result<T, FileOpError>) -> OperationOutcome<T> {
+    match result {
+        Ok(result) => OperationOutcome::Completed { result },
+        Err(FileOpError::Cancelled(_)) => OperationOutcome::Cancelled,
+        Err(e) => OperationOutcome::Failed {
+            error: e.to_string(),
+        },
+    }
+}
+
+/// Copy a file within the allowed roots in the background
+///
+/// Returns an operation id right away; progress arrives in `file-progress`
+/// events and the copied file's info in `file-operation-complete`.
+#[tauri::command]
+pub fn copy_file(
+    app: AppHandle,
+    window: Window,
+    roots: State<'_, AllowedRoots>,
+    src: String,
+    dest: String,
+    overwrite: bool,
+) -> Result<String, String> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let audit = app.state::<AuditLog>();
+    let guard = CommandGuard::new(&audit, "copy_file");
+    let (src, dest) = resolve_copy_paths(&guard, roots.inner(), &src, &dest)?;
+    Ok(spawn_operation(app, move |progress| {
+        outcome_of(copy_resolved(&src, &dest, overwrite, progress))
+    }))
+}
+
+/// Resolve a copy's source and destination, both of which must lie within `roots`
+fn resolve_copy_paths(
+    guard: &CommandGuard,
+    roots: &AllowedRoots,
+    src: &str,
+    dest: &str,
+) -> Result<(PathBuf, PathBuf), String> {
+    // The content that gets copied is the link target, so it must be in a root too
+    let src = guard.resolved_path(src, |path| -> Result<PathBuf, String> {
+        let link = resolve_in_roots(path, roots).map_err(|e| e.to_string())?;
+        Ok(roots.check(&link)?)
+    })?;
+    let dest = guard
+        .resolved_path(dest, |path| resolve_new_in_roots(path, roots))
+        .map_err(|e| e.to_string())?;
+    Ok((src, dest))
+}
+
+/// Copy the resolved `src` to `dest` and describe the copy
+fn copy_resolved(
+    src: &Path,
+    dest: &Path,
+    overwrite: bool,
+    progress: &mut ProgressReporter,
+) -> Result<FileInfo, FileOpError> {
+    copy_preserving(src, dest, overwrite, progress)?;
+    FileInfo::from_path(dest, false).map_err(FileOpError::Io)
+}
+
+/// Rename or move a file or directory within the allowed roots
+#[tauri::command]
+pub fn rename_file(
+    window: Window,
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    src: String,
+    dest: String,
+    overwrite: bool,
+) -> Result<FileInfo, String> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let guard = CommandGuard::new(&audit, "rename_file");
+    rename_within(&guard, roots.inner(), &src, &dest, overwrite)
+}
+
+/// Rename `src` to `dest`, both of which must lie within `roots`
+pub(crate) fn rename_within(
+    guard: &CommandGuard,
+    roots: &AllowedRoots,
+    src: &str,
+    dest: &str,
+    overwrite: bool,
+) -> Result<FileInfo, String> {
+    let src = guard
+        .resolved_path(src, |path| resolve_in_roots(path, roots))
+        .map_err(|e| e.to_string())?;
+    let dest = guard
+        .resolved_path(dest, |path| resolve_new_in_roots(path, roots))
+        .map_err(|e| e.to_string())?;
+
+    // Another process briefly holding either file shouldn't fail the rename
+    rename_with(&src, &dest, overwrite, |from, to| {
+        if overwrite {
+            retry(&RetryPolicy::default(), || fs::rename(from, to))
+        } else {
+            rename_noreplace(from, to)
+        }
+    })
+    .map_err(|e| e.to_string())?;
+    FileInfo::from_path(&dest, false)
+}
+
+/// Create the directory `path`, and its missing parents when `recursive` is set
+///
+/// Fails with `AlreadyExists` if `path` already exists. On Unix, `path` is
+/// created with the permission bits of `mode`, so it is never more open than
+/// requested, and then given exactly those bits regardless of the umask;
+/// setuid, setgid and sticky bits are dropped. Intermediate directories get
+/// the default mode. `mode` is ignored on other platforms.
+pub(crate) fn create_dir_with_mode(
+    path: &Path,
+    recursive: bool,
+    mode: Option<u32>,
+) -> Result<(), FileOpError> {
+    if recursive {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| FileOpError::io("create", parent, e))?;
+        }
+    }
+
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(mode & 0o777);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    // Creating the final component separately keeps "already exists" distinct
+    builder.create(path).map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => FileOpError::AlreadyExists(path.display().to_string()),
+        _ => FileOpError::io("create", path, e),
+    })?;
+
+    // The umask may have narrowed the mode further than requested
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))
+            .map_err(|e| FileOpError::io("set permissions on", path, e))?;
+    }
+
+    Ok(())
+}
+
+/// Create a directory within the allowed roots
+#[tauri::command]
+pub fn create_directory(
+    window: Window,
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    path: String,
+    recursive: bool,
+    mode: Option<u32>,
+) -> Result<FileInfo, String> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let guard = CommandGuard::new(&audit, "create_directory");
+    create_directory_within(&guard, roots.inner(), &path, recursive, mode)
+}
+
+/// Create the directory `path`, which must lie within `roots`
+pub(crate) fn create_directory_within(
+    guard: &CommandGuard,
+    roots: &AllowedRoots,
+    path: &str,
+    recursive: bool,
+    mode: Option<u32>,
+) -> Result<FileInfo, String> {
+    let path = guard
+        .resolved_path(path, |path| resolve_new_dir_in_roots(path, roots))
+        .map_err(|e| e.to_string())?;
+    create_dir_with_mode(&path, recursive, mode).map_err(|e| e.to_string())?;
+    FileInfo::from_path(&path, false)
+}
+
+/// Convert seconds since the Unix epoch into a `FileTime`
+fn unix_file_time(secs: u64) -> Result<FileTime, FileOpError> {
+    i64::try_from(secs)
+        .map(|secs| FileTime::from_unix_time(secs, 0))
+        .map_err(|_| FileOpError::InvalidPath(format!("Timestamp {} is out of range", secs)))
+}
+
+/// Set the access and/or modification time of `path`, leaving a `None` time as is
+pub(crate) fn set_times(
+    path: &Path,
+    accessed: Option<u64>,
+    modified: Option<u64>,
+) -> Result<(), FileOpError> {
+    let accessed = accessed.map(unix_file_time).transpose()?;
+    let modified = modified.map(unix_file_time).transpose()?;
+    let result = match (accessed, modified) {
+        (Some(accessed), Some(modified)) => filetime::set_file_times(path, accessed, modified),
+        (Some(accessed), None) => filetime::set_file_atime(path, accessed),
+        (None, Some(modified)) => filetime::set_file_mtime(path, modified),
+        (None, None) => {
+            return Err(FileOpError::InvalidPath(
+                "Set at least one of accessed and modified".into(),
+            ))
+        }
+    };
+    result.map_err(|e| FileOpError::io("set times on", path, e))
+}
+
+/// Set a file's access and/or modification time, in seconds since the Unix epoch
+#[tauri::command]
+pub fn set_file_times(
+    window: Window,
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    path: String,
+    accessed: Option<u64>,
+    modified: Option<u64>,
+) -> Result<FileInfo, String> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let guard = CommandGuard::new(&audit, "set_file_times");
+    set_file_times_within(&guard, roots.inner(), &path, accessed, modified)
+}
+
+/// Set the times of `path`, which must lie within `roots`
+///
+/// The whole path is canonicalized, so a symlink is followed and its target
+/// must lie within the roots too.
+pub(crate) fn set_file_times_within(
+    guard: &CommandGuard,
+    roots: &AllowedRoots,
+    path: &str,
+    accessed: Option<u64>,
+    modified: Option<u64>,
+) -> Result<FileInfo, String> {
+    let path = guard.confined_path(path, roots)?;
+    set_times(&path, accessed, modified).map_err(|e| e.to_string())?;
+    FileInfo::from_path(&path, false)
+}
+
+/// Most entries a single `delete_directory` call may remove
+pub const MAX_DELETE_ENTRIES: usize = 100_000;
+
+/// A path that could not be removed or listed
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DeleteFailure {
+    /// The path that failed
+    pub path: String,
+    /// Why it failed
+    pub error: String,
+}
+
+/// Result of `delete_directory`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DeleteReport {
+    /// Whether this was a dry run, leaving the disk untouched
+    pub dry_run: bool,
+    /// Paths removed, or that would be removed in a dry run, children first
+    pub removed: Vec<String>,
+    /// Paths that could not be listed or removed
+    pub failed: Vec<DeleteFailure>,
+}
+
+impl DeleteReport {
+    fn fail(&mut self, path: &Path, error: io::Error) {
+        self.failed.push(DeleteFailure {
+            path: path.display().to_string(),
+            error: error.to_string(),
+        });
+    }
+}
+
+/// A path scheduled for removal, as (path, is directory, size in bytes)
+type PlannedRemoval = (PathBuf, bool, u64);
+
+/// Collect everything under `dir`, children before their parent
+///
+/// Symlinks are collected as links and never followed. Directories that can't
+/// be listed are recorded as failures, and so are skipped along with their
+/// parents.
+fn plan_removal(
+    dir: &Path,
+    plan: &mut Vec<PlannedRemoval>,
+    report: &mut DeleteReport,
+) -> Result<(), FileOpError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            report.fail(dir, e);
+            return Ok(());
+        }
+    };
+
+    for entry in entries {
+        // `DirEntry::metadata` doesn't follow symlinks
+        let (path, metadata) = match entry.and_then(|e| Ok((e.path(), e.metadata()?))) {
+            Ok(entry) => entry,
+            Err(e) => {
+                report.fail(dir, e);
+                continue;
+            }
+        };
+        let is_dir = metadata.is_dir();
+        if is_dir {
+            plan_removal(&path, plan, report)?;
+        }
+        if plan.len() >= MAX_DELETE_ENTRIES {
+            return Err(FileOpError::InvalidPath(format!(
+                "More than {} entries under {}",
+                MAX_DELETE_ENTRIES,
+                dir.display()
+            )));
+        }
+        let size = if metadata.is_file() {
+            metadata.len()
+        } else {
+            0
+        };
+        plan.push((path, is_dir, size));
+    }
+    Ok(())
+}
+
+/// Delete the directory `dir`, or list what would be deleted when `dry_run` is set
+///
+/// Failures on individual paths are collected rather than aborting, so one
+/// locked file doesn't leave the rest of the tree behind. Progress counts the
+/// bytes held by the files removed so far.
+pub(crate) fn delete_tree(
+    dir: &Path,
+    recursive: bool,
+    dry_run: bool,
+    progress: &mut ProgressReporter,
+) -> Result<DeleteReport, FileOpError> {
+    let mut report = DeleteReport {
+        dry_run,
+        ..DeleteReport::default()
+    };
+    let mut plan = Vec::new();
+    if recursive {
+        plan_removal(dir, &mut plan, &mut report)?;
+    } else if fs::read_dir(dir)
+        .map_err(|e| FileOpError::io("read", dir, e))?
+        .next()
+        .is_some()
+    {
+        return Err(FileOpError::NotEmpty(dir.display().to_string()));
+    }
+    plan.push((dir.to_path_buf(), true, 0));
+    progress.set_total(plan.iter().map(|(_, _, size)| size).sum());
+
+    let policy = RetryPolicy::default();
+    let mut bytes_done = 0;
+    for (path, is_dir, size) in plan {
+        if progress.is_cancelled() {
+            return Err(FileOpError::Cancelled(dir.display().to_string()));
+        }
+        if !dry_run {
+            let removed = retry(&policy, || {
+                if is_dir {
+                    fs::remove_dir(&path)
+                } else {
+                    fs::remove_file(&path)
+                }
+            });
+            if let Err(e) = removed {
+                report.fail(&path, e);
+                continue;
+            }
+        }
+        bytes_done += size;
+        progress.report(bytes_done, &path);
+        report.removed.push(path.display().to_string());
+    }
+    progress.finish(bytes_done, dir);
+    Ok(report)
+}
+
+/// Delete a directory within the allowed roots in the background, optionally as a dry run
+///
+/// Returns an operation id right away; progress arrives in `file-progress`
+/// events and the `DeleteReport` in `file-operation-complete`.
+#[tauri::command]
+pub fn delete_directory(
+    app: AppHandle,
+    window: Window,
+    roots: State<'_, AllowedRoots>,
+    path: String,
+    recursive: bool,
+    dry_run: bool,
+) -> Result<String, String> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let audit = app.state::<AuditLog>();
+    let guard = CommandGuard::new(&audit, "delete_directory");
+    let dir = resolve_delete_target(&guard, roots.inner(), &path)?;
+    Ok(spawn_operation(app, move |progress| {
+        outcome_of(delete_tree(&dir, recursive, dry_run, progress))
+    }))
+}
+
+/// Resolve a directory to delete, which must lie within `roots` without being
+/// or containing one
+fn resolve_delete_target(
+    guard: &CommandGuard,
+    roots: &AllowedRoots,
+    path: &str,
+) -> Result<PathBuf, String> {
+    let dir = guard
+        .resolved_path(path, |path| resolve_in_roots(path, roots))
+        .map_err(|e| e.to_string())?;
+    let metadata =
+        fs::symlink_metadata(&dir).map_err(|e| FileOpError::io("read", &dir, e).to_string())?;
+    if !metadata.is_dir() {
+        return Err(
+            FileOpError::InvalidPath(format!("Not a directory: {}", dir.display())).to_string(),
+        );
+    }
+    if let Some(root) = roots
+        .roots()
+        .into_iter()
+        .find(|root| root.starts_with(&dir))
+    {
+        return Err(FileOpError::InvalidPath(format!(
+            "Refusing to delete an allowed root: {}",
+            root.display()
+        ))
+        .to_string());
+    }
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::file_system::file_info;
+    use crate::utils::fs_backend::RealFs;
+    use crate::utils::guard::TestAudit;
+    use std::time::Duration;
+
+    fn path_string(path: &Path) -> String {
+        path.to_string_lossy().into_owned()
+    }
+
+    fn roots_at(root: &Path) -> AllowedRoots {
+        let roots = AllowedRoots::new();
+        roots.add(root).unwrap();
+        roots
+    }
+
+    fn copy_within(
+        roots: &AllowedRoots,
+        src: &str,
+        dest: &str,
+        overwrite: bool,
+    ) -> Result<FileInfo, String> {
+        let audit = TestAudit::new();
+        let (src, dest) = resolve_copy_paths(&audit.guard(), roots, src, dest)?;
+        copy_resolved(&src, &dest, overwrite, &mut ProgressReporter::silent())
+            .map_err(|e| e.to_string())
+    }
+
+    fn delete_directory_within(
+        roots: &AllowedRoots,
+        path: &str,
+        recursive: bool,
+        dry_run: bool,
+    ) -> Result<DeleteReport, String> {
+        let audit = TestAudit::new();
+        let dir = resolve_delete_target(&audit.guard(), roots, path)?;
+        delete_tree(&dir, recursive, dry_run, &mut ProgressReporter::silent())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_copy_preserves_contents_and_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("a.txt");
+        fs::write(&src, "hello").unwrap();
+        let mtime = FileTime::from_unix_time(1_600_000_000, 0);
+        filetime::set_file_mtime(&src, mtime).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&src, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+
+        let roots = roots_at(dir.path());
+        let dest = dir.path().join("b.txt");
+        let info = copy_within(&roots, &path_string(&src), &path_string(&dest), false).unwrap();
+
+        assert_eq!(info.size, 5);
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "hello");
+        assert_eq!(fs::read_to_string(&src).unwrap(), "hello");
+        let copied = fs::metadata(&dest).unwrap();
+        assert_eq!(FileTime::from_last_modification_time(&copied), mtime);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(copied.permissions().mode() & 0o7777, 0o640);
+        }
+    }
+
+    #[test]
+    fn test_rename_across_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("from")).unwrap();
+        fs::create_dir(dir.path().join("to")).unwrap();
+        let src = dir.path().join("from").join("report.txt");
+        fs::write(&src, "data").unwrap();
+
+        let roots = roots_at(dir.path());
+
+        let audit = TestAudit::new();
+
+        let guard = audit.guard();
+        let dest = dir.path().join("to").join("report.txt");
+        rename_within(
+            &guard,
+            &roots,
+            &path_string(&src),
+            &path_string(&dest),
+            false,
+        )
+        .unwrap();
+
+        assert!(!src.exists());
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "data");
+    }
+
+    #[test]
+    fn test_blocked_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("new.txt");
+        let dest = dir.path().join("existing.txt");
+        fs::write(&src, "new").unwrap();
+        fs::write(&dest, "old").unwrap();
+        let roots = roots_at(dir.path());
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let (src_raw, dest_raw) = (path_string(&src), path_string(&dest));
+
+        let err = copy_within(&roots, &src_raw, &dest_raw, false).unwrap_err();
+        assert!(err.starts_with("AlreadyExists"));
+        let err = rename_within(&guard, &roots, &src_raw, &dest_raw, false).unwrap_err();
+        assert!(err.starts_with("AlreadyExists"));
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "old");
+        assert!(src.exists());
+
+        copy_within(&roots, &src_raw, &dest_raw, true).unwrap();
+        assert_eq!(fs::read_to_string(&dest).unwrap(), "new");
+        // No staging files are left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_rename_never_replaces_existing_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dest = dir.path().join("dest");
+        fs::create_dir(&src).unwrap();
+        fs::write(src.join("a.txt"), "a").unwrap();
+        // A plain rename would silently replace an empty directory
+        fs::create_dir(&dest).unwrap();
+        let roots = roots_at(dir.path());
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let (src_raw, dest_raw) = (path_string(&src), path_string(&dest));
+
+        let err = rename_within(&guard, &roots, &src_raw, &dest_raw, false).unwrap_err();
+        assert!(err.starts_with("AlreadyExists"), "{}", err);
+        assert!(src.join("a.txt").exists());
+        assert!(dest.is_dir());
+
+        fs::remove_dir(&dest).unwrap();
+        rename_within(&guard, &roots, &src_raw, &dest_raw, false).unwrap();
+        assert!(!src.exists());
+        assert_eq!(fs::read_to_string(dest.join("a.txt")).unwrap(), "a");
+    }
+
+    #[test]
+    fn test_cross_filesystem_rename_falls_back_to_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("big.bin");
+        let dest = dir.path().join("moved.bin");
+        fs::write(&src, [7u8; 1024]).unwrap();
+
+        let cross_device =
+            |_: &Path, _: &Path| Err(io::Error::from_raw_os_error(CROSS_DEVICE_ERROR.unwrap()));
+        rename_with(&src, &dest, false, cross_device).unwrap();
+
+        assert!(!src.exists());
+        assert_eq!(fs::read(&dest).unwrap(), vec![7u8; 1024]);
+    }
+
+    #[test]
+    fn test_copy_reports_monotonic_progress_to_completion() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("large.bin");
+        let size = COPY_CHUNK_SIZE as u64 * 5 + 123;
+        fs::write(&src, vec![3u8; size as usize]).unwrap();
+        let roots = roots_at(dir.path());
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let dest = path_string(&dir.path().join("copy.bin"));
+
+        let mut events = Vec::new();
+        let mut progress = ProgressReporter::new("copy-1", |event| events.push(event))
+            .with_interval(Duration::ZERO);
+        let (src, dest) = resolve_copy_paths(&guard, &roots, &path_string(&src), &dest).unwrap();
+        copy_resolved(&src, &dest, false, &mut progress).unwrap();
+        drop(progress);
+
+        assert!(events.len() > 1);
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].bytes_done < pair[1].bytes_done));
+        assert!(events.iter().all(|e| e.bytes_total == size));
+        let last = events.last().unwrap();
+        assert_eq!(
+            (last.operation_id.as_str(), last.bytes_done),
+            ("copy-1", size)
+        );
+    }
+
+    #[test]
+    fn test_rejects_paths_outside_roots() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let src = outside.path().join("secret.txt");
+        fs::write(&src, "x").unwrap();
+
+        let roots = roots_at(root.path());
+
+        let audit = TestAudit::new();
+
+        let guard = audit.guard();
+        let dest = path_string(&root.path().join("copy.txt"));
+        assert!(copy_within(&roots, &path_string(&src), &dest, false).is_err());
+        assert!(rename_within(&guard, &roots, "../../etc/passwd", &dest, false).is_err());
+        assert!(!Path::new(&dest).exists());
+    }
+
+    #[test]
+    fn test_create_single_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = roots_at(dir.path());
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let target = dir.path().join("cache");
+
+        create_directory_within(&guard, &roots, &path_string(&target), false, Some(0o700)).unwrap();
+        assert!(target.is_dir());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&target).unwrap().permissions().mode();
+            assert_eq!(mode & 0o7777, 0o700);
+
+            // Special bits are never applied
+            let special = dir.path().join("shared");
+            create_directory_within(&guard, &roots, &path_string(&special), false, Some(0o4750))
+                .unwrap();
+            let mode = fs::metadata(&special).unwrap().permissions().mode();
+            assert_eq!(mode & 0o7777, 0o750);
+        }
+
+        // Without `recursive`, missing parents are an error
+        let nested = dir.path().join("missing").join("child");
+        let err = create_directory_within(&guard, &roots, &path_string(&nested), false, None)
+            .unwrap_err();
+        assert!(err.starts_with("Io"));
+    }
+
+    #[test]
+    fn test_create_nested_directories_recursively() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = roots_at(dir.path());
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let target = dir.path().join("a").join("b").join("c");
+
+        create_directory_within(&guard, &roots, &path_string(&target), true, None).unwrap();
+        assert!(target.is_dir());
+
+        let outside = tempfile::tempdir().unwrap();
+        let escaped = outside.path().join("x").join("y");
+        let err = create_directory_within(&guard, &roots, &path_string(&escaped), true, None)
+            .unwrap_err();
+        assert!(err.starts_with("InvalidPath"));
+        assert!(!outside.path().join("x").exists());
+    }
+
+    #[test]
+    fn test_invalid_new_names_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = roots_at(dir.path());
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let src = dir.path().join("a.txt");
+        fs::write(&src, "x").unwrap();
+
+        for name in ["CON", "notes:stream", "report."] {
+            let dest = dir.path().join(name);
+            let err = rename_within(
+                &guard,
+                &roots,
+                &path_string(&src),
+                &path_string(&dest),
+                false,
+            )
+            .unwrap_err();
+            assert!(err.starts_with("InvalidPath"), "{}", err);
+
+            let nested = dir.path().join(name).join("child");
+            let err = create_directory_within(&guard, &roots, &path_string(&nested), true, None)
+                .unwrap_err();
+            assert!(err.starts_with("InvalidPath"), "{}", err);
+        }
+        assert!(src.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_create_existing_directory_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = roots_at(dir.path());
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let target = dir.path().join("logs");
+        fs::create_dir(&target).unwrap();
+
+        for recursive in [false, true] {
+            let err =
+                create_directory_within(&guard, &roots, &path_string(&target), recursive, None)
+                    .unwrap_err();
+            assert!(err.starts_with("AlreadyExists"), "{}", err);
+        }
+    }
+
+    fn delete_fixture(root: &Path) -> PathBuf {
+        let tree = root.join("build");
+        fs::create_dir_all(tree.join("obj").join("debug")).unwrap();
+        fs::write(tree.join("out.bin"), "x").unwrap();
+        fs::write(tree.join("obj").join("debug").join("a.o"), "y").unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_delete_dry_run_lists_without_removing() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = roots_at(dir.path());
+        let tree = delete_fixture(dir.path());
+
+        let report = delete_directory_within(&roots, &path_string(&tree), true, true).unwrap();
+        assert!(report.dry_run);
+        assert!(report.failed.is_empty());
+        assert_eq!(report.removed.len(), 5);
+        // Children come before their parents, and the tree itself comes last
+        let position = |p: &Path| report.removed.iter().position(|r| Path::new(r) == p);
+        let debug = tree.join("obj").join("debug");
+        assert!(position(&debug.join("a.o")) < position(&debug));
+        assert_eq!(report.removed.last().map(PathBuf::from), Some(tree.clone()));
+
+        assert!(debug.join("a.o").exists());
+        let err = delete_directory_within(&roots, &path_string(&tree), false, true).unwrap_err();
+        assert!(err.starts_with("NotEmpty"));
+    }
+
+    #[test]
+    fn test_delete_recursive() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = roots_at(dir.path());
+        let tree = delete_fixture(dir.path());
+
+        let report = delete_directory_within(&roots, &path_string(&tree), true, false).unwrap();
+        assert!(!report.dry_run);
+        assert!(report.failed.is_empty());
+        assert_eq!(report.removed.len(), 5);
+        assert!(!tree.exists());
+        assert!(dir.path().exists());
+    }
+
+    #[test]
+    fn test_delete_refuses_allowed_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = roots_at(dir.path());
+        let tree = delete_fixture(dir.path());
+        roots.add(&tree).unwrap();
+
+        // A nested root can't be deleted even though its parent is allowed
+        let err = delete_directory_within(&roots, &path_string(&tree), true, true).unwrap_err();
+        assert!(
+            err.contains("Refusing to delete an allowed root"),
+            "{}",
+            err
+        );
+        let err =
+            delete_directory_within(&roots, &path_string(dir.path()), true, false).unwrap_err();
+        assert!(err.starts_with("InvalidPath"), "{}", err);
+        assert!(tree.join("out.bin").exists());
+
+        // Nor can a directory that contains one
+        let projects = dir.path().join("projects");
+        let nested = delete_fixture(&projects);
+        roots.add(&nested).unwrap();
+        let err =
+            delete_directory_within(&roots, &path_string(&projects), true, false).unwrap_err();
+        assert!(
+            err.contains("Refusing to delete an allowed root"),
+            "{}",
+            err
+        );
+        assert!(nested.join("out.bin").exists());
+    }
+
+    #[test]
+    fn test_set_modified_time_reads_back_through_file_info() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "hello").unwrap();
+        filetime::set_file_atime(&file, FileTime::from_unix_time(1_500_000_000, 0)).unwrap();
+
+        let roots = roots_at(dir.path());
+
+        let audit = TestAudit::new();
+
+        let guard = audit.guard();
+        let info = set_file_times_within(
+            &guard,
+            &roots,
+            &path_string(&file),
+            None,
+            Some(1_600_000_000),
+        )
+        .unwrap();
+        assert_eq!(info.last_modified, 1_600_000_000);
+
+        let info = file_info(&RealFs, &file, false, true).unwrap();
+        assert_eq!(info.last_modified, 1_600_000_000);
+        assert_eq!(info.accessed, 1_500_000_000);
+    }
+
+    #[test]
+    fn test_set_access_time_leaves_modified_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        fs::write(&file, "hello").unwrap();
+        filetime::set_file_mtime(&file, FileTime::from_unix_time(1_600_000_000, 0)).unwrap();
+
+        let roots = roots_at(dir.path());
+
+        let audit = TestAudit::new();
+
+        let guard = audit.guard();
+        let info = set_file_times_within(
+            &guard,
+            &roots,
+            &path_string(&file),
+            Some(1_700_000_000),
+            None,
+        )
+        .unwrap();
+        assert_eq!(info.accessed, 1_700_000_000);
+        assert_eq!(info.last_modified, 1_600_000_000);
+
+        assert!(set_file_times_within(&guard, &roots, &path_string(&file), None, None).is_err());
+        assert!(
+            set_file_times_within(&guard, &roots, &path_string(&file), Some(u64::MAX), None)
+                .is_err()
+        );
+        let outside = tempfile::tempdir().unwrap();
+        let other = outside.path().join("b.txt");
+        fs::write(&other, "x").unwrap();
+        assert!(
+            set_file_times_within(&guard, &roots, &path_string(&other), None, Some(1)).is_err()
+        );
+    }
+}
@@ -0,0 +1,2684 @@
+//! File system commands for Tauri applications
+//!
+//! This module provides file system access with security checks applied:
+//! 1. Path validation before touching the file system
+//! 2. Bounded, symlink-safe directory walks via `DirectoryWalker`
+//! 3. Errors are converted to strings suitable for the frontend
+//! 4. Listing and metadata commands go through the managed `FileSystem` backend
+//! 5. Two directory trees can be compared by metadata or by contents
+//! 6. Read commands also accept paths the calling window picked in a dialog
+//! 7. Paths that aren't valid Unicode are reported with a lossless `raw_path`,
+//!    which `get_file_info_raw` accepts back
+
+use super::allowed_roots::AllowedRoots;
+use super::audit::AuditLog;
+use super::crypto::{hash_file, HashAlgorithm};
+use super::error::AppError;
+use super::fs_backend::{FileSystem, RealFs};
+use super::guard::CommandGuard;
+use super::memory_safe::{BoundaryValidator, DEFAULT_MAX_PATH_COMPONENTS, DEFAULT_MAX_PATH_LEN};
+use super::metadata_cache::MetadataCache;
+use super::scoped_access::{check_scoped, check_scoped_entry, ScopedAccess};
+use super::walker::{DirectoryWalker, DEFAULT_MAX_ENTRIES_PER_DIR};
+use data_encoding::{BASE64URL_NOPAD, HEXLOWER};
+use glob::{MatchOptions, Pattern};
+use log::warn;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State, Window};
+use thiserror::Error;
+
+/// Number of leading bytes read when sniffing a file's content type
+const MIME_SNIFF_BYTES: u64 = 8 * 1024;
+
+/// Largest range returned by a single `read_file_range` call (16 MiB)
+pub const MAX_RANGE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Most lines returned by a single `tail_file` call
+pub const MAX_TAIL_LINES: usize = 10_000;
+
+/// Size of the blocks `tail_file` reads while scanning backwards
+const TAIL_BLOCK_BYTES: u64 = 8 * 1024;
+
+/// Information about a file or directory
+///
+/// Field names are snake_case; see `RustFileInfo` in `src/utils/typeConversion.ts`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FileInfo {
+    /// Opaque identifier for the entry, the same on every call; see `stable_file_id_in`
+    pub id: String,
+    /// File name without its parent directory
+    pub name: String,
+    /// Full path of the entry
+    pub path: String,
+    /// Whether the entry is a directory
+    pub is_directory: bool,
+    /// Size in bytes
+    pub size: u64,
+    /// Last modification time in seconds since the Unix epoch
+    pub last_modified: u64,
+    /// Last access time in seconds since the Unix epoch, or 0 if unknown
+    pub accessed: u64,
+    /// Lowercase file extension, or an empty string
+    pub file_type: String,
+    /// MIME type, when detection was requested and succeeded
+    pub mime_type: Option<String>,
+    /// Whether the entry itself is a symbolic link
+    ///
+    /// `size` and `is_directory` describe the link's target, unless symlinks
+    /// were not followed.
+    pub is_symlink: bool,
+    /// Where the link points, exactly as stored in the link
+    pub link_target: Option<String>,
+    /// Whether the entry's permissions forbid writing to it
+    pub readonly: bool,
+    /// Whether `path` is not valid Unicode and had characters replaced
+    ///
+    /// A lossy `path` may name a different file or none at all; use `raw_path`.
+    pub path_lossy: bool,
+    /// The exact path as `encode_raw_path` text, present only when `path_lossy`
+    pub raw_path: Option<String>,
+}
+
+impl FileInfo {
+    /// Read file information for `path` from the real file system
+    pub fn from_path(path: &Path, detect_mime: bool) -> Result<Self, String> {
+        Self::from_path_in(&RealFs, path, detect_mime)
+    }
+
+    /// Read file information for `path` from `fs`
+    pub fn from_path_in(
+        fs: &dyn FileSystem,
+        path: &Path,
+        detect_mime: bool,
+    ) -> Result<Self, String> {
+        Self::from_path_with(fs, path, detect_mime, true)
+    }
+
+    /// Read file information for `path` from `fs`
+    ///
+    /// Without `follow_symlinks`, a symlink is described by its own size and
+    /// type, as `lstat` would report them.
+    pub fn from_path_with(
+        fs: &dyn FileSystem,
+        path: &Path,
+        detect_mime: bool,
+        follow_symlinks: bool,
+    ) -> Result<Self, String> {
+        let read_error =
+            |e: std::io::Error| format!("Failed to read metadata for {}: {}", path.display(), e);
+        let link_metadata = fs.symlink_metadata(path).map_err(read_error)?;
+        let is_symlink = link_metadata.is_symlink;
+        let link_target = if is_symlink {
+            fs.read_link(path)
+                .ok()
+                .map(|target| target.to_string_lossy().into_owned())
+        } else {
+            None
+        };
+
+        // Describe the target, or the link itself if it is dangling or not followed
+        let metadata = if !follow_symlinks {
+            link_metadata
+        } else {
+            match fs.metadata(path) {
+                Ok(metadata) => metadata,
+                Err(_) if is_symlink => link_metadata,
+                Err(e) => return Err(read_error(e)),
+            }
+        };
+
+        let unix_secs = |time: Option<SystemTime>| {
+            time.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        };
+        let last_modified = unix_secs(metadata.modified);
+        let accessed = unix_secs(metadata.accessed);
+
+        let file_type = if metadata.is_dir {
+            String::new()
+        } else {
+            path.extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default()
+        };
+
+        let mime_type = if detect_mime && metadata.is_file {
+            detect_mime_type(fs, path)
+        } else {
+            None
+        };
+
+        let path_string = path.to_string_lossy().into_owned();
+        let path_lossy = path.to_str().is_none();
+
+        Ok(Self {
+            id: stable_file_id_in(fs, path),
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path_string.clone()),
+            path: path_string,
+            is_directory: metadata.is_dir,
+            size: metadata.len,
+            last_modified,
+            accessed,
+            file_type,
+            mime_type,
+            is_symlink,
+            link_target,
+            readonly: metadata.readonly,
+            path_lossy,
+            raw_path: path_lossy.then(|| encode_raw_path(path)),
+        })
+    }
+}
+
+/// Encode `path` losslessly as unpadded base64url text
+///
+/// The bytes are the path's native form: raw bytes on Unix and little-endian
+/// UTF-16 code units on Windows, so paths that aren't valid Unicode survive
+/// the trip to the frontend and back through `decode_raw_path`.
+pub fn encode_raw_path(path: &Path) -> String {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    };
+    #[cfg(windows)]
+    let bytes: Vec<u8> = {
+        use std::os::windows::ffi::OsStrExt;
+        path.as_os_str()
+            .encode_wide()
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    };
+    #[cfg(not(any(unix, windows)))]
+    let bytes = path.to_string_lossy().into_owned().into_bytes();
+    BASE64URL_NOPAD.encode(&bytes)
+}
+
+/// Decode a path produced by `encode_raw_path`
+///
+/// Empty paths and NUL are rejected even though they encode fine, since no
+/// OS path can hold them.
+pub fn decode_raw_path(raw: &str) -> Result<PathBuf, AppError> {
+    let invalid = |reason: &str| AppError::Validation(format!("Invalid raw path: {}", reason));
+    let bytes = BASE64URL_NOPAD
+        .decode(raw.as_bytes())
+        .map_err(|e| invalid(&e.to_string()))?;
+
+    #[cfg(unix)]
+    let (path, has_nul) = {
+        use std::os::unix::ffi::OsStringExt;
+        let has_nul = bytes.contains(&0);
+        (std::ffi::OsString::from_vec(bytes), has_nul)
+    };
+    #[cfg(windows)]
+    let (path, has_nul) = {
+        use std::os::windows::ffi::OsStringExt;
+        if bytes.len() % 2 != 0 {
+            return Err(invalid("odd number of bytes"));
+        }
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        (std::ffi::OsString::from_wide(&units), units.contains(&0))
+    };
+    #[cfg(not(any(unix, windows)))]
+    let (path, has_nul) = {
+        let text = String::from_utf8(bytes).map_err(|e| invalid(&e.to_string()))?;
+        let has_nul = text.contains('\0');
+        (std::ffi::OsString::from(text), has_nul)
+    };
+
+    if path.is_empty() || has_nul {
+        return Err(invalid("empty or contains NUL"));
+    }
+    Ok(PathBuf::from(path))
+}
+
+/// Length of a stable file id in bytes, before hex encoding
+const FILE_ID_BYTES: usize = 16;
+
+/// Context string separating file ids from other BLAKE3 uses
+const FILE_ID_CONTEXT: &str = "tauri-security-boilerplate 2024 stable file id";
+
+/// The canonical path of the entry at `path`, without following a final symlink
+///
+/// A symlink and its target are different entries, so only the parent is
+/// resolved.
+pub(crate) fn canonical_entry_path(fs: &dyn FileSystem, path: &Path) -> Option<PathBuf> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            fs.canonicalize(parent).ok().map(|p| p.join(name))
+        }
+        _ => fs.canonicalize(path).ok(),
+    }
+}
+
+/// An opaque identifier for the entry at `path` in `fs`, stable across calls
+///
+/// The id is a hash of the entry's canonical path, so every way of reaching
+/// the same entry yields the same id, and a symlink's id differs from its
+/// target's. The inode is deliberately left out: editors that save by
+/// writing a new file and renaming it would otherwise change the id.
+pub(crate) fn stable_file_id_in(fs: &dyn FileSystem, path: &Path) -> String {
+    let canonical = canonical_entry_path(fs, path).unwrap_or_else(|| path.to_path_buf());
+    let mut hasher = blake3::Hasher::new_derive_key(FILE_ID_CONTEXT);
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        hasher.update(canonical.as_os_str().as_bytes());
+    }
+    #[cfg(not(unix))]
+    hasher.update(canonical.to_string_lossy().as_bytes());
+    HEXLOWER.encode(&hasher.finalize().as_bytes()[..FILE_ID_BYTES])
+}
+
+/// Detect a file's MIME type from its content, falling back to its extension
+pub(crate) fn detect_mime_type(fs: &dyn FileSystem, path: &Path) -> Option<String> {
+    let buffer = fs.read_head(path, MIME_SNIFF_BYTES).unwrap_or_default();
+
+    infer::get(&buffer)
+        .map(|kind| kind.mime_type().to_string())
+        .or_else(|| mime_guess::from_path(path).first_raw().map(str::to_string))
+}
+
+/// Validate a path received from the frontend and ensure it is a directory
+///
+/// Only traversal is refused; callers confine the result to the allowed roots.
+pub(crate) fn validated_dir(dir_path: &str) -> Result<PathBuf, String> {
+    validated_dir_in(&RealFs, dir_path)
+}
+
+/// Validate a path and ensure it is a directory in `fs`
+pub(crate) fn validated_dir_in(fs: &dyn FileSystem, dir_path: &str) -> Result<PathBuf, String> {
+    if !BoundaryValidator::validate_confined_path(dir_path) {
+        return Err("Invalid path detected".into());
+    }
+
+    let path = PathBuf::from(dir_path);
+    ensure_dir_in(fs, &path)?;
+    Ok(path)
+}
+
+/// Ensure `dir` is a directory in `fs`
+pub(crate) fn ensure_dir_in(fs: &dyn FileSystem, dir: &Path) -> Result<(), String> {
+    if !fs.metadata(dir).map(|m| m.is_dir).unwrap_or(false) {
+        return Err(format!("Not a directory: {}", dir.display()));
+    }
+    Ok(())
+}
+
+/// Validate a path received from the frontend and ensure it exists
+///
+/// Only traversal is refused; callers confine the result to the allowed roots.
+pub(crate) fn validated_path(file_path: &str) -> Result<PathBuf, String> {
+    validated_path_in(&RealFs, file_path)
+}
+
+/// Validate a path and ensure it exists in `fs`
+pub(crate) fn validated_path_in(fs: &dyn FileSystem, file_path: &str) -> Result<PathBuf, String> {
+    if !BoundaryValidator::validate_confined_path(file_path) {
+        return Err("Invalid path detected".into());
+    }
+
+    let path = PathBuf::from(file_path);
+    ensure_exists_in(fs, &path)?;
+    Ok(path)
+}
+
+/// Ensure `path` exists in `fs`
+pub(crate) fn ensure_exists_in(fs: &dyn FileSystem, path: &Path) -> Result<(), String> {
+    if fs.metadata(path).is_err() {
+        return Err(format!("Path does not exist: {}", path.display()));
+    }
+    Ok(())
+}
+
+/// Validate `raw` through `guard` and resolve it to a directory within `roots`
+fn confined_dir(guard: &CommandGuard, roots: &AllowedRoots, raw: &str) -> Result<PathBuf, String> {
+    guard.resolved_path(raw, |path| -> Result<PathBuf, String> {
+        let dir = roots.check(path)?;
+        ensure_dir_in(&RealFs, &dir)?;
+        Ok(dir)
+    })
+}
+
+/// Get information about a single file or directory
+///
+/// Symlinks are followed unless `follow_symlinks` is `false`, in which case
+/// the link itself is described. Served from the `MetadataCache` when one is
+/// managed.
+#[tauri::command]
+pub fn get_file_info(
+    fs: State<'_, Arc<dyn FileSystem>>,
+    roots: State<'_, AllowedRoots>,
+    scope: State<'_, ScopedAccess>,
+    audit: State<'_, AuditLog>,
+    window: Window,
+    file_path: String,
+    detect_mime: bool,
+    follow_symlinks: Option<bool>,
+) -> Result<FileInfo, String> {
+    scoped_file_info(
+        &CommandGuard::new(&audit, "get_file_info"),
+        fs.inner().as_ref(),
+        &roots,
+        &scope,
+        window.label(),
+        window.try_state::<MetadataCache>().as_deref(),
+        Path::new(&file_path),
+        detect_mime,
+        follow_symlinks.unwrap_or(true),
+    )
+}
+
+/// Get file information for a path given as `raw_path` text
+///
+/// Use this for entries reported with `path_lossy`, whose `path` can't be
+/// passed back to `get_file_info`.
+#[tauri::command]
+pub fn get_file_info_raw(
+    fs: State<'_, Arc<dyn FileSystem>>,
+    roots: State<'_, AllowedRoots>,
+    scope: State<'_, ScopedAccess>,
+    audit: State<'_, AuditLog>,
+    window: Window,
+    raw_path: String,
+    detect_mime: bool,
+    follow_symlinks: Option<bool>,
+) -> Result<FileInfo, String> {
+    let path = decode_raw_path(&raw_path)?;
+    scoped_file_info(
+        &CommandGuard::new(&audit, "get_file_info_raw"),
+        fs.inner().as_ref(),
+        &roots,
+        &scope,
+        window.label(),
+        window.try_state::<MetadataCache>().as_deref(),
+        &path,
+        detect_mime,
+        follow_symlinks.unwrap_or(true),
+    )
+}
+
+/// Check `file_path` for the window labelled `label` and read its information
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn scoped_file_info(
+    guard: &CommandGuard,
+    fs: &dyn FileSystem,
+    roots: &AllowedRoots,
+    scope: &ScopedAccess,
+    label: &str,
+    cache: Option<&MetadataCache>,
+    file_path: &Path,
+    detect_mime: bool,
+    follow_symlinks: bool,
+) -> Result<FileInfo, String> {
+    // Read through the checked path, so a link swapped in afterwards is ignored
+    let path = guard.resolved_os_path(file_path, |file_path| {
+        if follow_symlinks {
+            check_scoped(roots, scope, label, file_path)
+        } else {
+            check_scoped_entry(roots, scope, label, file_path)
+        }
+    })?;
+    match cache {
+        Some(cache) => cache.file_info(fs, &path, detect_mime, follow_symlinks),
+        None => file_info(fs, &path, detect_mime, follow_symlinks),
+    }
+}
+
+/// Read the information of an already validated `path` from `fs`
+pub(crate) fn file_info(
+    fs: &dyn FileSystem,
+    path: &Path,
+    detect_mime: bool,
+    follow_symlinks: bool,
+) -> Result<FileInfo, String> {
+    // A dangling link still exists as an entry of its own
+    let stat = if follow_symlinks {
+        fs.metadata(path)
+    } else {
+        fs.symlink_metadata(path)
+    };
+    if stat.is_err() {
+        return Err(format!("Path does not exist: {}", path.display()));
+    }
+    FileInfo::from_path_with(fs, path, detect_mime, follow_symlinks)
+}
+
+/// Most paths accepted by a single `get_file_info_batch` call
+pub const MAX_BATCH_PATHS: usize = 10_000;
+
+/// Most metadata lookups a batch runs at once, bounding open file descriptors
+pub const MAX_BATCH_CONCURRENCY: usize = 8;
+
+/// Read the information of one path for the window labelled `label`, classifying failures
+fn classified_file_info(
+    guard: &CommandGuard,
+    fs: &dyn FileSystem,
+    roots: &AllowedRoots,
+    scope: &ScopedAccess,
+    label: &str,
+    file_path: &str,
+) -> Result<FileInfo, AppError> {
+    let path = guard.scoped_path(file_path, roots, scope, label)?;
+    match fs.metadata(&path) {
+        Ok(_) => FileInfo::from_path_in(fs, &path, false).map_err(AppError::Io),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(AppError::NotFound(file_path.to_string()))
+        }
+        Err(e) => Err(AppError::Io(format!("Failed to read {}: {}", file_path, e))),
+    }
+}
+
+/// Read the information of every path, using at most `concurrency` threads
+///
+/// Workers claim the next unread index, so results come back in input order
+/// no matter which lookups finish first.
+pub(crate) fn file_info_batch(
+    guard: &CommandGuard,
+    fs: &dyn FileSystem,
+    roots: &AllowedRoots,
+    scope: &ScopedAccess,
+    label: &str,
+    paths: &[String],
+    concurrency: usize,
+) -> Vec<Result<FileInfo, AppError>> {
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<Result<FileInfo, AppError>>>> =
+        paths.iter().map(|_| Mutex::new(None)).collect();
+
+    let workers = concurrency.clamp(1, paths.len().max(1));
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = paths.get(index) else {
+                    break;
+                };
+                let result = classified_file_info(guard, fs, roots, scope, label, path);
+                *results[index].lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|slot| {
+            slot.into_inner()
+                .unwrap_or_else(|e| e.into_inner())
+                .unwrap_or_else(|| Err(AppError::Io("Lookup did not complete".into())))
+        })
+        .collect()
+}
+
+/// Get information about many files in one call
+///
+/// Each path gets its own result, so one missing file doesn't fail the batch.
+/// Lookups run off the IPC thread, at most `MAX_BATCH_CONCURRENCY` at a time.
+#[tauri::command]
+pub async fn get_file_info_batch(
+    app: AppHandle,
+    window: Window,
+    fs: State<'_, Arc<dyn FileSystem>>,
+    paths: Vec<String>,
+) -> Result<Vec<Result<FileInfo, AppError>>, AppError> {
+    if paths.len() > MAX_BATCH_PATHS {
+        return Err(AppError::Validation(format!(
+            "Batch exceeds the maximum of {} paths",
+            MAX_BATCH_PATHS
+        )));
+    }
+
+    let fs = Arc::clone(fs.inner());
+    let label = window.label().to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        let roots = app.state::<AllowedRoots>();
+        let scope = app.state::<ScopedAccess>();
+        let audit = app.state::<AuditLog>();
+        file_info_batch(
+            &CommandGuard::new(&audit, "get_file_info_batch"),
+            fs.as_ref(),
+            roots.inner(),
+            scope.inner(),
+            &label,
+            &paths,
+            MAX_BATCH_CONCURRENCY,
+        )
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Batch lookup failed: {}", e)))
+}
+
+/// Longest timeout accepted by `get_file_info_timeout` (5 minutes)
+pub const MAX_LOOKUP_TIMEOUT_MS: u64 = 5 * 60 * 1000;
+
+/// Most timed lookup threads that may be alive at once
+///
+/// A thread stuck on a hung mount can't be killed, only abandoned. Capping
+/// the number alive stops repeated timeouts from piling up threads.
+pub const MAX_TIMED_LOOKUPS: usize = 16;
+
+/// Number of timed lookup threads currently alive, finished or not
+static TIMED_LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+
+/// Decrements `TIMED_LOOKUPS` when a lookup thread exits
+struct TimedLookupSlot;
+
+impl Drop for TimedLookupSlot {
+    fn drop(&mut self) {
+        TIMED_LOOKUPS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Run `lookup` on its own thread, giving up with `Timeout` after `timeout`
+///
+/// On timeout the thread is detached; it exits as soon as the blocked call
+/// returns and its result is discarded.
+pub(crate) fn run_with_timeout<T, F>(timeout: Duration, lookup: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    if TIMED_LOOKUPS.fetch_add(1, Ordering::SeqCst) >= MAX_TIMED_LOOKUPS {
+        TIMED_LOOKUPS.fetch_sub(1, Ordering::SeqCst);
+        return Err(AppError::Timeout(
+            "Too many lookups are still waiting on the file system".into(),
+        ));
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let spawned = thread::Builder::new()
+        .name("timed-file-lookup".into())
+        .spawn(move || {
+            let _slot = TimedLookupSlot;
+            // The receiver is gone if we timed out, which is fine
+            let _ = sender.send(lookup());
+        });
+    if let Err(e) = spawned {
+        TIMED_LOOKUPS.fetch_sub(1, Ordering::SeqCst);
+        return Err(AppError::Io(format!("Failed to start lookup: {}", e)));
+    }
+
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(AppError::Timeout(format!(
+            "Lookup did not finish within {} ms",
+            timeout.as_millis()
+        ))),
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            Err(AppError::Io("Lookup thread panicked".into()))
+        }
+    }
+}
+
+/// Get information about a file, giving up after `timeout_ms`
+///
+/// Useful for paths on network mounts, where `metadata` can hang for minutes.
+/// `timeout_ms` is capped at `MAX_LOOKUP_TIMEOUT_MS`.
+#[tauri::command]
+pub async fn get_file_info_timeout(
+    app: AppHandle,
+    window: Window,
+    fs: State<'_, Arc<dyn FileSystem>>,
+    file_path: String,
+    timeout_ms: u64,
+) -> Result<FileInfo, AppError> {
+    let fs = Arc::clone(fs.inner());
+    let timeout = Duration::from_millis(timeout_ms.clamp(1, MAX_LOOKUP_TIMEOUT_MS));
+    let label = window.label().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        run_with_timeout(timeout, move || {
+            // Resolving the allowed roots touches the disk too, so it is timed as well
+            let roots = app.state::<AllowedRoots>();
+            let scope = app.state::<ScopedAccess>();
+            let audit = app.state::<AuditLog>();
+            classified_file_info(
+                &CommandGuard::new(&audit, "get_file_info_timeout"),
+                fs.as_ref(),
+                roots.inner(),
+                scope.inner(),
+                &label,
+                &file_path,
+            )
+        })
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Lookup failed: {}", e)))?
+}
+
+/// Remove the Windows verbatim prefix (`\\?\`) that `canonicalize` adds
+///
+/// `\\?\C:\dir` becomes `C:\dir` and `\\?\UNC\server\share` becomes
+/// `\\server\share`; other paths are returned unchanged.
+fn strip_verbatim_prefix(path: &str) -> String {
+    if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", rest)
+    } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+        rest.to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+/// Resolve `.`, `..` and symlinks in a path and return its absolute form
+///
+/// Unlike most commands this accepts `..`, since resolving it is the point;
+/// nothing is read beyond the path's metadata.
+#[tauri::command]
+pub fn canonicalize_path(
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    path: String,
+) -> Result<String, AppError> {
+    let guard = CommandGuard::new(&audit, "canonicalize_path");
+    canonical_path_in(&guard, roots.inner(), &path)
+}
+
+/// Resolve `path` to its absolute form, provided it lies within `roots`
+///
+/// The roots are checked through the deepest existing ancestor before the
+/// path itself is resolved, so a path outside them fails with the same
+/// `Authorization` error whether or not it exists.
+pub(crate) fn canonical_path_in(
+    guard: &CommandGuard,
+    roots: &AllowedRoots,
+    path: &str,
+) -> Result<String, AppError> {
+    let denied = || AppError::Authorization(format!("Path is outside the allowed roots: {}", path));
+    guard.resolvable_path(path, |checked| {
+        let existing = checked
+            .ancestors()
+            .map(|ancestor| {
+                if ancestor.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    ancestor
+                }
+            })
+            .find(|ancestor| fs::symlink_metadata(ancestor).is_ok());
+        match existing {
+            Some(ancestor) if roots.is_allowed(ancestor) => {}
+            _ => return Err(denied()),
+        }
+
+        let canonical = canonical_path(path)?;
+        roots.check(Path::new(&canonical)).map_err(|_| denied())?;
+        Ok(canonical)
+    })
+}
+
+/// Reject NUL bytes and paths beyond the default length and depth limits
+fn check_path_input(path: &str) -> Result<(), AppError> {
+    if path.contains('\0') {
+        return Err(AppError::Validation("Null byte detected".into()));
+    }
+    BoundaryValidator::validate_path_limits(path, DEFAULT_MAX_PATH_LEN, DEFAULT_MAX_PATH_COMPONENTS)
+        .map_err(|e| AppError::Validation(e.to_string()))
+}
+
+/// Resolve `path` to its absolute form without consulting the allowed roots
+pub(crate) fn canonical_path(path: &str) -> Result<String, AppError> {
+    check_path_input(path)?;
+
+    let canonical = fs::canonicalize(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::NotFound(path.to_string()),
+        _ => AppError::Io(format!("Failed to resolve {}: {}", path, e)),
+    })?;
+
+    let canonical = canonical.to_string_lossy();
+    Ok(if cfg!(windows) {
+        strip_verbatim_prefix(&canonical)
+    } else {
+        canonical.into_owned()
+    })
+}
+
+/// Express a path relative to a base directory when it lies under it
+///
+/// Both paths are canonicalized first, so symlinks on either side don't
+/// hide the relationship. A path outside the base is returned in its
+/// absolute canonical form, and the base itself is `.`.
+#[tauri::command]
+pub fn relativize_path(
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    path: String,
+    base: String,
+) -> Result<String, AppError> {
+    let guard = CommandGuard::new(&audit, "relativize_path");
+    relativize_in(&guard, roots.inner(), &path, &base)
+}
+
+/// Canonicalize `path` and `base` within `roots`, then relativize
+pub(crate) fn relativize_in(
+    guard: &CommandGuard,
+    roots: &AllowedRoots,
+    path: &str,
+    base: &str,
+) -> Result<String, AppError> {
+    let path = canonical_path_in(guard, roots, path)?;
+    let base = canonical_path_in(guard, roots, base)?;
+    Ok(relative_to(path, &base))
+}
+
+/// Strip the canonical `base` from the canonical `path`, if it is a prefix
+pub(crate) fn relative_to(path: String, base: &str) -> String {
+    match Path::new(&path).strip_prefix(base) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Ok(relative) => relative.to_string_lossy().into_owned(),
+        Err(_) => path,
+    }
+}
+
+/// What a path refers to, as reported by `path_kind`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathKind {
+    /// A regular file
+    File,
+    /// A directory
+    Directory,
+    /// A symbolic link, which is not followed
+    Symlink,
+    /// Nothing exists at the path
+    Missing,
+    /// Something else, such as a socket or device
+    Other,
+    /// The path lies outside the allowed roots, so it was not inspected
+    OutsideRoots,
+}
+
+/// Classify a path without failing when it doesn't exist
+#[tauri::command]
+pub fn path_kind(
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    path: String,
+) -> Result<PathKind, AppError> {
+    let guard = CommandGuard::new(&audit, "path_kind");
+    guard.resolved_path(&path, |path| classify_path(roots.inner(), path))
+}
+
+/// Classify `path` from its own metadata, never following a final symlink
+///
+/// Missing paths are checked against the roots through their deepest
+/// existing ancestor, so `Missing` is only reported inside a root. Relative
+/// paths resolve against the working directory, as in `resolve_in_roots`,
+/// and only the resolved path is inspected. `path` should come from
+/// `CommandGuard::resolved_path`, which validates it first.
+pub(crate) fn classify_path(roots: &AllowedRoots, path: &Path) -> Result<PathKind, AppError> {
+    let existing = path
+        .ancestors()
+        .map(|ancestor| {
+            if ancestor.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                ancestor
+            }
+        })
+        .find(|ancestor| fs::symlink_metadata(ancestor).is_ok());
+    let Some(ancestor) = existing else {
+        return Ok(PathKind::OutsideRoots);
+    };
+    let Ok(resolved) = roots.check_entry(ancestor) else {
+        return Ok(PathKind::OutsideRoots);
+    };
+    if !path
+        .strip_prefix(ancestor)
+        .is_ok_and(|rest| rest.as_os_str().is_empty())
+    {
+        return Ok(PathKind::Missing);
+    }
+
+    let metadata = match fs::symlink_metadata(&resolved) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(PathKind::Missing),
+        Err(e) => {
+            return Err(AppError::Io(format!(
+                "Failed to read metadata for {}: {}",
+                path.display(),
+                e
+            )))
+        }
+    };
+
+    let file_type = metadata.file_type();
+    Ok(if file_type.is_symlink() {
+        PathKind::Symlink
+    } else if file_type.is_dir() {
+        PathKind::Directory
+    } else if file_type.is_file() {
+        PathKind::File
+    } else {
+        PathKind::Other
+    })
+}
+
+/// Bytes read from part of a file by `read_file_range`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FileRange {
+    /// The bytes that were read
+    pub data: Vec<u8>,
+    /// Number of bytes read, fewer than requested at the end of the file
+    pub bytes_read: u64,
+}
+
+/// Read up to `length` bytes starting at `offset`
+///
+/// `length` is capped at `MAX_RANGE_BYTES`, so large files must be read in
+/// several calls.
+#[tauri::command]
+pub fn read_file_range(
+    roots: State<'_, AllowedRoots>,
+    scope: State<'_, ScopedAccess>,
+    audit: State<'_, AuditLog>,
+    window: Window,
+    file_path: String,
+    offset: u64,
+    length: u64,
+) -> Result<FileRange, String> {
+    let guard = CommandGuard::new(&audit, "read_file_range");
+    let path = guard.scoped_path(&file_path, &roots, &scope, window.label())?;
+    read_range(&path, offset, length)
+}
+
+/// Read up to `length` bytes of `path` starting at `offset`
+pub(crate) fn read_range(path: &Path, offset: u64, length: u64) -> Result<FileRange, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+
+    let file_len = file
+        .metadata()
+        .map_err(|e| format!("Failed to read metadata for {}: {}", path.display(), e))?
+        .len();
+    if offset > file_len {
+        return Err(format!(
+            "Offset {} is beyond the end of the file ({} bytes)",
+            offset, file_len
+        ));
+    }
+
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek in {}: {}", path.display(), e))?;
+
+    // Never allocate more than the cap or what remains of the file
+    let length = length.min(MAX_RANGE_BYTES).min(file_len - offset);
+    let mut data = Vec::with_capacity(length as usize);
+    file.take(length)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    Ok(FileRange {
+        bytes_read: data.len() as u64,
+        data,
+    })
+}
+
+/// Read the last `lines` lines of a text file, such as a log
+///
+/// Lines longer than `max_line_len` bytes are truncated, so memory stays
+/// bounded even for a file that is one giant line. `lines` is capped at
+/// `MAX_TAIL_LINES`.
+#[tauri::command]
+pub fn tail_file(
+    roots: State<'_, AllowedRoots>,
+    scope: State<'_, ScopedAccess>,
+    audit: State<'_, AuditLog>,
+    window: Window,
+    file_path: String,
+    lines: usize,
+    max_line_len: usize,
+) -> Result<Vec<String>, String> {
+    let guard = CommandGuard::new(&audit, "tail_file");
+    let path = guard.scoped_path(&file_path, &roots, &scope, window.label())?;
+    tail_lines(&path, lines, max_line_len)
+}
+
+/// Read the last `lines` lines of `path`, truncating each to `max_line_len` bytes
+pub(crate) fn tail_lines(
+    path: &Path,
+    lines: usize,
+    max_line_len: usize,
+) -> Result<Vec<String>, String> {
+    let io_error = |e: std::io::Error| format!("Failed to read {}: {}", path.display(), e);
+    let mut file = File::open(path).map_err(io_error)?;
+    let file_len = file.metadata().map_err(io_error)?.len();
+    let lines = lines.min(MAX_TAIL_LINES);
+    if lines == 0 || file_len == 0 {
+        return Ok(Vec::new());
+    }
+
+    // A trailing newline ends the last line rather than starting an empty one
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::Start(file_len - 1)).map_err(io_error)?;
+    file.read_exact(&mut last).map_err(io_error)?;
+    let content_end = if last[0] == b'\n' {
+        file_len - 1
+    } else {
+        file_len
+    };
+
+    // Scan backwards block by block until enough line breaks have been seen
+    let mut block = vec![0u8; TAIL_BLOCK_BYTES as usize];
+    let mut start = 0;
+    let mut newlines = 0;
+    let mut block_end = content_end;
+    'scan: while block_end > 0 {
+        let block_start = block_end.saturating_sub(TAIL_BLOCK_BYTES);
+        let buf = &mut block[..(block_end - block_start) as usize];
+        file.seek(SeekFrom::Start(block_start)).map_err(io_error)?;
+        file.read_exact(buf).map_err(io_error)?;
+
+        for (i, byte) in buf.iter().enumerate().rev() {
+            if *byte == b'\n' {
+                newlines += 1;
+                if newlines == lines {
+                    start = block_start + i as u64 + 1;
+                    break 'scan;
+                }
+            }
+        }
+        block_end = block_start;
+    }
+
+    // Read the window forwards, keeping at most `max_line_len` bytes per line
+    file.seek(SeekFrom::Start(start)).map_err(io_error)?;
+    let mut reader = file.take(content_end - start);
+    let mut result = Vec::new();
+    let mut current = Vec::new();
+    loop {
+        let read = reader.read(&mut block).map_err(io_error)?;
+        if read == 0 {
+            break;
+        }
+        for &byte in &block[..read] {
+            if byte == b'\n' {
+                result.push(line_to_string(&current));
+                current.clear();
+            } else if current.len() < max_line_len {
+                current.push(byte);
+            }
+        }
+    }
+    result.push(line_to_string(&current));
+
+    Ok(result)
+}
+
+/// Decode a possibly truncated line, dropping a character cut off at the end
+fn line_to_string(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(line) => line.to_string(),
+        // `error_len` is `None` only when the input ends mid-character
+        Err(e) if e.error_len().is_none() => {
+            String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned()
+        }
+        Err(_) => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// An entry left out of a listing because its information couldn't be read
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SkippedEntry {
+    /// Full path of the entry
+    pub path: String,
+    /// Why the entry couldn't be read
+    pub reason: String,
+}
+
+/// A directory listing produced by `list_directory`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DirectoryListing {
+    /// Entries found, in walk order
+    pub entries: Vec<FileInfo>,
+    /// Whether any directory had more entries than the limit
+    pub truncated: bool,
+    /// Directories whose entries were only partially listed
+    pub truncated_dirs: Vec<String>,
+    /// Entries that were found but couldn't be read, in walk order
+    pub skipped: Vec<SkippedEntry>,
+}
+
+/// Walk `dir` and collect a listing, or `None` if the walk was cancelled
+pub(crate) fn build_listing(
+    fs: &dyn FileSystem,
+    dir: &Path,
+    walker: &DirectoryWalker,
+) -> Result<Option<DirectoryListing>, String> {
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+    let summary = walker.walk_in(fs, dir, |_, dir_entries| {
+        for entry in dir_entries {
+            match FileInfo::from_path_in(fs, entry, false) {
+                Ok(info) => entries.push(info),
+                Err(reason) => {
+                    warn!("Skipping unreadable entry: {}", reason);
+                    skipped.push(SkippedEntry {
+                        path: entry.to_string_lossy().into_owned(),
+                        reason,
+                    });
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    if summary.cancelled {
+        return Ok(None);
+    }
+
+    Ok(Some(DirectoryListing {
+        entries,
+        truncated: !summary.truncated_dirs.is_empty(),
+        truncated_dirs: summary.truncated_dirs,
+        skipped,
+    }))
+}
+
+/// List the contents of a directory, optionally recursively
+#[tauri::command]
+pub fn list_directory(
+    fs: State<'_, Arc<dyn FileSystem>>,
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    dir_path: String,
+    recursive: Option<bool>,
+    max_entries_per_dir: Option<usize>,
+) -> Result<DirectoryListing, String> {
+    let guard = CommandGuard::new(&audit, "list_directory");
+    let dir = guard.confined_path(&dir_path, &roots)?;
+    list_directory_in(fs.inner().as_ref(), &dir, recursive, max_entries_per_dir)
+}
+
+/// List the already validated directory `dir` from `fs`
+pub(crate) fn list_directory_in(
+    fs: &dyn FileSystem,
+    dir: &Path,
+    recursive: Option<bool>,
+    max_entries_per_dir: Option<usize>,
+) -> Result<DirectoryListing, String> {
+    ensure_dir_in(fs, dir)?;
+    let walker = DirectoryWalker::new(
+        recursive.unwrap_or(false),
+        max_entries_per_dir.unwrap_or(DEFAULT_MAX_ENTRIES_PER_DIR),
+    );
+
+    build_listing(fs, dir, &walker)?.ok_or_else(|| "Directory listing was cancelled".to_string())
+}
+
+/// Aggregate size information for a directory tree
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DirectorySize {
+    /// Total size of all files in bytes, counting hardlinked files once
+    pub total_bytes: u64,
+    /// Number of files found
+    pub file_count: u64,
+    /// Number of subdirectories found
+    pub dir_count: u64,
+    /// Subdirectories that could not be read
+    pub skipped: Vec<String>,
+}
+
+/// Compute the total size of a directory tree
+///
+/// Large trees take a while, so the walk runs off the IPC thread.
+#[tauri::command]
+pub async fn directory_size(
+    app: AppHandle,
+    dir_path: String,
+    follow_symlinks: bool,
+) -> Result<DirectorySize, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let roots = app.state::<AllowedRoots>();
+        let audit = app.state::<AuditLog>();
+        let guard = CommandGuard::new(&audit, "directory_size");
+        let dir = confined_dir(&guard, &roots, &dir_path)?;
+        measure_directory(roots.inner(), &dir, follow_symlinks)
+    })
+    .await
+    .map_err(|e| format!("Directory size failed: {}", e))?
+}
+
+/// Total the sizes of everything under `dir`
+///
+/// Followed symlinks must resolve within `roots`; ones that escape are skipped.
+pub(crate) fn measure_directory(
+    roots: &AllowedRoots,
+    dir: &Path,
+    follow_symlinks: bool,
+) -> Result<DirectorySize, String> {
+    let walker = DirectoryWalker::new(true, DEFAULT_MAX_ENTRIES_PER_DIR)
+        .with_follow_symlinks(follow_symlinks)
+        .with_confinement(roots.roots());
+
+    let mut size = DirectorySize::default();
+    #[cfg(unix)]
+    let mut seen_inodes = std::collections::HashSet::new();
+
+    let summary = walker.walk(dir, |_, entries| {
+        for path in entries {
+            let metadata = match fs::symlink_metadata(path) {
+                Ok(link) if follow_symlinks && link.file_type().is_symlink() => {
+                    match roots.check(path) {
+                        Ok(target) => fs::metadata(target),
+                        Err(_) => {
+                            warn!(
+                                "Not following {}; it leads outside the allowed roots",
+                                path.display()
+                            );
+                            continue;
+                        }
+                    }
+                }
+                other => other,
+            };
+            let metadata = match metadata {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    warn!("Skipping unreadable entry {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            if metadata.is_dir() {
+                size.dir_count += 1;
+            } else if metadata.is_file() {
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::MetadataExt;
+                    // Count each hardlinked file once
+                    if metadata.nlink() > 1 && !seen_inodes.insert((metadata.dev(), metadata.ino()))
+                    {
+                        continue;
+                    }
+                }
+                size.file_count += 1;
+                size.total_bytes += metadata.len();
+            }
+        }
+        Ok(())
+    })?;
+
+    size.skipped = summary.skipped_dirs;
+    Ok(size)
+}
+
+/// Errors produced while filtering a listing by pattern
+#[derive(Debug, Error)]
+pub enum GlobError {
+    /// The pattern could not be parsed
+    #[error("InvalidPattern: {0}")]
+    InvalidPattern(String),
+}
+
+/// Options used when matching glob patterns
+///
+/// Matching is case-insensitive on Windows and case-sensitive elsewhere,
+/// mirroring the default behaviour of each platform's file systems.
+fn glob_match_options() -> MatchOptions {
+    MatchOptions {
+        case_sensitive: !cfg!(windows),
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    }
+}
+
+/// Compile a glob pattern, reporting malformed patterns as errors
+pub fn compile_glob(pattern: &str) -> Result<Pattern, GlobError> {
+    Pattern::new(pattern).map_err(|e| GlobError::InvalidPattern(format!("{}: {}", pattern, e)))
+}
+
+/// The final component of `path` as a string, or an empty string
+fn file_name_lossy(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Express `path` relative to `base` using `/` separators
+fn relative_slash_path(base: &Path, path: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// List directory entries matching a glob pattern
+///
+/// Patterns without a path separator (`*.rs`) match entry names in the
+/// directory itself. Patterns containing `/` or `**` (`**/*.log`) walk the
+/// tree and match paths relative to `dir_path`.
+#[tauri::command]
+pub fn list_directory_glob(
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    dir_path: String,
+    pattern: String,
+) -> Result<Vec<FileInfo>, String> {
+    let guard = CommandGuard::new(&audit, "list_directory_glob");
+    let dir = confined_dir(&guard, &roots, &dir_path)?;
+    glob_listing(&dir, &pattern)
+}
+
+/// List entries of `dir` matching `pattern`
+pub(crate) fn glob_listing(dir: &Path, pattern: &str) -> Result<Vec<FileInfo>, String> {
+    let matcher = compile_glob(pattern).map_err(|e| e.to_string())?;
+    let options = glob_match_options();
+    let match_paths = pattern.contains('/') || pattern.contains("**");
+
+    let walker = DirectoryWalker::new(match_paths, DEFAULT_MAX_ENTRIES_PER_DIR);
+    let mut matches = Vec::new();
+    walker.walk(dir, |_, entries| {
+        for entry in entries {
+            let candidate = if match_paths {
+                relative_slash_path(dir, entry)
+            } else {
+                file_name_lossy(entry)
+            };
+
+            if matcher.matches_with(&candidate, options) {
+                match FileInfo::from_path(entry, false) {
+                    Ok(info) => matches.push(info),
+                    Err(e) => warn!("Skipping unreadable entry: {}", e),
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(matches)
+}
+
+/// Default number of results returned by `search_directory`
+pub const DEFAULT_SEARCH_LIMIT: usize = 100;
+
+/// Most results a single `search_directory` call may return
+pub const MAX_SEARCH_LIMIT: usize = 10_000;
+
+/// Whether `c` separates words in a file name, e.g. `my_notes.txt`
+fn is_name_separator(c: char) -> bool {
+    matches!(c, '_' | '-' | '.' | ' ')
+}
+
+/// Score `name` by where `query` occurs in it as a substring
+///
+/// Exact names rank first, then prefixes, then earlier and tighter matches.
+fn substring_score(name: &str, query: &str) -> Option<i64> {
+    let position = name.find(query)? as i64;
+    let mut score = 1000 - position * 10 - (name.len() - query.len()) as i64;
+    if position == 0 {
+        score += 500;
+    }
+    if name.len() == query.len() {
+        score += 1000;
+    }
+    Some(score)
+}
+
+/// Score `name` by matching the characters of `query` in order
+///
+/// Each matched character scores, with bonuses for runs and for starting a
+/// word and a penalty for every skipped character. Returns `None` unless all
+/// of `query` is matched.
+fn fuzzy_score(name: &str, query: &str) -> Option<i64> {
+    let name: Vec<char> = name.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+
+    for wanted in query.chars() {
+        let found = next + name[next..].iter().position(|&c| c == wanted)?;
+        score += 10;
+        match previous {
+            Some(p) if found == p + 1 => score += 15,
+            Some(p) => score -= (found - p - 1) as i64,
+            None => score -= found as i64,
+        }
+        if found == 0 || is_name_separator(name[found - 1]) {
+            score += 10;
+        }
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+/// Search names under a directory, returning the best matches first
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn search_directory(
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    dir_path: String,
+    query: String,
+    case_sensitive: bool,
+    recursive: bool,
+    fuzzy: bool,
+    limit: Option<usize>,
+) -> Result<Vec<FileInfo>, String> {
+    let guard = CommandGuard::new(&audit, "search_directory");
+    let dir = confined_dir(&guard, &roots, &dir_path)?;
+    search_names(&dir, &query, case_sensitive, recursive, fuzzy, limit)
+}
+
+/// Rank entries of `dir` whose names match `query`
+///
+/// Only the top `limit` matches are read with `FileInfo`; ties are broken by
+/// path so results are stable.
+pub(crate) fn search_names(
+    dir: &Path,
+    query: &str,
+    case_sensitive: bool,
+    recursive: bool,
+    fuzzy: bool,
+    limit: Option<usize>,
+) -> Result<Vec<FileInfo>, String> {
+    if query.is_empty() {
+        return Err("Search query must not be empty".into());
+    }
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT).min(MAX_SEARCH_LIMIT);
+    let fold = |text: &str| {
+        if case_sensitive {
+            text.to_string()
+        } else {
+            text.to_lowercase()
+        }
+    };
+    let query = fold(query);
+
+    let walker = DirectoryWalker::new(recursive, DEFAULT_MAX_ENTRIES_PER_DIR);
+    let mut scored: Vec<(i64, PathBuf)> = Vec::new();
+    walker.walk(dir, |_, entries| {
+        for entry in entries {
+            let name = fold(&file_name_lossy(entry));
+            let score = if fuzzy {
+                fuzzy_score(&name, &query)
+            } else {
+                substring_score(&name, &query)
+            };
+            if let Some(score) = score {
+                scored.push((score, entry.clone()));
+            }
+        }
+        Ok(())
+    })?;
+
+    scored.sort_by(|(a_score, a_path), (b_score, b_path)| {
+        b_score.cmp(a_score).then_with(|| a_path.cmp(b_path))
+    });
+
+    let mut results = Vec::with_capacity(limit.min(scored.len()));
+    for (_, path) in scored {
+        if results.len() == limit {
+            break;
+        }
+        match FileInfo::from_path(&path, false) {
+            Ok(info) => results.push(info),
+            Err(e) => warn!("Skipping unreadable entry: {}", e),
+        }
+    }
+    Ok(results)
+}
+
+/// Find entries whose names would collide on a case-insensitive file system
+#[tauri::command]
+pub fn find_case_collisions(
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    dir_path: String,
+    recursive: Option<bool>,
+) -> Result<Vec<Vec<String>>, String> {
+    let guard = CommandGuard::new(&audit, "find_case_collisions");
+    let dir = confined_dir(&guard, &roots, &dir_path)?;
+    case_collisions(&dir, recursive)
+}
+
+/// Group entries under `dir` whose names differ only by case
+pub(crate) fn case_collisions(
+    dir: &Path,
+    recursive: Option<bool>,
+) -> Result<Vec<Vec<String>>, String> {
+    let walker = DirectoryWalker::new(recursive.unwrap_or(false), DEFAULT_MAX_ENTRIES_PER_DIR);
+
+    let mut collisions = Vec::new();
+    walker.walk(dir, |_, entries| {
+        // Group entries of this directory whose names differ only by case
+        let mut by_folded_name: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for entry in entries {
+            by_folded_name
+                .entry(file_name_lossy(entry).to_lowercase())
+                .or_default()
+                .push(entry.to_string_lossy().into_owned());
+        }
+
+        for mut group in by_folded_name.into_values() {
+            if group.len() > 1 {
+                group.sort();
+                collisions.push(group);
+            }
+        }
+        Ok(())
+    })?;
+
+    Ok(collisions)
+}
+
+/// Why an entry present in both trees is reported as differing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffReason {
+    /// One side is a file, directory or symlink and the other is something else
+    KindMismatch,
+    /// The files have different sizes
+    Size,
+    /// The files have the same size but different modification times
+    Modified,
+    /// The files have the same size but different contents
+    Contents,
+    /// The symlinks point to different targets
+    LinkTarget,
+    /// One of the files could not be read to compare contents
+    Unreadable,
+}
+
+/// An entry present in both trees that is not identical
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DifferingEntry {
+    /// Path relative to both roots, using `/` separators
+    pub path: String,
+    /// The first difference found
+    pub reason: DiffReason,
+}
+
+/// Result of `diff_directories`
+///
+/// Paths are relative to the compared directories and use `/` separators.
+/// A directory present on one side only is listed once, without its contents.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DirectoryDiff {
+    /// Entries that exist only under `left`
+    pub only_in_left: Vec<String>,
+    /// Entries that exist only under `right`
+    pub only_in_right: Vec<String>,
+    /// Entries that exist on both sides but differ
+    pub differing: Vec<DifferingEntry>,
+    /// Number of files and symlinks that are the same on both sides
+    pub identical_count: u64,
+}
+
+/// What a path in a compared tree refers to
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TreeEntry {
+    Directory,
+    File { size: u64, last_modified: u64 },
+    Symlink(Option<PathBuf>),
+    Other,
+}
+
+/// Walk `root` once, keyed by slash-separated path relative to `root`
+///
+/// Symlinks are recorded, not followed. A tree that can only be partially
+/// listed is an error, since its missing entries would show up as removals.
+fn collect_tree(root: &Path) -> Result<BTreeMap<String, (PathBuf, TreeEntry)>, String> {
+    let walker = DirectoryWalker::new(true, DEFAULT_MAX_ENTRIES_PER_DIR);
+    let mut tree = BTreeMap::new();
+    let summary = walker.walk(root, |_, entries| {
+        for path in entries {
+            let metadata = fs::symlink_metadata(path)
+                .map_err(|e| format!("Failed to read metadata for {}: {}", path.display(), e))?;
+            let entry = if metadata.file_type().is_symlink() {
+                TreeEntry::Symlink(fs::read_link(path).ok())
+            } else if metadata.is_dir() {
+                TreeEntry::Directory
+            } else if metadata.is_file() {
+                TreeEntry::File {
+                    size: metadata.len(),
+                    last_modified: metadata
+                        .modified()
+                        .ok()
+                        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0),
+                }
+            } else {
+                TreeEntry::Other
+            };
+            tree.insert(relative_slash_path(root, path), (path.clone(), entry));
+        }
+        Ok(())
+    })?;
+
+    if let Some(dir) = summary
+        .truncated_dirs
+        .first()
+        .or(summary.skipped_dirs.first())
+    {
+        return Err(format!("Could not list every entry of {}", dir));
+    }
+    Ok(tree)
+}
+
+/// Compare two files by streamed digest
+fn compare_file_contents(left: &Path, right: &Path) -> Option<DiffReason> {
+    match (
+        hash_file(left, HashAlgorithm::Blake3),
+        hash_file(right, HashAlgorithm::Blake3),
+    ) {
+        (Ok(a), Ok(b)) if a == b => None,
+        (Ok(_), Ok(_)) => Some(DiffReason::Contents),
+        (Err(e), _) | (_, Err(e)) => {
+            warn!("Cannot compare contents: {}", e);
+            Some(DiffReason::Unreadable)
+        }
+    }
+}
+
+/// Whether any parent of the relative path `path` is in `dirs`
+fn has_ancestor_in(path: &str, dirs: &HashSet<String>) -> bool {
+    path.match_indices('/')
+        .any(|(index, _)| dirs.contains(&path[..index]))
+}
+
+/// Compare two directory trees
+///
+/// Files are compared by size and modification time, or by a streamed digest
+/// when `compare_contents` is set.
+#[tauri::command]
+pub fn diff_directories(
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    left: String,
+    right: String,
+    compare_contents: bool,
+) -> Result<DirectoryDiff, String> {
+    let guard = CommandGuard::new(&audit, "diff_directories");
+    let left = confined_dir(&guard, &roots, &left)?;
+    let right = confined_dir(&guard, &roots, &right)?;
+    diff_trees(&left, &right, compare_contents)
+}
+
+/// Compare the trees under `left` and `right`, matching entries by relative path
+pub(crate) fn diff_trees(
+    left: &Path,
+    right: &Path,
+    compare_contents: bool,
+) -> Result<DirectoryDiff, String> {
+    let mut left_tree = collect_tree(left)?;
+    let right_tree = collect_tree(right)?;
+
+    let mut diff = DirectoryDiff::default();
+    // Directories reported as a whole, whose contents are not listed again
+    let mut collapsed = HashSet::new();
+
+    for (path, (right_path, right_entry)) in right_tree {
+        if has_ancestor_in(&path, &collapsed) {
+            left_tree.remove(&path);
+            continue;
+        }
+        let Some((left_path, left_entry)) = left_tree.remove(&path) else {
+            if right_entry == TreeEntry::Directory {
+                collapsed.insert(path.clone());
+            }
+            diff.only_in_right.push(path);
+            continue;
+        };
+
+        let reason = match (&left_entry, &right_entry) {
+            (TreeEntry::Directory, TreeEntry::Directory) => continue,
+            (
+                TreeEntry::File {
+                    size: left_size,
+                    last_modified: left_modified,
+                },
+                TreeEntry::File {
+                    size: right_size,
+                    last_modified: right_modified,
+                },
+            ) => {
+                if left_size != right_size {
+                    Some(DiffReason::Size)
+                } else if compare_contents {
+                    compare_file_contents(&left_path, &right_path)
+                } else if left_modified != right_modified {
+                    Some(DiffReason::Modified)
+                } else {
+                    None
+                }
+            }
+            (TreeEntry::Symlink(a), TreeEntry::Symlink(b)) => {
+                (a != b).then_some(DiffReason::LinkTarget)
+            }
+            (a, b) if a == b => None,
+            _ => {
+                // Entries below a directory replaced by a file only exist on one side
+                if left_entry == TreeEntry::Directory || right_entry == TreeEntry::Directory {
+                    collapsed.insert(path.clone());
+                }
+                Some(DiffReason::KindMismatch)
+            }
+        };
+
+        match reason {
+            Some(reason) => diff.differing.push(DifferingEntry { path, reason }),
+            None => diff.identical_count += 1,
+        }
+    }
+
+    // Whatever is left exists only under `left`
+    for (path, (_, entry)) in left_tree {
+        if has_ancestor_in(&path, &collapsed) {
+            continue;
+        }
+        if entry == TreeEntry::Directory {
+            collapsed.insert(path.clone());
+        }
+        diff.only_in_left.push(path);
+    }
+
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::guard::TestAudit;
+
+    fn names(group: &[String]) -> Vec<String> {
+        group
+            .iter()
+            .map(|p| {
+                Path::new(p)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_find_case_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("README"), "a").unwrap();
+        fs::write(dir.path().join("readme"), "b").unwrap();
+        // Skip on case-insensitive file systems where the fixture can't exist
+        if fs::read_dir(dir.path()).unwrap().count() < 2 {
+            return;
+        }
+        fs::write(dir.path().join("notes.txt"), "c").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub").join("Photo.JPG"), "d").unwrap();
+        fs::write(dir.path().join("sub").join("photo.jpg"), "e").unwrap();
+
+        let shallow = case_collisions(dir.path(), None).unwrap();
+        assert_eq!(shallow.len(), 1);
+        assert_eq!(names(&shallow[0]), vec!["README", "readme"]);
+
+        let deep = case_collisions(dir.path(), Some(true)).unwrap();
+        assert_eq!(deep.len(), 2);
+        assert_eq!(names(&deep[1]), vec!["Photo.JPG", "photo.jpg"]);
+    }
+
+    #[test]
+    fn test_no_case_collisions() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("b.txt"), "b").unwrap();
+
+        assert!(case_collisions(dir.path(), Some(true)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_directory_reports_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let wide = dir.path().join("wide");
+        let narrow = dir.path().join("narrow");
+        fs::create_dir(&wide).unwrap();
+        fs::create_dir(&narrow).unwrap();
+        for i in 0..10 {
+            fs::write(wide.join(format!("{}.txt", i)), "x").unwrap();
+        }
+        fs::write(narrow.join("only.txt"), "x").unwrap();
+
+        let listing = list_directory_in(&RealFs, dir.path(), Some(true), Some(4)).unwrap();
+
+        assert!(listing.truncated);
+        assert_eq!(listing.truncated_dirs, vec![wide.to_string_lossy()]);
+        // Two root entries, four from the truncated directory, one from the other
+        assert_eq!(listing.entries.len(), 7);
+        assert!(listing.entries.iter().any(|e| e.name == "only.txt"));
+    }
+
+    #[test]
+    fn test_directory_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(dir.path().join("root.bin"), [0u8; 100]).unwrap();
+        fs::write(dir.path().join("a").join("mid.bin"), [0u8; 20]).unwrap();
+        fs::write(nested.join("leaf.bin"), [0u8; 3]).unwrap();
+        #[cfg(unix)]
+        fs::hard_link(dir.path().join("root.bin"), nested.join("hardlink.bin")).unwrap();
+
+        let roots = AllowedRoots::new();
+        roots.add(dir.path()).unwrap();
+
+        let size = measure_directory(&roots, dir.path(), false).unwrap();
+        assert_eq!(size.total_bytes, 123);
+        assert_eq!(size.file_count, 3);
+        assert_eq!(size.dir_count, 2);
+        assert!(size.skipped.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_directory_size_does_not_follow_links_out_of_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("inner")).unwrap();
+        fs::write(dir.path().join("inner").join("kept.bin"), [0u8; 5]).unwrap();
+        fs::write(outside.path().join("secret.bin"), [0u8; 1000]).unwrap();
+        fs::create_dir(outside.path().join("nested")).unwrap();
+        fs::write(outside.path().join("nested").join("deep.bin"), [0u8; 500]).unwrap();
+
+        let link = |target: &Path, name: &str| {
+            std::os::unix::fs::symlink(target, dir.path().join(name)).unwrap()
+        };
+        link(outside.path(), "escape_dir");
+        link(&outside.path().join("secret.bin"), "escape_file");
+        link(&dir.path().join("inner"), "inner_link");
+
+        let roots = AllowedRoots::new();
+        let root = roots.add(dir.path()).unwrap();
+        let size = measure_directory(&roots, &root, true).unwrap();
+
+        // Only the file inside the root counts, once through the link into `inner`
+        assert_eq!(size.total_bytes, 5);
+        assert_eq!(size.file_count, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_directory_size_skips_unreadable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("hidden.bin"), [0u8; 50]).unwrap();
+        fs::write(dir.path().join("visible.bin"), [0u8; 10]).unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Privileged users can read the directory anyway
+        let enforced = fs::read_dir(&locked).is_err();
+        let roots = AllowedRoots::new();
+        roots.add(dir.path()).unwrap();
+        let size = measure_directory(&roots, dir.path(), false);
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o700)).unwrap();
+
+        let size = size.unwrap();
+        if enforced {
+            assert_eq!(size.total_bytes, 10);
+            assert_eq!(size.skipped, vec![locked.to_string_lossy()]);
+        } else {
+            assert_eq!(size.total_bytes, 60);
+        }
+    }
+
+    #[test]
+    fn test_list_directory_glob() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = dir.path().join("logs").join("old");
+        fs::create_dir_all(&logs).unwrap();
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("b.txt"), "b").unwrap();
+        fs::write(dir.path().join("c.md"), "c").unwrap();
+        fs::write(dir.path().join("logs").join("app.log"), "d").unwrap();
+        fs::write(logs.join("app.1.log"), "e").unwrap();
+
+        let txt = glob_listing(dir.path(), "*.txt").unwrap();
+        let mut names: Vec<_> = txt.iter().map(|f| f.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["a.txt", "b.txt"]);
+
+        let logs = glob_listing(dir.path(), "**/*.log").unwrap();
+        let mut names: Vec<_> = logs.iter().map(|f| f.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, vec!["app.1.log", "app.log"]);
+
+        let err = glob_listing(dir.path(), "[unclosed").unwrap_err();
+        assert!(err.starts_with("InvalidPattern"));
+    }
+
+    fn search_fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("archive")).unwrap();
+        for name in [
+            "report.txt",
+            "Quarterly_Report.pdf",
+            "rpt.txt",
+            "readme_part.md",
+        ] {
+            fs::write(dir.path().join(name), "x").unwrap();
+        }
+        fs::write(dir.path().join("archive").join("report-2019.txt"), "x").unwrap();
+        dir
+    }
+
+    fn result_names(results: &[FileInfo]) -> Vec<&str> {
+        results.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_search_exact_substring() {
+        let dir = search_fixture();
+
+        let found = search_names(dir.path(), "report", true, false, false, None).unwrap();
+        assert_eq!(result_names(&found), vec!["report.txt"]);
+
+        let found = search_names(dir.path(), "report", true, true, false, None).unwrap();
+        // The prefix match with the shorter name ranks first
+        assert_eq!(result_names(&found), vec!["report.txt", "report-2019.txt"]);
+
+        let found = search_names(dir.path(), "report", true, true, false, Some(1)).unwrap();
+        assert_eq!(result_names(&found), vec!["report.txt"]);
+        assert!(search_names(dir.path(), "", true, true, false, None).is_err());
+    }
+
+    #[test]
+    fn test_search_case_insensitive() {
+        let dir = search_fixture();
+
+        let found = search_names(dir.path(), "REPORT", false, false, false, None).unwrap();
+        assert_eq!(
+            result_names(&found),
+            vec!["report.txt", "Quarterly_Report.pdf"]
+        );
+        let found = search_names(dir.path(), "REPORT", true, false, false, None).unwrap();
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_search_fuzzy_ranks_closer_matches_first() {
+        let dir = search_fixture();
+
+        let found = search_names(dir.path(), "rpt", true, false, true, None).unwrap();
+        let names = result_names(&found);
+        assert_eq!(names.first(), Some(&"rpt.txt"));
+        assert!(names.contains(&"report.txt"));
+        assert!(!names.contains(&"archive"));
+
+        // A contiguous run beats the same letters spread across words
+        let found = search_names(dir.path(), "rep", true, false, true, None).unwrap();
+        assert_eq!(result_names(&found)[..2], ["report.txt", "readme_part.md"]);
+    }
+
+    #[test]
+    fn test_mime_detection_from_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let png_header = [
+            0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D,
+        ];
+
+        // A PNG disguised as a text file is classified by its content
+        let mislabeled = dir.path().join("notes.txt");
+        fs::write(&mislabeled, png_header).unwrap();
+        let info = file_info(&RealFs, &mislabeled, true, true).unwrap();
+        assert_eq!(info.file_type, "txt");
+        assert_eq!(info.mime_type.as_deref(), Some("image/png"));
+
+        let correct = dir.path().join("image.png");
+        fs::write(&correct, png_header).unwrap();
+        let info = file_info(&RealFs, &correct, true, true).unwrap();
+        assert_eq!(info.mime_type.as_deref(), Some("image/png"));
+
+        // Detection is skipped unless requested
+        let info = file_info(&RealFs, &correct, false, true).unwrap();
+        assert_eq!(info.mime_type, None);
+    }
+
+    #[test]
+    fn test_mime_detection_empty_file() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // Nothing to sniff, so the extension decides
+        let empty = dir.path().join("empty.txt");
+        fs::write(&empty, "").unwrap();
+        let info = file_info(&RealFs, &empty, true, true).unwrap();
+        assert_eq!(info.size, 0);
+        assert_eq!(info.mime_type.as_deref(), Some("text/plain"));
+
+        let bare = dir.path().join("empty");
+        fs::write(&bare, "").unwrap();
+        let info = file_info(&RealFs, &bare, true, true).unwrap();
+        assert_eq!(info.mime_type, None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_metadata() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("target.txt");
+        let sub = dir.path().join("target_dir");
+        fs::write(&file, "12345").unwrap();
+        fs::create_dir(&sub).unwrap();
+        symlink(&file, dir.path().join("file_link")).unwrap();
+        symlink(&sub, dir.path().join("dir_link")).unwrap();
+
+        let plain = FileInfo::from_path(&file, false).unwrap();
+        assert!(!plain.is_symlink);
+        assert_eq!(plain.link_target, None);
+
+        let file_link = FileInfo::from_path(&dir.path().join("file_link"), false).unwrap();
+        assert!(file_link.is_symlink);
+        assert!(!file_link.is_directory);
+        assert_eq!(file_link.size, 5);
+        assert_eq!(
+            file_link.link_target,
+            Some(file.to_string_lossy().into_owned())
+        );
+
+        let dir_link = FileInfo::from_path(&dir.path().join("dir_link"), false).unwrap();
+        assert!(dir_link.is_symlink);
+        assert!(dir_link.is_directory);
+        assert_eq!(
+            dir_link.link_target,
+            Some(sub.to_string_lossy().into_owned())
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_not_followed() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("target.txt");
+        let sub = dir.path().join("target_dir");
+        fs::write(&file, "a longer target than its link").unwrap();
+        fs::create_dir(&sub).unwrap();
+        let file_link = dir.path().join("file_link");
+        let dir_link = dir.path().join("dir_link");
+        symlink(&file, &file_link).unwrap();
+        symlink(&sub, &dir_link).unwrap();
+
+        let followed = file_info(&RealFs, &file_link, false, true).unwrap();
+        let own = file_info(&RealFs, &file_link, false, false).unwrap();
+        assert_eq!(followed.size, 29);
+        assert_eq!(own.size, file.as_os_str().len() as u64);
+        assert!(followed.is_symlink && own.is_symlink);
+        assert_eq!(followed.link_target, own.link_target);
+        assert_eq!(followed.id, own.id);
+
+        let followed = file_info(&RealFs, &dir_link, false, true).unwrap();
+        let own = file_info(&RealFs, &dir_link, false, false).unwrap();
+        assert!(followed.is_directory);
+        assert!(!own.is_directory);
+        assert_eq!(own.link_target, Some(sub.to_string_lossy().into_owned()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dangling_symlink_not_followed() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let link = dir.path().join("dangling");
+        symlink(dir.path().join("missing.txt"), &link).unwrap();
+
+        assert!(file_info(&RealFs, &link, false, true).is_err());
+        let own = file_info(&RealFs, &link, false, false).unwrap();
+        assert!(own.is_symlink);
+        assert_eq!(own.name, "dangling");
+        assert!(file_info(&RealFs, &dir.path().join("nope"), false, false).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_path_is_flagged_and_round_trips() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let name = OsStr::from_bytes(b"report-\xff\xfe.txt");
+        let path = dir.path().join(name);
+        if fs::write(&path, "data").is_err() {
+            // Some file systems (e.g. on macOS) only accept valid UTF-8 names
+            return;
+        }
+
+        let info = FileInfo::from_path(&path, false).unwrap();
+        assert!(info.path_lossy);
+        assert!(info.path.contains('\u{FFFD}'));
+        assert!(!Path::new(&info.path).exists());
+
+        let raw = info.raw_path.expect("lossy paths carry a raw form");
+        let decoded = decode_raw_path(&raw).unwrap();
+        assert_eq!(decoded, path);
+        assert_eq!(fs::read(&decoded).unwrap(), b"data");
+
+        let plain = dir.path().join("plain.txt");
+        fs::write(&plain, "x").unwrap();
+        let info = FileInfo::from_path(&plain, false).unwrap();
+        assert!(!info.path_lossy);
+        assert_eq!(info.raw_path, None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_raw_path_reaches_a_non_utf8_entry() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(OsStr::from_bytes(b"report-\xff.txt"));
+        if fs::write(&path, "data").is_err() {
+            return;
+        }
+        let roots = AllowedRoots::new();
+        roots.add(dir.path()).unwrap();
+        let scope = ScopedAccess::default();
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let lookup = |path: &Path| {
+            scoped_file_info(
+                &guard, &RealFs, &roots, &scope, "main", None, path, false, true,
+            )
+        };
+
+        // The lossy display path names no file, but the raw form does
+        let listed = lookup(&path).unwrap();
+        assert!(lookup(Path::new(&listed.path)).is_err());
+        let raw = decode_raw_path(listed.raw_path.as_deref().unwrap()).unwrap();
+        assert_eq!(lookup(&raw).unwrap(), listed);
+    }
+
+    #[test]
+    fn test_root_under_home_is_usable() {
+        // The default root is the app data directory, usually under `/home/`
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("home/alice/.local/share/app");
+        fs::create_dir_all(data_dir.join("notes")).unwrap();
+        let note = data_dir.join("notes").join("today.txt");
+        fs::write(&note, "x").unwrap();
+        let roots = AllowedRoots::new();
+        roots.add(&data_dir).unwrap();
+        let scope = ScopedAccess::default();
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+
+        let info = scoped_file_info(
+            &guard, &RealFs, &roots, &scope, "main", None, &note, false, true,
+        )
+        .unwrap();
+        assert_eq!(info.size, 1);
+        let notes = data_dir.join("notes");
+        assert!(confined_dir(&guard, &roots, &notes.to_string_lossy()).is_ok());
+        let kind = |path: &Path| {
+            guard
+                .resolved_path(&path.to_string_lossy(), |path| classify_path(&roots, path))
+                .unwrap()
+        };
+        assert_eq!(kind(&note), PathKind::File);
+
+        // Other homes are still kept out by the root check
+        assert_eq!(
+            kind(&dir.path().join("home/bob/secret.txt")),
+            PathKind::OutsideRoots
+        );
+    }
+
+    #[test]
+    fn test_decode_raw_path_rejects_nul_and_garbage() {
+        let with_nul = BASE64URL_NOPAD.encode(b"/tmp/a\0b");
+        assert!(matches!(
+            decode_raw_path(&with_nul),
+            Err(AppError::Validation(_))
+        ));
+        assert!(decode_raw_path("not base64!").is_err());
+        assert!(decode_raw_path("").is_err());
+        assert_eq!(
+            decode_raw_path(&encode_raw_path(Path::new("/tmp/a b"))).unwrap(),
+            Path::new("/tmp/a b")
+        );
+    }
+
+    #[test]
+    fn test_read_file_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.txt");
+        fs::write(&path, "0123456789").unwrap();
+
+        let middle = read_range(&path, 3, 4).unwrap();
+        assert_eq!(middle.data, b"3456");
+        assert_eq!(middle.bytes_read, 4);
+
+        let tail = read_range(&path, 8, 100).unwrap();
+        assert_eq!(tail.data, b"89");
+        assert_eq!(tail.bytes_read, 2);
+
+        let empty = read_range(&path, 5, 0).unwrap();
+        assert!(empty.data.is_empty());
+        assert_eq!(empty.bytes_read, 0);
+
+        assert_eq!(read_range(&path, 10, 1).unwrap().bytes_read, 0);
+        assert!(read_range(&path, 11, 1).is_err());
+    }
+
+    #[test]
+    fn test_tail_exactly_and_fewer_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        fs::write(&path, "one\ntwo\nthree\n").unwrap();
+
+        assert_eq!(
+            tail_lines(&path, 3, 80).unwrap(),
+            vec!["one", "two", "three"]
+        );
+        assert_eq!(tail_lines(&path, 2, 80).unwrap(), vec!["two", "three"]);
+        // Asking for more lines than exist returns the whole file
+        assert_eq!(
+            tail_lines(&path, 10, 80).unwrap(),
+            vec!["one", "two", "three"]
+        );
+
+        // No trailing newline, and lines spanning several scan blocks
+        let long = "x".repeat(TAIL_BLOCK_BYTES as usize * 2);
+        fs::write(&path, format!("{}\r\nlast", long)).unwrap();
+        assert_eq!(tail_lines(&path, 1, 80).unwrap(), vec!["last"]);
+        assert_eq!(tail_lines(&path, 2, 4).unwrap(), vec!["xxxx", "last"]);
+        assert!(tail_lines(&path, 0, 80).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tail_truncates_giant_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("minified.js");
+        // 3-byte characters, so a 10-byte cut lands mid-character
+        fs::write(&path, "€".repeat(100_000)).unwrap();
+
+        let tail = tail_lines(&path, 5, 10).unwrap();
+        assert_eq!(tail, vec!["€€€"]);
+    }
+
+    #[test]
+    fn test_file_info_batch_mixed() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.txt"), "aa").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("b.txt"), "b").unwrap();
+        let roots = AllowedRoots::new();
+        roots.add(dir.path()).unwrap();
+
+        let paths = vec![
+            path("a.txt"),
+            path("missing.txt"),
+            path("sub"),
+            "../../etc/passwd".to_string(),
+            outside.path().join("b.txt").to_string_lossy().into_owned(),
+        ];
+        let scope = ScopedAccess::default();
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let results = file_info_batch(&guard, &RealFs, &roots, &scope, "main", &paths, 2);
+
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].as_ref().unwrap().size, 2);
+        assert_eq!(results[1], Err(AppError::NotFound(paths[1].clone())));
+        assert!(results[2].as_ref().unwrap().is_directory);
+        assert!(matches!(results[3], Err(AppError::Validation(_))));
+        assert!(matches!(results[4], Err(AppError::Authorization(_))));
+        let empty = file_info_batch(
+            &guard,
+            &RealFs,
+            &roots,
+            &scope,
+            "main",
+            &[],
+            MAX_BATCH_CONCURRENCY,
+        );
+        assert!(empty.is_empty());
+
+        // A file the window picked in a dialog is readable outside the roots
+        let ttl = Duration::from_secs(60);
+        scope.grant("main", vec![outside.path().join("b.txt")], false, ttl);
+        let results = file_info_batch(&guard, &RealFs, &roots, &scope, "main", &paths[4..], 1);
+        assert_eq!(results[0].as_ref().unwrap().size, 1);
+        let results = file_info_batch(&guard, &RealFs, &roots, &scope, "preview", &paths[4..], 1);
+        assert!(matches!(results[0], Err(AppError::Authorization(_))));
+    }
+
+    #[test]
+    fn test_file_info_batch_preserves_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<String> = (0..50)
+            .map(|i| {
+                let path = dir.path().join(format!("file{}.txt", i));
+                fs::write(&path, vec![b'x'; i]).unwrap();
+                path.to_string_lossy().into_owned()
+            })
+            .collect();
+
+        let roots = AllowedRoots::new();
+        roots.add(dir.path()).unwrap();
+        let scope = ScopedAccess::default();
+        let audit = TestAudit::new();
+        let results = file_info_batch(
+            &audit.guard(),
+            &RealFs,
+            &roots,
+            &scope,
+            "main",
+            &paths,
+            MAX_BATCH_CONCURRENCY,
+        );
+        for (i, result) in results.iter().enumerate() {
+            let info = result.as_ref().unwrap();
+            assert_eq!(info.path, paths[i]);
+            assert_eq!(info.size, i as u64);
+        }
+    }
+
+    #[cfg(feature = "test-fs")]
+    #[test]
+    fn test_timed_lookup_on_slow_mount_returns_promptly() {
+        use crate::utils::fs_backend::MockFs;
+        use std::time::Instant;
+
+        let mock = MockFs::new().with_latency(Duration::from_secs(5));
+        mock.add_file("/mnt/share/report.txt", "data");
+        let slow: Arc<dyn FileSystem> = Arc::new(mock);
+
+        let started = Instant::now();
+        let result = run_with_timeout(Duration::from_millis(50), move || {
+            file_info(
+                slow.as_ref(),
+                Path::new("/mnt/share/report.txt"),
+                false,
+                true,
+            )
+            .map_err(AppError::Io)
+        });
+        assert!(matches!(result, Err(AppError::Timeout(_))));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let fast: Arc<dyn FileSystem> = Arc::new(MockFs::new());
+        let result = run_with_timeout(Duration::from_secs(5), move || {
+            file_info(fast.as_ref(), Path::new("/"), false, true).map_err(AppError::Io)
+        });
+        assert!(result.unwrap().is_directory);
+    }
+
+    #[cfg(feature = "test-fs")]
+    #[test]
+    fn test_listing_and_metadata_against_mock_fs() {
+        use crate::utils::fs_backend::MockFs;
+
+        let mock = MockFs::new();
+        mock.add_file("/data/report.PDF", "%PDF-1.4 fake");
+        mock.add_file("/data/nested/notes.txt", "hello");
+        mock.add_dir("/data/empty");
+
+        let info = file_info(&mock, Path::new("/data/report.PDF"), true, true).unwrap();
+        assert_eq!(info.name, "report.PDF");
+        assert_eq!(info.file_type, "pdf");
+        assert_eq!(info.size, 13);
+        assert_eq!(info.mime_type.as_deref(), Some("application/pdf"));
+        assert!(!info.is_directory && !info.is_symlink);
+
+        let shallow = list_directory_in(&mock, Path::new("/data"), None, None).unwrap();
+        let names: Vec<&str> = shallow.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["empty", "nested", "report.PDF"]);
+
+        let deep = list_directory_in(&mock, Path::new("/data"), Some(true), Some(2)).unwrap();
+        assert!(deep.truncated);
+        assert_eq!(deep.truncated_dirs, vec!["/data"]);
+        assert!(deep.entries.iter().any(|e| e.name == "notes.txt"));
+
+        assert!(file_info(&mock, Path::new("/data/missing.txt"), false, true).is_err());
+        assert!(list_directory_in(&mock, Path::new("/data/report.PDF"), None, None).is_err());
+    }
+
+    #[cfg(feature = "test-fs")]
+    #[test]
+    fn test_unreadable_entries_are_reported_as_skipped() {
+        use crate::utils::fs_backend::MockFs;
+        use std::io::ErrorKind;
+
+        let mock = MockFs::new();
+        mock.add_file("/data/a.txt", "a");
+        mock.add_unreadable("/data/b.txt", ErrorKind::PermissionDenied);
+        mock.add_file("/data/c.txt", "c");
+        mock.add_unreadable("/data/nested/d.txt", ErrorKind::TimedOut);
+
+        let listing = list_directory_in(&mock, Path::new("/data"), Some(true), None).unwrap();
+        let names: Vec<&str> = listing.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["a.txt", "c.txt", "nested"]);
+
+        let skipped: Vec<&str> = listing.skipped.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(skipped, vec!["/data/b.txt", "/data/nested/d.txt"]);
+        assert!(listing.skipped[0].reason.contains("PermissionDenied"));
+        assert!(listing.skipped[1].reason.contains("TimedOut"));
+        assert!(!listing.truncated);
+    }
+
+    #[test]
+    fn test_path_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let roots = AllowedRoots::new();
+        roots.add(dir.path()).unwrap();
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+
+        let kind = |path: &Path| classify_path(&roots, path).unwrap();
+        assert_eq!(kind(&dir.path().join("a.txt")), PathKind::File);
+        assert_eq!(kind(&dir.path().join("sub")), PathKind::Directory);
+        assert_eq!(kind(&dir.path().join("gone.txt")), PathKind::Missing);
+        assert_eq!(kind(&dir.path().join("no").join("such")), PathKind::Missing);
+        assert_eq!(kind(&outside.path().join("x")), PathKind::OutsideRoots);
+        assert_eq!(kind(outside.path()), PathKind::OutsideRoots);
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("a.txt", dir.path().join("link")).unwrap();
+            assert_eq!(kind(&dir.path().join("link")), PathKind::Symlink);
+            let socket = dir.path().join("app.sock");
+            let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+            assert_eq!(kind(&socket), PathKind::Other);
+        }
+
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        assert!(matches!(
+            guard.resolved_path("../../etc/passwd", |path| classify_path(&roots, path)),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_path_kind_resolves_relative_paths() {
+        // Tests run from the crate directory, which only needs reading here
+        let cwd = std::env::current_dir().unwrap();
+        let roots = AllowedRoots::new();
+        let kind = |path: &str| classify_path(&roots, Path::new(path)).unwrap();
+        assert_eq!(kind("Cargo.toml"), PathKind::OutsideRoots);
+        assert_eq!(kind("no-such-dir/file.txt"), PathKind::OutsideRoots);
+
+        roots.add(&cwd).unwrap();
+        assert_eq!(kind("Cargo.toml"), PathKind::File);
+        assert_eq!(kind("./src"), PathKind::Directory);
+        assert_eq!(kind("no-such-dir/file.txt"), PathKind::Missing);
+    }
+
+    #[test]
+    fn test_canonicalize_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().canonicalize().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("file.txt"), "x").unwrap();
+
+        let cwd = std::env::current_dir().unwrap().canonicalize().unwrap();
+        assert_eq!(canonical_path(".").unwrap(), cwd.to_string_lossy());
+
+        let dotted = dir.path().join("sub").join("..").join("file.txt");
+        assert_eq!(
+            canonical_path(&dotted.to_string_lossy()).unwrap(),
+            real.join("file.txt").to_string_lossy()
+        );
+
+        let missing = dir.path().join("missing");
+        assert!(matches!(
+            canonical_path(&missing.to_string_lossy()),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_canonicalize_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().canonicalize().unwrap();
+        fs::write(dir.path().join("target.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("target.txt"), dir.path().join("link")).unwrap();
+
+        let link = dir.path().join("link").to_string_lossy().into_owned();
+        assert_eq!(
+            canonical_path(&link).unwrap(),
+            real.join("target.txt").to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn test_canonicalize_outside_roots_hides_existence() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("present.txt"), "x").unwrap();
+        let roots = AllowedRoots::new();
+        roots.add(dir.path()).unwrap();
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+
+        // Outside the roots, a missing path fails exactly like an existing one
+        let present = outside.path().join("present.txt");
+        let absent = outside.path().join("absent.txt");
+        let error = |path: &Path| match canonical_path_in(&guard, &roots, &path.to_string_lossy()) {
+            Err(AppError::Authorization(message)) => {
+                message.replace(&*path.to_string_lossy(), "<path>")
+            }
+            other => panic!("expected Authorization, got {:?}", other),
+        };
+        assert_eq!(error(&present), error(&absent));
+        assert_eq!(error(&absent.join("deeper")), error(&present));
+
+        // Inside them, missing paths are still reported as such
+        let missing = dir.path().join("missing.txt");
+        assert!(matches!(
+            canonical_path_in(&guard, &roots, &missing.to_string_lossy()),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    /// Canonicalize both paths, then relativize, as `relativize_path` does
+    fn relativize(path: &Path, base: &Path) -> String {
+        let roots = AllowedRoots::new();
+        roots.add(base.parent().unwrap()).unwrap();
+        let audit = TestAudit::new();
+        let (path, base) = (path.to_string_lossy(), base.to_string_lossy());
+        relativize_in(&audit.guard(), &roots, &path, &base).unwrap()
+    }
+
+    #[test]
+    fn test_relativize_path_under_base() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("photos").join("2024")).unwrap();
+        fs::write(dir.path().join("photos").join("2024").join("a.jpg"), "x").unwrap();
+
+        let file = dir.path().join("photos").join("2024").join("a.jpg");
+        assert_eq!(
+            relativize(&file, &dir.path().join("photos")),
+            Path::new("2024").join("a.jpg").to_string_lossy()
+        );
+        assert_eq!(
+            relativize(&dir.path().join("photos"), &dir.path().join("photos")),
+            "."
+        );
+    }
+
+    #[test]
+    fn test_relativize_sibling_stays_absolute() {
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().canonicalize().unwrap();
+        fs::create_dir(dir.path().join("photos")).unwrap();
+        fs::create_dir(dir.path().join("photos-old")).unwrap();
+
+        // A shared string prefix doesn't make a path a child
+        assert_eq!(
+            relativize(&dir.path().join("photos-old"), &dir.path().join("photos")),
+            real.join("photos-old").to_string_lossy()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_relativize_through_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("data")).unwrap();
+        fs::write(dir.path().join("data").join("report.csv"), "x").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("data"), dir.path().join("current")).unwrap();
+
+        let via_link = dir.path().join("current").join("report.csv");
+        assert_eq!(
+            relativize(&via_link, &dir.path().join("data")),
+            "report.csv"
+        );
+        let direct = dir.path().join("data").join("report.csv");
+        assert_eq!(
+            relativize(&direct, &dir.path().join("current")),
+            "report.csv"
+        );
+    }
+
+    #[test]
+    fn test_strip_verbatim_prefix() {
+        assert_eq!(strip_verbatim_prefix(r"\\?\C:\Users\me"), r"C:\Users\me");
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\UNC\server\share\f"),
+            r"\\server\share\f"
+        );
+        assert_eq!(strip_verbatim_prefix("/tmp/x"), "/tmp/x");
+    }
+
+    #[test]
+    fn test_rejects_traversal() {
+        assert!(validated_dir("../../etc").is_err());
+    }
+
+    #[test]
+    fn test_diff_directories() {
+        use filetime::{set_file_mtime, FileTime};
+
+        let left = tempfile::tempdir().unwrap();
+        let right = tempfile::tempdir().unwrap();
+        let write = |root: &Path, name: &str, contents: &str, mtime: i64| {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, contents).unwrap();
+            set_file_mtime(&path, FileTime::from_unix_time(mtime, 0)).unwrap();
+        };
+        for root in [left.path(), right.path()] {
+            write(root, "same.txt", "same", 1_000);
+            write(root, "sub/nested.txt", "nested", 1_000);
+        }
+        write(left.path(), "removed.txt", "gone", 1_000);
+        write(left.path(), "changed.txt", "aaaa", 1_000);
+        write(left.path(), "touched.txt", "touch", 1_000);
+        write(left.path(), "kind", "file", 1_000);
+        write(right.path(), "added.txt", "new", 1_000);
+        write(right.path(), "changed.txt", "bbbb", 2_000);
+        write(right.path(), "touched.txt", "touch", 2_000);
+        write(right.path(), "kind/inner.txt", "dir", 1_000);
+        write(right.path(), "newdir/inner.txt", "dir", 1_000);
+
+        let diff = diff_trees(left.path(), right.path(), false).unwrap();
+        assert_eq!(diff.only_in_left, vec!["removed.txt"]);
+        // A directory on one side only is listed without its contents
+        assert_eq!(diff.only_in_right, vec!["added.txt", "newdir"]);
+        let differing: Vec<(&str, DiffReason)> = diff
+            .differing
+            .iter()
+            .map(|d| (d.path.as_str(), d.reason))
+            .collect();
+        assert_eq!(
+            differing,
+            vec![
+                ("changed.txt", DiffReason::Modified),
+                ("kind", DiffReason::KindMismatch),
+                ("touched.txt", DiffReason::Modified),
+            ]
+        );
+        assert_eq!(diff.identical_count, 2);
+
+        // By contents, a file that was only touched is identical
+        let diff = diff_trees(left.path(), right.path(), true).unwrap();
+        let differing: Vec<(&str, DiffReason)> = diff
+            .differing
+            .iter()
+            .map(|d| (d.path.as_str(), d.reason))
+            .collect();
+        assert_eq!(
+            differing,
+            vec![
+                ("changed.txt", DiffReason::Contents),
+                ("kind", DiffReason::KindMismatch),
+            ]
+        );
+        assert_eq!(diff.identical_count, 3);
+    }
+
+    #[test]
+    fn test_file_ids_are_stable_and_distinct() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        fs::write(&a, "a").unwrap();
+        fs::write(&b, "b").unwrap();
+
+        let first = file_info(&RealFs, &a, false, true).unwrap();
+        let second = file_info(&RealFs, &a, false, true).unwrap();
+        assert_eq!(first.id, second.id);
+        assert_eq!(first.id, stable_file_id_in(&RealFs, &a));
+        assert!(!first.id.contains("a.txt"));
+
+        let other = file_info(&RealFs, &b, false, true).unwrap();
+        assert_ne!(first.id, other.id);
+
+        // Another route to the same file yields the same id
+        let dotted = dir.path().join(".").join("a.txt");
+        assert_eq!(stable_file_id_in(&RealFs, &dotted), first.id);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_id_differs_from_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target.txt");
+        let link = dir.path().join("link.txt");
+        fs::write(&target, "x").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        assert_ne!(
+            stable_file_id_in(&RealFs, &link),
+            stable_file_id_in(&RealFs, &target)
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! Pluggable file system backends for Tauri applications
+//!
+//! File commands reach the disk through the `FileSystem` trait:
+//! 1. `RealFs` forwards to `std::fs` and is what the app manages by default
+//! 2. `MockFs` keeps a tree in memory, behind the `test-fs` feature
+//! 3. Commands take the backend from managed state as `Arc<dyn FileSystem>`
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// The subset of file metadata the file commands rely on
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FsMetadata {
+    /// Whether the entry is a directory
+    pub is_dir: bool,
+    /// Whether the entry is a regular file
+    pub is_file: bool,
+    /// Whether the entry is a symbolic link
+    pub is_symlink: bool,
+    /// Size in bytes
+    pub len: u64,
+    /// Last modification time, if the platform reports one
+    pub modified: Option<SystemTime>,
+    /// Last access time, if the platform reports one
+    pub accessed: Option<SystemTime>,
+    /// Last change to the entry's inode, such as a `chmod`; Unix only
+    pub changed: Option<SystemTime>,
+    /// Whether the entry's permissions forbid writing to it
+    pub readonly: bool,
+}
+
+/// The inode change time of `metadata`
+#[cfg(unix)]
+fn changed_time(metadata: &fs::Metadata) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt;
+    let secs = u64::try_from(metadata.ctime()).ok()?;
+    let nanos = u32::try_from(metadata.ctime_nsec()).ok()?;
+    SystemTime::UNIX_EPOCH.checked_add(std::time::Duration::new(secs, nanos))
+}
+
+/// Only Unix records an inode change time
+#[cfg(not(unix))]
+fn changed_time(_metadata: &fs::Metadata) -> Option<SystemTime> {
+    None
+}
+
+impl From<fs::Metadata> for FsMetadata {
+    fn from(metadata: fs::Metadata) -> Self {
+        Self {
+            is_dir: metadata.is_dir(),
+            is_file: metadata.is_file(),
+            is_symlink: metadata.file_type().is_symlink(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+            changed: changed_time(&metadata),
+            readonly: metadata.permissions().readonly(),
+        }
+    }
+}
+
+/// Lazily produced directory entries, as full paths
+pub type DirEntries<'a> = Box<dyn Iterator<Item = io::Result<PathBuf>> + 'a>;
+
+/// File system operations used by the file commands
+pub trait FileSystem: Send + Sync {
+    /// Metadata of `path`, following symlinks
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+
+    /// Metadata of `path` itself, without following symlinks
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+
+    /// Where the symlink at `path` points
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Resolve `path` to an absolute path without symlinks
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Entries of the directory at `path`, in no particular order
+    fn read_dir(&self, path: &Path) -> io::Result<DirEntries<'_>>;
+
+    /// Read the whole file at `path`
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Read at most the first `limit` bytes of the file at `path`
+    fn read_head(&self, path: &Path, limit: u64) -> io::Result<Vec<u8>> {
+        let mut data = self.read(path)?;
+        data.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+        Ok(data)
+    }
+
+    /// Create or replace the file at `path` with `data`
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+}
+
+/// The real file system
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealFs;
+
+impl FileSystem for RealFs {
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        fs::metadata(path).map(FsMetadata::from)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        fs::symlink_metadata(path).map(FsMetadata::from)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        path.canonicalize()
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<DirEntries<'_>> {
+        let entries = fs::read_dir(path)?;
+        Ok(Box::new(entries.map(|entry| entry.map(|e| e.path()))))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn read_head(&self, path: &Path, limit: u64) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        File::open(path)?.take(limit).read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        fs::write(path, data)
+    }
+}
+
+/// Create the default backend placed in managed state
+pub fn default_backend() -> Arc<dyn FileSystem> {
+    Arc::new(RealFs)
+}
+
+#[cfg(feature = "test-fs")]
+pub use mock::MockFs;
+
+#[cfg(feature = "test-fs")]
+mod mock {
+    use super::{DirEntries, FileSystem, FsMetadata};
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    /// A node of the in-memory tree
+    #[derive(Clone, Debug)]
+    enum Node {
+        Dir,
+        File {
+            data: Vec<u8>,
+            modified: SystemTime,
+        },
+        /// An entry that is listed but fails every other lookup
+        Unreadable(io::ErrorKind),
+    }
+
+    /// An in-memory file system for tests
+    ///
+    /// Paths are used verbatim, so tests should stick to absolute paths
+    /// without `.` or `..`. Symlinks are not supported.
+    #[derive(Debug, Default)]
+    pub struct MockFs {
+        nodes: Mutex<BTreeMap<PathBuf, Node>>,
+        /// Delay added to every metadata lookup, simulating a slow mount
+        latency: Duration,
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} not found", path.display()),
+        )
+    }
+
+    fn unreadable(path: &Path, kind: io::ErrorKind) -> io::Error {
+        io::Error::new(
+            kind,
+            format!("{} is unreadable ({:?})", path.display(), kind),
+        )
+    }
+
+    impl MockFs {
+        /// Create an empty file system containing only the root directory
+        pub fn new() -> Self {
+            let fs = Self::default();
+            fs.add_dir("/");
+            fs
+        }
+
+        /// Delay every metadata lookup by `latency`
+        pub fn with_latency(mut self, latency: Duration) -> Self {
+            self.latency = latency;
+            self
+        }
+
+        /// Add a directory and any missing parents
+        pub fn add_dir(&self, path: impl AsRef<Path>) {
+            let mut nodes = self.nodes.lock().unwrap_or_else(|e| e.into_inner());
+            for ancestor in path.as_ref().ancestors() {
+                if !ancestor.as_os_str().is_empty() {
+                    nodes.entry(ancestor.to_path_buf()).or_insert(Node::Dir);
+                }
+            }
+        }
+
+        /// Add a file, creating missing parent directories
+        pub fn add_file(&self, path: impl AsRef<Path>, data: impl Into<Vec<u8>>) {
+            let path = path.as_ref();
+            if let Some(parent) = path.parent() {
+                self.add_dir(parent);
+            }
+            self.nodes.lock().unwrap_or_else(|e| e.into_inner()).insert(
+                path.to_path_buf(),
+                Node::File {
+                    data: data.into(),
+                    modified: SystemTime::now(),
+                },
+            );
+        }
+
+        /// Add an entry whose metadata and contents fail with `kind`
+        pub fn add_unreadable(&self, path: impl AsRef<Path>, kind: io::ErrorKind) {
+            let path = path.as_ref();
+            if let Some(parent) = path.parent() {
+                self.add_dir(parent);
+            }
+            self.nodes
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(path.to_path_buf(), Node::Unreadable(kind));
+        }
+
+        fn node(&self, path: &Path) -> io::Result<Node> {
+            self.nodes
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(path)
+                .cloned()
+                .ok_or_else(|| not_found(path))
+        }
+    }
+
+    impl FileSystem for MockFs {
+        fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+            if !self.latency.is_zero() {
+                thread::sleep(self.latency);
+            }
+            Ok(match self.node(path)? {
+                Node::Dir => FsMetadata {
+                    is_dir: true,
+                    is_file: false,
+                    is_symlink: false,
+                    len: 0,
+                    modified: None,
+                    accessed: None,
+                    changed: None,
+                    readonly: false,
+                },
+                Node::File { data, modified } => FsMetadata {
+                    is_dir: false,
+                    is_file: true,
+                    is_symlink: false,
+                    len: data.len() as u64,
+                    modified: Some(modified),
+                    accessed: None,
+                    changed: None,
+                    readonly: false,
+                },
+                Node::Unreadable(kind) => return Err(unreadable(path, kind)),
+            })
+        }
+
+        fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+            self.metadata(path)
+        }
+
+        fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+            self.node(path)?;
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a symlink", path.display()),
+            ))
+        }
+
+        fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+            self.node(path).map(|_| path.to_path_buf())
+        }
+
+        fn read_dir(&self, path: &Path) -> io::Result<DirEntries<'_>> {
+            if !matches!(self.node(path)?, Node::Dir) {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("{} is not a directory", path.display()),
+                ));
+            }
+
+            let children: Vec<PathBuf> = self
+                .nodes
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .keys()
+                .filter(|child| child.parent() == Some(path))
+                .cloned()
+                .collect();
+            Ok(Box::new(children.into_iter().map(Ok)))
+        }
+
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            match self.node(path)? {
+                Node::File { data, .. } => Ok(data),
+                Node::Unreadable(kind) => Err(unreadable(path, kind)),
+                Node::Dir => Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("{} is a directory", path.display()),
+                )),
+            }
+        }
+
+        fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+            match path.parent() {
+                Some(parent) if matches!(self.node(parent)?, Node::Dir) => {}
+                _ => return Err(not_found(path)),
+            }
+            self.add_file(path, data);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "test-fs"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_fs_round_trip() {
+        let fs = MockFs::new();
+        fs.add_dir("/data");
+        fs.write(Path::new("/data/a.txt"), b"hello").unwrap();
+
+        assert_eq!(fs.read(Path::new("/data/a.txt")).unwrap(), b"hello");
+        assert_eq!(fs.read_head(Path::new("/data/a.txt"), 2).unwrap(), b"he");
+        assert!(fs.metadata(Path::new("/data")).unwrap().is_dir);
+        assert_eq!(fs.metadata(Path::new("/data/a.txt")).unwrap().len, 5);
+        assert!(fs.write(Path::new("/missing/b.txt"), b"x").is_err());
+
+        let entries: Vec<PathBuf> = fs
+            .read_dir(Path::new("/data"))
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(entries, vec![PathBuf::from("/data/a.txt")]);
+    }
+}
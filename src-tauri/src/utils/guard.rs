@@ -0,0 +1,363 @@
+//! Input guards for Tauri commands
+//!
+//! Every command should validate its input the same way before acting on it:
+//! 1. Strings and paths go through `BoundaryValidator`, and paths must stay
+//!    within `DEFAULT_MAX_PATH_LEN` and `DEFAULT_MAX_PATH_COMPONENTS`
+//! 2. Paths are confined to the `AllowedRoots`, or to the dialog grants of the
+//!    calling window in `ScopedAccess`
+//! 3. Rejections are recorded in the audit log with their reason
+//!
+//! Call the guard at the top of a command:
+//!
+//! ```ignore
+//! let guard = CommandGuard::new(&audit, "my_command");
+//! let path = guard.confined_path(&raw_path, &roots)?;
+//! ```
+
+use super::allowed_roots::AllowedRoots;
+use super::audit::{AuditLog, AuditOutcome};
+use super::error::AppError;
+use super::memory_safe::{
+    BoundaryValidator, SecureString, WindowsPath, DEFAULT_MAX_PATH_COMPONENTS, DEFAULT_MAX_PATH_LEN,
+};
+use super::scoped_access::{check_scoped, check_scoped_entry, ScopedAccess};
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// Validates the input of one command invocation and audits the outcome
+pub struct CommandGuard<'a> {
+    audit: &'a AuditLog,
+    command: &'static str,
+}
+
+impl<'a> CommandGuard<'a> {
+    /// Create a guard for `command`, recording to `audit`
+    pub fn new(audit: &'a AuditLog, command: &'static str) -> Self {
+        Self { audit, command }
+    }
+
+    /// Record that the input was rejected for `reason`
+    fn record_rejection(&self, reason: &dyn Display, redacted_arg: String) {
+        self.audit.record(
+            self.command,
+            AuditOutcome::Rejected {
+                reason: reason.to_string(),
+            },
+            vec![redacted_arg],
+        );
+    }
+
+    /// Record a rejection and build the matching error
+    fn reject(&self, error: AppError, redacted_arg: String) -> AppError {
+        self.record_rejection(&error, redacted_arg);
+        error
+    }
+
+    /// Record that the command accepted its input
+    pub fn accept(&self, redacted_args: Vec<String>) {
+        self.audit
+            .record(self.command, AuditOutcome::Accepted, redacted_args);
+    }
+
+    /// Validate a free-form string, returning it unchanged if it is clean
+    ///
+    /// The input is redacted in the audit log since it may be sensitive.
+    pub fn validated_input<'r>(&self, raw: &'r str) -> Result<&'r str, AppError> {
+        match BoundaryValidator::validate_string_detailed(raw) {
+            Ok(()) => Ok(raw),
+            Err(reason) => Err(self.reject(
+                AppError::Validation(reason.to_string()),
+                SecureString::new(raw).to_string(),
+            )),
+        }
+    }
+
+    /// Validate a path received from the frontend
+    ///
+    /// Paths longer than `DEFAULT_MAX_PATH_LEN` or deeper than
+    /// `DEFAULT_MAX_PATH_COMPONENTS` are rejected before any other check.
+    /// On Windows the separators are normalized to `\`, keeping any drive,
+    /// UNC or verbatim prefix intact.
+    pub fn validated_path(&self, raw: &str) -> Result<PathBuf, AppError> {
+        self.checked_path(raw, BoundaryValidator::validate_path)
+    }
+
+    /// Like `validated_path`, for a path that is confined afterwards
+    ///
+    /// Sensitive system directories are left to the confinement, which
+    /// decides where the path may lead; see `validate_confined_path`.
+    pub fn confinable_path(&self, raw: &str) -> Result<PathBuf, AppError> {
+        self.checked_path(raw, BoundaryValidator::validate_confined_path)
+    }
+
+    /// Check `raw` against the path limits and then `is_valid`
+    fn checked_path(&self, raw: &str, is_valid: fn(&str) -> bool) -> Result<PathBuf, AppError> {
+        if let Err(e) = BoundaryValidator::validate_path_limits(
+            raw,
+            DEFAULT_MAX_PATH_LEN,
+            DEFAULT_MAX_PATH_COMPONENTS,
+        ) {
+            // Don't copy an oversized path into the audit log
+            return Err(self.reject(
+                AppError::Validation(e.to_string()),
+                format!("<{} byte path>", raw.len()),
+            ));
+        }
+        if !is_valid(raw) {
+            return Err(self.reject(
+                AppError::Validation("Invalid path detected".into()),
+                raw.to_string(),
+            ));
+        }
+        Ok(Self::normalized_path(raw, cfg!(windows)))
+    }
+
+    /// `raw` as a path, with its separators normalized if it is a `windows` path
+    fn normalized_path(raw: &str, windows: bool) -> PathBuf {
+        match WindowsPath::parse(raw).filter(|_| windows) {
+            Some(parsed) => PathBuf::from(parsed.to_normalized()),
+            None => PathBuf::from(raw),
+        }
+    }
+
+    /// Validate `raw` and confine it with `confine`, auditing a rejection by either
+    ///
+    /// `confine` receives the validated path and returns what the command
+    /// acts on, such as a canonical path checked against the `AllowedRoots`.
+    pub fn resolved_path<T, E, F>(&self, raw: &str, confine: F) -> Result<T, E>
+    where
+        F: FnOnce(&Path) -> Result<T, E>,
+        E: From<AppError> + Display,
+    {
+        let path = self.confinable_path(raw)?;
+        self.confined_with(raw, confine(&path))
+    }
+
+    /// Like `resolved_path`, for a path that need not be valid Unicode
+    ///
+    /// The lossy text of `path` is validated, but `confine` receives `path`
+    /// itself so the entry it names is the one acted on.
+    pub fn resolved_os_path<T, E, F>(&self, path: &Path, confine: F) -> Result<T, E>
+    where
+        F: FnOnce(&Path) -> Result<T, E>,
+        E: From<AppError> + Display,
+    {
+        let raw = path.to_string_lossy();
+        self.confinable_path(&raw)?;
+        self.confined_with(&raw, confine(path))
+    }
+
+    /// Like `resolved_path`, but `..` is left for `resolve` to resolve
+    ///
+    /// Only the path limits and NUL bytes are checked up front, so `resolve`
+    /// must confine the path it ends up with, e.g. by canonicalizing it.
+    pub fn resolvable_path<T, E, F>(&self, raw: &str, resolve: F) -> Result<T, E>
+    where
+        F: FnOnce(&Path) -> Result<T, E>,
+        E: From<AppError> + Display,
+    {
+        let path = self.checked_path(raw, |raw| !raw.contains('\0'))?;
+        self.confined_with(raw, resolve(&path))
+    }
+
+    /// Audit `result` as a rejection of `raw` if it failed
+    fn confined_with<T, E: Display>(&self, raw: &str, result: Result<T, E>) -> Result<T, E> {
+        result.map_err(|e| {
+            self.record_rejection(&e, raw.to_string());
+            e
+        })
+    }
+
+    /// Validate `raw` and ensure it resolves under one of `roots`
+    ///
+    /// Returns the canonical path; see `AllowedRoots::check`.
+    pub fn confined_path(&self, raw: &str, roots: &AllowedRoots) -> Result<PathBuf, AppError> {
+        self.resolved_path(raw, |path| roots.check(path))
+    }
+
+    /// Validate `raw` for the window labelled `label`, see `check_scoped`
+    pub fn scoped_path(
+        &self,
+        raw: &str,
+        roots: &AllowedRoots,
+        scope: &ScopedAccess,
+        label: &str,
+    ) -> Result<PathBuf, AppError> {
+        self.resolved_path(raw, |path| check_scoped(roots, scope, label, path))
+    }
+
+    /// Like `scoped_path`, but a final symlink is not followed
+    pub fn scoped_entry(
+        &self,
+        raw: &str,
+        roots: &AllowedRoots,
+        scope: &ScopedAccess,
+        label: &str,
+    ) -> Result<PathBuf, AppError> {
+        self.resolved_path(raw, |path| check_scoped_entry(roots, scope, label, path))
+    }
+}
+
+/// An audit log in a temporary directory of its own, for testing guarded functions
+#[cfg(test)]
+pub(crate) struct TestAudit {
+    pub(crate) log: AuditLog,
+    _dir: tempfile::TempDir,
+}
+
+#[cfg(test)]
+impl TestAudit {
+    pub(crate) fn new() -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog::new(
+            dir.path().join("audit.log"),
+            super::audit::DEFAULT_MAX_BYTES,
+            1,
+        );
+        Self { log, _dir: dir }
+    }
+
+    /// A guard recording to this log
+    pub(crate) fn guard(&self) -> CommandGuard<'_> {
+        CommandGuard::new(&self.log, "test")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::audit::{AuditEntry, DEFAULT_MAX_BYTES};
+    use std::fs;
+
+    fn read_entries(audit: &AuditLog) -> Vec<AuditEntry> {
+        fs::read_to_string(audit.path())
+            .unwrap_or_default()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_input_passes_through() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = AuditLog::new(dir.path().join("audit.log"), DEFAULT_MAX_BYTES, 1);
+        let guard = CommandGuard::new(&audit, "test");
+
+        assert_eq!(guard.validated_input("hello world").unwrap(), "hello world");
+        assert_eq!(
+            guard.validated_path("docs/report.txt").unwrap(),
+            PathBuf::from("docs/report.txt")
+        );
+        assert!(read_entries(&audit).is_empty());
+    }
+
+    #[test]
+    fn test_rejects_injection_and_traversal() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = AuditLog::new(dir.path().join("audit.log"), DEFAULT_MAX_BYTES, 1);
+        let guard = CommandGuard::new(&audit, "test");
+
+        assert!(matches!(
+            guard.validated_input("<script>alert(1)</script>"),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            guard.validated_path("../../etc/passwd"),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            guard.validated_path(&"deep/".repeat(DEFAULT_MAX_PATH_COMPONENTS + 1)),
+            Err(AppError::Validation(_))
+        ));
+
+        let entries = read_entries(&audit);
+        assert_eq!(entries.len(), 3);
+        assert!(entries
+            .iter()
+            .all(|e| matches!(e.outcome, AuditOutcome::Rejected { .. })));
+        assert!(!entries[0].redacted_args[0].contains("alert"));
+    }
+
+    #[test]
+    fn test_windows_separators_are_normalized() {
+        assert_eq!(
+            CommandGuard::normalized_path("C:/Users/ada/./notes.txt", true),
+            PathBuf::from(r"C:\Users\ada\notes.txt")
+        );
+        assert_eq!(
+            CommandGuard::normalized_path("//server/share/docs/q1.xlsx", true),
+            PathBuf::from(r"\\server\share\docs\q1.xlsx")
+        );
+        assert_eq!(
+            CommandGuard::normalized_path("docs/q1.xlsx", false),
+            PathBuf::from("docs/q1.xlsx")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_path_confined_to_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("home/alice/.local/share/app");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("inside.txt"), "x").unwrap();
+        fs::write(dir.path().join("picked.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(dir.path(), root.join("escape")).unwrap();
+        let roots = AllowedRoots::new();
+        let canonical_root = roots.add(&root).unwrap();
+        let scope = ScopedAccess::default();
+
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let raw = |path: &Path| path.to_string_lossy().into_owned();
+
+        // A root under a home directory is fine once the path is confined
+        let inside = guard
+            .confined_path(&raw(&root.join("inside.txt")), &roots)
+            .unwrap();
+        assert_eq!(inside, canonical_root.join("inside.txt"));
+        assert!(guard
+            .confined_path(&raw(&root.join("new.txt")), &roots)
+            .is_ok());
+        assert!(read_entries(&audit.log).is_empty());
+
+        assert!(matches!(
+            guard.confined_path(&raw(&root.join("escape/picked.txt")), &roots),
+            Err(AppError::Authorization(_))
+        ));
+        let picked = raw(&dir.path().join("picked.txt"));
+        assert!(guard.scoped_path(&picked, &roots, &scope, "main").is_err());
+        scope.grant(
+            "main",
+            vec![dir.path().join("picked.txt")],
+            false,
+            std::time::Duration::from_secs(60),
+        );
+        assert!(guard.scoped_path(&picked, &roots, &scope, "main").is_ok());
+
+        let entries = read_entries(&audit.log);
+        assert_eq!(entries.len(), 2);
+        assert!(entries
+            .iter()
+            .all(|e| matches!(e.outcome, AuditOutcome::Rejected { .. })));
+    }
+
+    #[test]
+    fn test_resolvable_path_leaves_dots_to_the_resolver() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        let roots = AllowedRoots::new();
+        let root = roots.add(dir.path()).unwrap();
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let resolve = |raw: &str| guard.resolvable_path(raw, |path| roots.check(path));
+
+        let dotted = dir.path().join("sub").join("..");
+        assert_eq!(resolve(&dotted.to_string_lossy()).unwrap(), root);
+        assert!(read_entries(&audit.log).is_empty());
+
+        assert!(resolve("/tmp/a\0b").is_err());
+        assert!(resolve(&dir.path().join("..").to_string_lossy()).is_err());
+        assert_eq!(read_entries(&audit.log).len(), 2);
+    }
+}
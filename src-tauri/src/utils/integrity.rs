@@ -0,0 +1,335 @@
+//! File integrity monitoring for Tauri applications
+//!
+//! Tamper detection needs to notice when a critical file changes unexpectedly:
+//! 1. `monitor_file` records a file's digest, given or computed on the spot
+//! 2. A background thread re-hashes every monitored file on an interval
+//! 3. A digest that diverges emits one `integrity-violation` event per new content
+//! 4. Files are hashed by streaming, so large files don't need to fit in memory
+//! 5. Monitoring can be paused and resumed, and files can be unmonitored
+
+use super::allowed_roots::AllowedRoots;
+use super::crypto::{hash_file, parse_digest, HashAlgorithm};
+use super::error::AppError;
+use constant_time_eq::constant_time_eq;
+use data_encoding::HEXLOWER;
+use log::{error, info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+/// Event emitted when a monitored file's digest diverges
+pub const INTEGRITY_VIOLATION_EVENT: &str = "integrity-violation";
+
+/// Interval between checks unless `set_integrity_interval` changes it (1 minute)
+pub const DEFAULT_INTEGRITY_INTERVAL_MS: u64 = 60 * 1000;
+
+/// Shortest interval between checks (1 second)
+pub const MIN_INTEGRITY_INTERVAL_MS: u64 = 1000;
+
+/// Most files that may be monitored at once
+pub const MAX_MONITORED_FILES: usize = 1024;
+
+/// A monitored file and the digest it should have
+struct MonitoredFile {
+    path: PathBuf,
+    algorithm: HashAlgorithm,
+    expected: Vec<u8>,
+    /// The divergent digest last reported, so each change is reported once
+    reported: Option<Vec<u8>>,
+}
+
+/// Payload of an `integrity-violation` event
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct IntegrityViolation {
+    /// Id returned by `monitor_file`
+    pub id: String,
+    /// The monitored file
+    pub path: String,
+    /// Lowercase hex digest the file should have
+    pub expected: String,
+    /// Lowercase hex digest the file has now, if it could be read
+    pub actual: Option<String>,
+    /// Why the file couldn't be hashed, e.g. because it was deleted
+    pub error: Option<String>,
+}
+
+/// Files whose digests are checked in the background, in Tauri managed state
+pub struct IntegrityMonitor {
+    files: Mutex<HashMap<String, MonitoredFile>>,
+    paused: AtomicBool,
+    interval_ms: AtomicU64,
+}
+
+impl Default for IntegrityMonitor {
+    fn default() -> Self {
+        Self {
+            files: Mutex::new(HashMap::new()),
+            paused: AtomicBool::new(false),
+            interval_ms: AtomicU64::new(DEFAULT_INTEGRITY_INTERVAL_MS),
+        }
+    }
+}
+
+impl IntegrityMonitor {
+    fn lock_files(&self) -> MutexGuard<'_, HashMap<String, MonitoredFile>> {
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Start monitoring `path`, returning an id for `unmonitor`
+    ///
+    /// Without `expected_hex`, the file's current digest is the expected one.
+    pub fn monitor(
+        &self,
+        path: &Path,
+        algorithm: HashAlgorithm,
+        expected_hex: Option<&str>,
+    ) -> Result<String, AppError> {
+        let expected = match expected_hex {
+            Some(hex) => parse_digest(hex, algorithm)?,
+            None => hash_file(path, algorithm)?,
+        };
+
+        let mut files = self.lock_files();
+        if files.len() >= MAX_MONITORED_FILES {
+            return Err(AppError::Validation(format!(
+                "At most {} files can be monitored",
+                MAX_MONITORED_FILES
+            )));
+        }
+        let id = Uuid::new_v4().to_string();
+        files.insert(
+            id.clone(),
+            MonitoredFile {
+                path: path.to_path_buf(),
+                algorithm,
+                expected,
+                reported: None,
+            },
+        );
+        info!("Monitoring integrity of {}", path.display());
+        Ok(id)
+    }
+
+    /// Stop monitoring the file registered under `id`, returning whether it was
+    pub fn unmonitor(&self, id: &str) -> bool {
+        self.lock_files().remove(id).is_some()
+    }
+
+    /// Skip checks until `resume` is called
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Run checks again after `pause`
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Whether checks are paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Time between two background checks
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms.load(Ordering::SeqCst))
+    }
+
+    /// Change the time between background checks
+    pub fn set_interval(&self, interval: Duration) -> Result<(), AppError> {
+        let interval_ms = u64::try_from(interval.as_millis()).unwrap_or(u64::MAX);
+        if interval_ms < MIN_INTEGRITY_INTERVAL_MS {
+            return Err(AppError::Validation(format!(
+                "Interval must be at least {} ms",
+                MIN_INTEGRITY_INTERVAL_MS
+            )));
+        }
+        self.interval_ms.store(interval_ms, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Hash every monitored file, returning the violations not reported before
+    ///
+    /// Returns nothing while paused. Files are hashed without holding the lock,
+    /// so registering files isn't blocked by a slow check.
+    pub fn check(&self) -> Vec<IntegrityViolation> {
+        if self.is_paused() {
+            return Vec::new();
+        }
+
+        let snapshot: Vec<(String, PathBuf, HashAlgorithm)> = self
+            .lock_files()
+            .iter()
+            .map(|(id, file)| (id.clone(), file.path.clone(), file.algorithm))
+            .collect();
+
+        let mut violations = Vec::new();
+        for (id, path, algorithm) in snapshot {
+            let digest = hash_file(&path, algorithm);
+
+            let mut files = self.lock_files();
+            // The file may have been unmonitored while it was hashed
+            let Some(file) = files.get_mut(&id) else {
+                continue;
+            };
+            let (actual, error) = match digest {
+                Ok(actual) if constant_time_eq(&actual, &file.expected) => {
+                    file.reported = None;
+                    continue;
+                }
+                Ok(actual) => (actual, None),
+                Err(e) => (Vec::new(), Some(e.to_string())),
+            };
+            if file.reported.as_ref() == Some(&actual) {
+                continue;
+            }
+            file.reported = Some(actual.clone());
+
+            warn!("Integrity violation for {}", path.display());
+            violations.push(IntegrityViolation {
+                id,
+                path: path.to_string_lossy().into_owned(),
+                expected: HEXLOWER.encode(&file.expected),
+                actual: error.is_none().then(|| HEXLOWER.encode(&actual)),
+                error,
+            });
+        }
+        violations
+    }
+}
+
+/// Check monitored files in the background for the app's lifetime
+pub fn spawn_integrity_monitor(app: AppHandle) {
+    thread::spawn(move || loop {
+        let monitor = app.state::<IntegrityMonitor>();
+        thread::sleep(monitor.interval());
+        for violation in monitor.check() {
+            if let Err(e) = app.emit(INTEGRITY_VIOLATION_EVENT, violation) {
+                error!("Failed to emit integrity violation: {}", e);
+            }
+        }
+    });
+}
+
+/// Monitor a file within the allowed roots, returning its monitor id
+///
+/// Without `expected_hex`, the file's current digest is the baseline. The
+/// file is hashed off the IPC thread.
+#[tauri::command]
+pub async fn monitor_file(
+    app: AppHandle,
+    path: String,
+    algorithm: HashAlgorithm,
+    expected_hex: Option<String>,
+) -> Result<String, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let roots = app.state::<AllowedRoots>();
+        let path = roots.check(Path::new(&path))?;
+        app.state::<IntegrityMonitor>()
+            .monitor(&path, algorithm, expected_hex.as_deref())
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Monitoring failed: {}", e)))?
+}
+
+/// Stop monitoring a file, returning whether it was monitored
+#[tauri::command]
+pub fn unmonitor_file(monitor: State<'_, IntegrityMonitor>, id: String) -> bool {
+    monitor.unmonitor(&id)
+}
+
+/// Stop checking monitored files until resumed
+#[tauri::command]
+pub fn pause_integrity_monitor(monitor: State<'_, IntegrityMonitor>) {
+    monitor.pause();
+}
+
+/// Resume checking monitored files
+#[tauri::command]
+pub fn resume_integrity_monitor(monitor: State<'_, IntegrityMonitor>) {
+    monitor.resume();
+}
+
+/// Change how often monitored files are checked
+#[tauri::command]
+pub fn set_integrity_interval(
+    monitor: State<'_, IntegrityMonitor>,
+    interval_ms: u64,
+) -> Result<(), AppError> {
+    monitor.set_interval(Duration::from_millis(interval_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_modified_file_fires_one_violation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hosts");
+        fs::write(&path, "127.0.0.1 localhost\n").unwrap();
+
+        let monitor = IntegrityMonitor::default();
+        let id = monitor.monitor(&path, HashAlgorithm::Sha256, None).unwrap();
+        assert!(monitor.check().is_empty());
+
+        fs::write(&path, "127.0.0.1 bank.example\n").unwrap();
+        let violations = monitor.check();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].id, id);
+        assert_ne!(
+            violations[0].actual.as_deref(),
+            Some(&*violations[0].expected)
+        );
+        assert_eq!(violations[0].error, None);
+        // The same tampered content is only reported once
+        assert!(monitor.check().is_empty());
+
+        fs::remove_file(&path).unwrap();
+        let violations = monitor.check();
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].error.is_some());
+    }
+
+    #[test]
+    fn test_unchanged_file_is_quiet() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "secure = true\n").unwrap();
+        let expected = HEXLOWER.encode(&hash_file(&path, HashAlgorithm::Blake3).unwrap());
+
+        let monitor = IntegrityMonitor::default();
+        let id = monitor
+            .monitor(&path, HashAlgorithm::Blake3, Some(&expected))
+            .unwrap();
+        for _ in 0..3 {
+            assert!(monitor.check().is_empty());
+        }
+        assert!(monitor.unmonitor(&id));
+        assert!(!monitor.unmonitor(&id));
+    }
+
+    #[test]
+    fn test_paused_monitor_skips_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hosts");
+        fs::write(&path, "a").unwrap();
+
+        let monitor = IntegrityMonitor::default();
+        monitor.monitor(&path, HashAlgorithm::Sha512, None).unwrap();
+        monitor.pause();
+        fs::write(&path, "b").unwrap();
+        assert!(monitor.check().is_empty());
+
+        monitor.resume();
+        assert_eq!(monitor.check().len(), 1);
+        assert!(monitor.set_interval(Duration::from_millis(10)).is_err());
+    }
+}
@@ -0,0 +1,248 @@
+//! Metadata caching for Tauri applications
+//!
+//! File managers ask for the same paths' metadata over and over:
+//! 1. `MetadataCache` keeps recent `FileInfo` results in managed state, in LRU order
+//! 2. Entries are keyed by the entry's canonical path, so aliases share one slot
+//! 3. Every lookup still stats the file; a changed mtime, size, ctime or
+//!    read-only flag reloads the entry, so a `chmod` is never served stale
+//! 4. `get_file_info` uses the cache when one is managed and works without it
+//! 5. `invalidate_path` and `clear_cache` drop entries explicitly
+//! 6. Followed and unfollowed lookups of a symlink are cached separately
+
+use super::file_system::{canonical_entry_path, file_info, FileInfo};
+use super::fs_backend::{FileSystem, FsMetadata};
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+use tauri::State;
+
+/// Entries kept by `MetadataCache::default`
+pub const DEFAULT_METADATA_CACHE_CAPACITY: usize = 4096;
+
+/// A cache slot: the entry's canonical path, whether a MIME type was detected
+/// and whether symlinks were followed
+type CacheKey = (PathBuf, bool, bool);
+
+/// File information and the stat it was read under
+struct CachedInfo {
+    info: FileInfo,
+    modified: Option<SystemTime>,
+    changed: Option<SystemTime>,
+    len: u64,
+    readonly: bool,
+}
+
+impl CachedInfo {
+    /// Whether `stat` describes the entry exactly as it was cached
+    fn is_current(&self, stat: &FsMetadata) -> bool {
+        self.modified == stat.modified
+            && self.changed == stat.changed
+            && self.len == stat.len
+            && self.readonly == stat.readonly
+    }
+}
+
+/// Recently read file information, in Tauri managed state
+pub struct MetadataCache {
+    entries: Mutex<LruCache<CacheKey, CachedInfo>>,
+}
+
+impl Default for MetadataCache {
+    fn default() -> Self {
+        Self::new(NonZeroUsize::new(DEFAULT_METADATA_CACHE_CAPACITY).expect("capacity is non-zero"))
+    }
+}
+
+impl MetadataCache {
+    /// Create a cache holding at most `capacity` entries
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    fn lock_entries(&self) -> MutexGuard<'_, LruCache<CacheKey, CachedInfo>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Read information for the validated `path`, reusing the cached copy while it is current
+    pub fn file_info(
+        &self,
+        fs: &dyn FileSystem,
+        path: &Path,
+        detect_mime: bool,
+        follow_symlinks: bool,
+    ) -> Result<FileInfo, String> {
+        let stat = if follow_symlinks {
+            fs.metadata(path)
+        } else {
+            fs.symlink_metadata(path)
+        };
+        // Missing files and dangling links are left to the uncached path
+        let (Ok(stat), Some(key)) = (stat, canonical_entry_path(fs, path)) else {
+            return file_info(fs, path, detect_mime, follow_symlinks);
+        };
+        let key = (key, detect_mime, follow_symlinks);
+
+        if let Some(cached) = self.lock_entries().get(&key) {
+            if cached.is_current(&stat) {
+                // The same entry may have been reached through another path
+                let mut info = cached.info.clone();
+                info.path = path.to_string_lossy().into_owned();
+                return Ok(info);
+            }
+        }
+
+        let info = FileInfo::from_path_with(fs, path, detect_mime, follow_symlinks)?;
+        self.lock_entries().put(
+            key,
+            CachedInfo {
+                info: info.clone(),
+                modified: stat.modified,
+                changed: stat.changed,
+                len: stat.len,
+                readonly: stat.readonly,
+            },
+        );
+        Ok(info)
+    }
+
+    /// Drop the cached information for `path`, returning whether there was any
+    pub fn invalidate(&self, fs: &dyn FileSystem, path: &Path) -> bool {
+        let Some(key) = canonical_entry_path(fs, path) else {
+            return false;
+        };
+        let mut entries = self.lock_entries();
+        let mut found = false;
+        for detect_mime in [false, true] {
+            for follow_symlinks in [false, true] {
+                found |= entries
+                    .pop(&(key.clone(), detect_mime, follow_symlinks))
+                    .is_some();
+            }
+        }
+        found
+    }
+
+    /// Drop every cached entry, returning how many there were
+    pub fn clear(&self) -> usize {
+        let mut entries = self.lock_entries();
+        let count = entries.len();
+        entries.clear();
+        count
+    }
+}
+
+/// Forget the cached metadata of one path, e.g. after the frontend changed it
+#[tauri::command]
+pub fn invalidate_path(
+    fs: State<'_, Arc<dyn FileSystem>>,
+    cache: State<'_, MetadataCache>,
+    path: String,
+) -> bool {
+    cache.invalidate(fs.inner().as_ref(), Path::new(&path))
+}
+
+/// Forget all cached metadata, returning how many entries there were
+#[tauri::command]
+pub fn clear_cache(cache: State<'_, MetadataCache>) -> usize {
+    cache.clear()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs_backend::RealFs;
+    use filetime::FileTime;
+    use std::fs;
+
+    #[test]
+    fn test_unchanged_file_is_served_from_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "hello").unwrap();
+        filetime::set_file_atime(&path, FileTime::from_unix_time(1_000_000, 0)).unwrap();
+
+        let cache = MetadataCache::default();
+        let first = cache.file_info(&RealFs, &path, false, true).unwrap();
+        assert_eq!(first.accessed, 1_000_000);
+
+        // Only the access time changes, which doesn't invalidate the entry
+        filetime::set_file_atime(&path, FileTime::from_unix_time(2_000_000, 0)).unwrap();
+        let second = cache.file_info(&RealFs, &path, false, true).unwrap();
+        assert_eq!(second, first);
+
+        assert!(cache.invalidate(&RealFs, &path));
+        let fresh = cache.file_info(&RealFs, &path, false, true).unwrap();
+        assert_eq!(fresh.accessed, 2_000_000);
+    }
+
+    #[test]
+    fn test_changed_mtime_reloads_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "hello").unwrap();
+        filetime::set_file_mtime(&path, FileTime::from_unix_time(1_000_000, 0)).unwrap();
+
+        let cache = MetadataCache::default();
+        let first = cache.file_info(&RealFs, &path, false, true).unwrap();
+        assert_eq!(first.last_modified, 1_000_000);
+
+        filetime::set_file_mtime(&path, FileTime::from_unix_time(1_000_060, 0)).unwrap();
+        let second = cache.file_info(&RealFs, &path, false, true).unwrap();
+        assert_eq!(second.last_modified, 1_000_060);
+        assert_eq!(cache.clear(), 1);
+    }
+
+    #[test]
+    fn test_permission_change_reloads_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        fs::write(&path, "hello").unwrap();
+        filetime::set_file_mtime(&path, FileTime::from_unix_time(1_000_000, 0)).unwrap();
+
+        let cache = MetadataCache::default();
+        assert!(
+            !cache
+                .file_info(&RealFs, &path, false, true)
+                .unwrap()
+                .readonly
+        );
+
+        // Neither mtime nor size changes, only the permissions
+        let mut permissions = fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).unwrap();
+        assert!(
+            cache
+                .file_info(&RealFs, &path, false, true)
+                .unwrap()
+                .readonly
+        );
+    }
+
+    #[test]
+    fn test_least_recently_used_entry_is_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<PathBuf> = ["a", "b", "c"]
+            .iter()
+            .map(|name| dir.path().join(name))
+            .collect();
+        for path in &paths {
+            fs::write(path, "x").unwrap();
+        }
+
+        let cache = MetadataCache::new(NonZeroUsize::new(2).unwrap());
+        cache.file_info(&RealFs, &paths[0], false, true).unwrap();
+        cache.file_info(&RealFs, &paths[1], false, true).unwrap();
+        // Touch `a` so that `b` is the least recently used
+        cache.file_info(&RealFs, &paths[0], false, true).unwrap();
+        cache.file_info(&RealFs, &paths[2], false, true).unwrap();
+
+        assert!(cache.invalidate(&RealFs, &paths[0]));
+        assert!(!cache.invalidate(&RealFs, &paths[1]));
+        assert!(cache.invalidate(&RealFs, &paths[2]));
+    }
+}
@@ -2,8 +2,99 @@
 ///
 /// This module contains various utilities for enhancing application security,
 /// including memory-safe data handling, secure sanitization, and validation.
+// Export the path allowlist submodule
+pub mod allowed_roots;
+// Export the app configuration submodule
+pub mod app_config;
+// Export the safe archive extraction submodule
+pub mod archive;
+// Export the audit log submodule
+pub mod audit;
+// Export the secure clipboard submodule
+pub mod clipboard;
+// Export the file compression submodule
+pub mod compression;
+// Export the configuration submodule
+pub mod config;
+// Export the cryptographic helpers submodule
+pub mod crypto;
+// Export the graceful degradation submodule
+pub mod degraded;
+// Export the file dialog submodule
+pub mod dialog;
+// Export the binary encoding submodule
+pub mod encoding;
+// Export the file entropy submodule
+pub mod entropy;
+// Export the command error submodule
+pub mod error;
+// Export the advisory file lock submodule
+pub mod file_lock;
+// Export the file copy and rename submodule
+pub mod file_ops;
+// Export the file system submodule
+pub mod file_system;
+// Export the file system backend submodule
+pub mod fs_backend;
+// Export the command input guard submodule
+pub mod guard;
+// Export the file integrity monitor submodule
+pub mod integrity;
 // Export the memory-safe submodule
 pub mod memory_safe;
+// Export the file metadata cache submodule
+pub mod metadata_cache;
+// Export the read-once secret file submodule
+pub mod onetime_secret;
+// Export the default handler opener submodule
+pub mod opener;
+// Export the redacted panic hook submodule
+pub mod panic_hook;
+// Export the permission hardening submodule
+pub mod permissions;
+// Export the file preview submodule
+pub mod preview;
+// Export the allowlisted command submodule
+pub mod process;
+// Export the file operation progress submodule
+pub mod progress;
+// Export the log redaction submodule
+pub mod redact;
+// Export the transient error retry submodule
+pub mod retry;
+// Export the sanitizer pipeline submodule
+pub mod sanitize;
+// Export the cancellable scan submodule
+pub mod scan;
+// Export the per-window scoped file access submodule
+pub mod scoped_access;
+// Export the secret loading submodule
+pub mod secrets;
+// Export the security self-check submodule
+#[cfg(feature = "debug_diagnostics")]
+pub mod self_check;
+// Export the secure allocation registry submodule
+pub mod secure_registry;
+// Export the file signing submodule
+pub mod signing;
+// Export the shortcut inspection submodule
+pub mod shortcut;
+// Export the secure file deletion submodule
+pub mod shred;
+// Export the temporary file submodule
+pub mod tempfile;
+// Export the chunked file write submodule
+pub mod upload;
+// Export the short-lived secret vault submodule
+pub mod vault;
+// Export the mounted volume submodule
+pub mod volumes;
+// Export the bounded directory walker submodule
+pub mod walker;
+// Export the file watcher submodule
+pub mod watcher;
+// Export the window capability submodule
+pub mod window_caps;
 
 // Include tests in test mode
 #[cfg(test)]
@@ -0,0 +1,299 @@
+//! Read-once secret files for Tauri applications
+//!
+//! A secret shared through a file should be gone once it has been read:
+//! 1. `write_onetime` creates the file exclusively, readable only by its owner,
+//!    and marks it with `ONETIME_HEADER`
+//! 2. `read_onetime` claims the file with an atomic rename, so of several
+//!    concurrent readers exactly one gets the secret and the rest `NotFound`
+//! 3. Only files `write_onetime` produced are accepted: the header must match,
+//!    and on Unix the file must have a single link and mode `0600`. Anything
+//!    else is renamed back untouched, so the command can't destroy other files
+//! 4. The claimed file is overwritten with zeros and synced before it is unlinked
+//! 5. The secret is read straight into a `SecureString`
+
+use super::allowed_roots::AllowedRoots;
+use super::audit::AuditLog;
+use super::error::AppError;
+use super::file_ops::{resolve_in_roots, resolve_new_in_roots};
+use super::guard::CommandGuard;
+use super::memory_safe::{consume_into_secure, SecureString};
+use super::window_caps::{require_capability, CapabilityToken};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tauri::{State, Window};
+use uuid::Uuid;
+
+/// First line of every file `write_onetime` creates
+pub const ONETIME_HEADER: &str = "tauri-onetime-secret/v1\n";
+
+/// Size of the zero buffer used to overwrite a claimed file
+const SHRED_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A hidden, unique name next to `path` for a claimed secret
+fn claim_path_for(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.claimed", name, Uuid::new_v4()))
+}
+
+/// Write `secret` to a new file at `path`, readable only by its owner
+pub fn write_onetime(path: &Path, secret: &SecureString) -> Result<(), AppError> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => {
+            AppError::Validation(format!("{} already exists", path.display()))
+        }
+        _ => AppError::io("create", path, e),
+    })?;
+    let written = file
+        .write_all(ONETIME_HEADER.as_bytes())
+        .and_then(|_| file.write_all(secret.as_str().as_bytes()))
+        .and_then(|_| file.sync_all());
+    if let Err(e) = written {
+        drop(file);
+        let _ = fs::remove_file(path);
+        return Err(AppError::io("write", path, e));
+    }
+    Ok(())
+}
+
+/// Ensure the claimed file at `path` looks like one `write_onetime` created
+///
+/// Zeroing a file with other hard links would destroy their content too, and
+/// a file readable by others was never a one-time secret.
+fn check_onetime_file(path: &Path) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(path).map_err(|e| e.to_string())?;
+    if !metadata.file_type().is_file() {
+        return Err("not a regular file".into());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if metadata.nlink() != 1 {
+            return Err("the file has other hard links".into());
+        }
+        if metadata.mode() & 0o777 != 0o600 {
+            return Err("the file is not readable by its owner only".into());
+        }
+    }
+    Ok(())
+}
+
+/// Overwrite the file at `path` with zeros and flush it to disk
+fn zero_file(path: &Path) -> io::Result<()> {
+    let mut file = OpenOptions::new().write(true).open(path)?;
+    let mut remaining = file.metadata()?.len();
+    let zeros = vec![0u8; SHRED_CHUNK_SIZE];
+    while remaining > 0 {
+        let chunk = remaining.min(SHRED_CHUNK_SIZE as u64) as usize;
+        file.write_all(&zeros[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    file.sync_all()
+}
+
+/// Claim, read and destroy the secret at `path`, calling `before_unlink` in between
+fn take_onetime<F>(path: &Path, before_unlink: F) -> Result<SecureString, AppError>
+where
+    F: FnOnce(&Path),
+{
+    // Renaming is atomic, so only one reader can move the file away
+    let claimed = claim_path_for(path);
+    fs::rename(path, &claimed).map_err(|e| AppError::io("claim", path, e))?;
+
+    // Anything but a one-time secret is put back as it was
+    let contents = check_onetime_file(&claimed).and_then(|()| {
+        let contents = SecureString::from_file(&claimed).map_err(|e| e.to_string())?;
+        if !contents.as_str().starts_with(ONETIME_HEADER) {
+            return Err("the file was not written as a one-time secret".into());
+        }
+        Ok(contents)
+    });
+    let contents = match contents {
+        Ok(contents) => contents,
+        Err(reason) => {
+            let _ = fs::rename(&claimed, path);
+            return Err(AppError::Validation(format!(
+                "Not a one-time secret: {}: {}",
+                path.display(),
+                reason
+            )));
+        }
+    };
+    let secret = contents.substring(ONETIME_HEADER.len()..);
+    drop(contents);
+
+    let zeroed = zero_file(&claimed);
+    before_unlink(&claimed);
+    let removed = fs::remove_file(&claimed);
+
+    let secret = secret?;
+    zeroed.map_err(|e| AppError::io("overwrite", &claimed, e))?;
+    removed.map_err(|e| AppError::io("remove", &claimed, e))?;
+    Ok(secret)
+}
+
+/// Read the secret at `path` and securely delete the file
+///
+/// A second read, or a read that lost a race, fails with `NotFound`.
+pub fn read_onetime(path: &Path) -> Result<SecureString, AppError> {
+    take_onetime(path, |_| {})
+}
+
+/// Write a secret to a new file within the allowed roots, to be read once
+///
+/// The secret's buffer is moved into a `SecureString` and zeroed after use.
+#[tauri::command]
+pub fn write_onetime_secret(
+    window: Window,
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    path: String,
+    secret: String,
+) -> Result<(), AppError> {
+    let secret = consume_into_secure(secret);
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let guard = CommandGuard::new(&audit, "write_onetime_secret");
+    let path = guard.resolved_path(&path, |path| {
+        resolve_new_in_roots(path, roots.inner()).map_err(|e| AppError::Validation(e.to_string()))
+    })?;
+    write_onetime(&path, &secret)
+}
+
+/// Read a secret written by `write_onetime_secret`, deleting the file
+///
+/// The secret crosses IPC as a plain string, since that is what the
+/// frontend receives either way.
+#[tauri::command]
+pub fn read_onetime_secret(
+    window: Window,
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    path: String,
+) -> Result<String, AppError> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let guard = CommandGuard::new(&audit, "read_onetime_secret");
+    let path = guard.resolved_path(&path, |path| {
+        resolve_in_roots(path, roots.inner()).map_err(|e| AppError::Validation(e.to_string()))
+    })?;
+    let secret = read_onetime(&path)?;
+    Ok(secret.expose_secret().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    #[test]
+    fn test_write_then_read_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.secret");
+        write_onetime(&path, &SecureString::new("launch code")).unwrap();
+        assert!(matches!(
+            write_onetime(&path, &SecureString::new("other")),
+            Err(AppError::Validation(_))
+        ));
+
+        let secret = read_onetime(&path).unwrap();
+        assert_eq!(secret.as_str(), "launch code");
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_second_and_racing_reads_are_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.secret");
+        write_onetime(&path, &SecureString::new("launch code")).unwrap();
+
+        let barrier = Arc::new(Barrier::new(8));
+        let readers: Vec<_> = (0..8)
+            .map(|_| {
+                let path = path.clone();
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    read_onetime(&path).map(|secret| secret.as_str().to_string())
+                })
+            })
+            .collect();
+        let results: Vec<_> = readers.into_iter().map(|r| r.join().unwrap()).collect();
+
+        let winners: Vec<_> = results.iter().filter_map(|r| r.as_ref().ok()).collect();
+        assert_eq!(winners, vec!["launch code"]);
+        assert!(results
+            .iter()
+            .filter(|r| r.is_err())
+            .all(|r| matches!(r, Err(AppError::NotFound(_)))));
+        assert!(matches!(read_onetime(&path), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_file_is_zeroed_before_unlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.secret");
+        write_onetime(&path, &SecureString::new("launch code")).unwrap();
+
+        let mut on_disk = None;
+        let secret =
+            take_onetime(&path, |claimed| on_disk = Some(fs::read(claimed).unwrap())).unwrap();
+        assert_eq!(secret.as_str(), "launch code");
+        assert_eq!(
+            on_disk.unwrap(),
+            vec![0u8; ONETIME_HEADER.len() + "launch code".len()]
+        );
+    }
+
+    #[test]
+    fn test_other_files_are_left_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("thesis.txt");
+        fs::write(&path, "years of work").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+
+        assert!(matches!(read_onetime(&path), Err(AppError::Validation(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "years of work");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_linked_or_shared_secrets_are_refused() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("note.secret");
+        let link = dir.path().join("backup.secret");
+        write_onetime(&path, &SecureString::new("launch code")).unwrap();
+        fs::hard_link(&path, &link).unwrap();
+
+        // Zeroing would have destroyed the other link's content as well
+        assert!(matches!(read_onetime(&path), Err(AppError::Validation(_))));
+        let expected = format!("{}launch code", ONETIME_HEADER);
+        assert_eq!(fs::read_to_string(&link).unwrap(), expected);
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+
+        fs::remove_file(&link).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(matches!(read_onetime(&path), Err(AppError::Validation(_))));
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(read_onetime(&path).unwrap().as_str(), "launch code");
+    }
+}
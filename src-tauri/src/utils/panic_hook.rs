@@ -0,0 +1,171 @@
+//! Redacted panic reporting for Tauri applications
+//!
+//! The default panic hook prints the raw panic message, which may quote a secret:
+//! 1. `install_panic_hook` replaces it once per process; later calls do nothing
+//! 2. Reports carry the location, the thread and the message run through a `Redactor`
+//! 3. Payloads that aren't strings are never `Debug`-printed
+//! 4. Reports include how many secure allocations were live when the panic hit
+//! 5. The most recent reports are kept in memory; with the `debug_diagnostics`
+//!    feature, `recent_panic_reports` returns them
+//!
+//! Secure containers zero themselves in `Drop`, which runs while a panic
+//! unwinds. Release builds abort on panic instead, so nothing is dropped;
+//! their memory goes back to the operating system with the process.
+
+use super::redact::Redactor;
+use super::secure_registry::global_registry;
+use log::error;
+use serde::Serialize;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::panic::{self, Location};
+use std::sync::{Mutex, Once};
+use std::thread;
+
+/// Number of panic reports kept in memory
+pub const MAX_RECENT_PANICS: usize = 16;
+
+/// Message used when the payload is not a string
+const NON_STRING_PAYLOAD: &str = "<non-string panic payload>";
+
+/// Guards the one-time installation of the hook
+static INSTALL: Once = Once::new();
+
+/// The most recent panic reports, oldest first
+static RECENT_PANICS: Mutex<VecDeque<PanicReport>> = Mutex::new(VecDeque::new());
+
+/// A panic, as logged by the hook
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PanicReport {
+    /// Source location of the panic, as `file:line:column`
+    pub location: Option<String>,
+    /// Name of the panicking thread
+    pub thread: String,
+    /// The panic message, with secrets redacted
+    pub message: String,
+    /// Secure allocations still registered when the panic hit, if known
+    pub live_secure_allocations: Option<usize>,
+}
+
+impl PanicReport {
+    /// Build a report from the parts of a panic
+    fn new(
+        redactor: &Redactor,
+        location: Option<&Location<'_>>,
+        payload: &(dyn Any + Send),
+    ) -> Self {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            redactor.redact(message)
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            redactor.redact(message)
+        } else {
+            NON_STRING_PAYLOAD.to_string()
+        };
+
+        Self {
+            location: location.map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            thread: thread::current().name().unwrap_or("<unnamed>").to_string(),
+            message,
+            live_secure_allocations: global_registry().try_live_count(),
+        }
+    }
+}
+
+/// Log `report` and keep it with the recent reports
+fn record(report: PanicReport) {
+    let live = match report.live_secure_allocations {
+        Some(count) => count.to_string(),
+        None => "unknown".to_string(),
+    };
+    error!(
+        "Panic in thread {} at {}: {} ({} secure allocation(s) live)",
+        report.thread,
+        report.location.as_deref().unwrap_or("<unknown>"),
+        report.message,
+        live
+    );
+
+    let mut recent = RECENT_PANICS.lock().unwrap_or_else(|e| e.into_inner());
+    if recent.len() >= MAX_RECENT_PANICS {
+        recent.pop_front();
+    }
+    recent.push_back(report);
+}
+
+/// Replace the panic hook with one that logs redacted reports
+///
+/// Returns whether the hook was installed by this call; it is only ever
+/// installed once, so later calls and their redactors are ignored.
+pub fn install_panic_hook(redactor: Redactor) -> bool {
+    let mut installed = false;
+    INSTALL.call_once(|| {
+        panic::set_hook(Box::new(move |info| {
+            record(PanicReport::new(&redactor, info.location(), info.payload()));
+        }));
+        installed = true;
+    });
+    installed
+}
+
+/// The most recent panic reports, oldest first
+#[cfg(any(test, feature = "debug_diagnostics"))]
+pub fn recent_panics() -> Vec<PanicReport> {
+    RECENT_PANICS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .cloned()
+        .collect()
+}
+
+/// Return the most recent panic reports, oldest first
+///
+/// Only compiled with the `debug_diagnostics` feature.
+#[cfg(feature = "debug_diagnostics")]
+#[tauri::command]
+pub fn recent_panic_reports() -> Vec<PanicReport> {
+    recent_panics()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::memory_safe::SecureString;
+
+    // The hook itself is process-wide, so these tests build and record
+    // reports directly instead of installing it
+
+    #[test]
+    fn test_report_is_redacted_and_recorded() {
+        thread::Builder::new()
+            .name("panic-hook-test".into())
+            .spawn(|| {
+                let payload: Box<dyn Any + Send> = Box::new(format!(
+                    "Login failed for {} with Bearer abc.def",
+                    "alice@example.com"
+                ));
+                let report =
+                    PanicReport::new(&Redactor::default(), Some(Location::caller()), &*payload);
+                record(report);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let report = recent_panics()
+            .into_iter()
+            .find(|report| report.thread == "panic-hook-test")
+            .expect("the hook recorded the panic");
+        assert_eq!(report.message, "Login failed for *** with ***");
+        assert!(report.location.unwrap().contains("panic_hook.rs"));
+    }
+
+    #[test]
+    fn test_non_string_payload_is_not_printed() {
+        let redactor = Redactor::default();
+        let payload: Box<dyn Any + Send> = Box::new(SecureString::new("hunter2"));
+        let report = PanicReport::new(&redactor, None, payload.as_ref());
+        assert_eq!(report.message, NON_STRING_PAYLOAD);
+        assert_eq!(report.location, None);
+    }
+}
@@ -0,0 +1,171 @@
+//! File permission hardening for Tauri applications
+//!
+//! This module tightens permissions across a directory tree:
+//! 1. Only bits beyond the requested baseline are removed; nothing is loosened
+//! 2. The tree must lie within the managed `AllowedRoots`
+//! 3. Symlinks are skipped because changing their mode affects the target
+
+use super::allowed_roots::AllowedRoots;
+use super::file_system::validated_dir;
+#[cfg(unix)]
+use super::walker::{DirectoryWalker, DEFAULT_MAX_ENTRIES_PER_DIR};
+use super::window_caps::{require_capability, CapabilityToken};
+use serde::Serialize;
+#[cfg(unix)]
+use std::fs;
+use std::path::Path;
+use tauri::{State, Window};
+
+/// Permission bits considered when comparing against a baseline
+const MODE_MASK: u32 = 0o7777;
+
+/// A permission change that was made, or would be made in a dry run
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PermissionFix {
+    /// Path of the file or directory
+    pub path: String,
+    /// Mode before the fix
+    pub old_mode: u32,
+    /// Mode after the fix
+    pub new_mode: u32,
+    /// Whether the change was applied (false in a dry run)
+    pub applied: bool,
+}
+
+/// Compute the tightened mode, or `None` if `current` is already within `baseline`
+pub fn tightened_mode(current: u32, baseline: u32) -> Option<u32> {
+    let current = current & MODE_MASK;
+    let tightened = current & baseline & MODE_MASK;
+    (tightened != current).then_some(tightened)
+}
+
+/// Check one path against its baseline and record (and maybe apply) a fix
+#[cfg(unix)]
+fn check_entry(
+    path: &Path,
+    file_mode: u32,
+    dir_mode: u32,
+    dry_run: bool,
+    fixes: &mut Vec<PermissionFix>,
+) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = fs::symlink_metadata(path)
+        .map_err(|e| format!("Failed to read metadata for {}: {}", path.display(), e))?;
+    if metadata.file_type().is_symlink() {
+        return Ok(());
+    }
+
+    let baseline = if metadata.is_dir() {
+        dir_mode
+    } else {
+        file_mode
+    };
+    let old_mode = metadata.permissions().mode() & MODE_MASK;
+
+    if let Some(new_mode) = tightened_mode(old_mode, baseline) {
+        if !dry_run {
+            fs::set_permissions(path, fs::Permissions::from_mode(new_mode))
+                .map_err(|e| format!("Failed to set permissions on {}: {}", path.display(), e))?;
+        }
+
+        fixes.push(PermissionFix {
+            path: path.to_string_lossy().into_owned(),
+            old_mode,
+            new_mode,
+            applied: !dry_run,
+        });
+    }
+
+    Ok(())
+}
+
+/// Tighten permissions under `dir_path` so nothing exceeds the given baseline modes
+#[tauri::command]
+pub fn enforce_secure_permissions(
+    window: Window,
+    roots: State<'_, AllowedRoots>,
+    dir_path: String,
+    file_mode: u32,
+    dir_mode: u32,
+    dry_run: bool,
+) -> Result<Vec<PermissionFix>, String> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let dir = validated_dir(&dir_path)?;
+    let dir = roots.check(&dir)?;
+    enforce_under(&dir, file_mode, dir_mode, dry_run)
+}
+
+/// Tighten permissions of `dir` and everything beneath it
+pub(crate) fn enforce_under(
+    dir: &Path,
+    file_mode: u32,
+    dir_mode: u32,
+    dry_run: bool,
+) -> Result<Vec<PermissionFix>, String> {
+    #[cfg(unix)]
+    {
+        let mut fixes = Vec::new();
+        check_entry(dir, file_mode, dir_mode, dry_run, &mut fixes)?;
+
+        DirectoryWalker::new(true, DEFAULT_MAX_ENTRIES_PER_DIR).walk(dir, |_, entries| {
+            for entry in entries {
+                check_entry(entry, file_mode, dir_mode, dry_run, &mut fixes)?;
+            }
+            Ok(())
+        })?;
+
+        Ok(fixes)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (dir, file_mode, dir_mode, dry_run);
+        Err("Permission enforcement is only supported on Unix platforms".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tightened_mode_never_loosens() {
+        assert_eq!(tightened_mode(0o666, 0o600), Some(0o600));
+        assert_eq!(tightened_mode(0o777, 0o700), Some(0o700));
+        // A stricter mode is left alone rather than raised to the baseline
+        assert_eq!(tightened_mode(0o400, 0o600), None);
+        assert_eq!(tightened_mode(0o640, 0o604), Some(0o600));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_enforce_secure_permissions_dry_run_and_apply() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::set_permissions(dir.path(), fs::Permissions::from_mode(0o700)).unwrap();
+
+        let loose = dir.path().join("loose.txt");
+        let strict = dir.path().join("strict.txt");
+        fs::write(&loose, "x").unwrap();
+        fs::write(&strict, "x").unwrap();
+        fs::set_permissions(&loose, fs::Permissions::from_mode(0o666)).unwrap();
+        fs::set_permissions(&strict, fs::Permissions::from_mode(0o400)).unwrap();
+
+        let mode_of = |p: &Path| fs::metadata(p).unwrap().permissions().mode() & MODE_MASK;
+
+        let planned = enforce_under(dir.path(), 0o600, 0o700, true).unwrap();
+        assert_eq!(planned.len(), 1);
+        assert!(planned[0].path.ends_with("loose.txt"));
+        assert_eq!((planned[0].old_mode, planned[0].new_mode), (0o666, 0o600));
+        assert!(!planned[0].applied);
+        assert_eq!(mode_of(&loose), 0o666);
+
+        let applied = enforce_under(dir.path(), 0o600, 0o700, false).unwrap();
+        assert_eq!(applied.len(), 1);
+        assert!(applied[0].applied);
+        assert_eq!(mode_of(&loose), 0o600);
+        assert_eq!(mode_of(&strict), 0o400);
+    }
+}
@@ -0,0 +1,230 @@
+//! File previews for Tauri applications
+//!
+//! File managers show a glimpse of a file without reading all of it:
+//! 1. The MIME type is detected from content first, then from the extension
+//! 2. Text files yield at most `max_text_bytes` of text, cut at a character
+//! 3. Images yield their dimensions, read from the header alone
+//! 4. Binary and unknown files yield a "no preview" marker
+//! 5. Only a bounded head of the file is ever read
+
+use super::allowed_roots::AllowedRoots;
+use super::audit::AuditLog;
+use super::file_system::{detect_mime_type, ensure_exists_in};
+use super::fs_backend::FileSystem;
+use super::guard::CommandGuard;
+use super::scoped_access::{check_scoped, ScopedAccess};
+use image::io::Reader as ImageReader;
+use serde::Serialize;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::{State, Window};
+
+/// Largest text preview that may be requested (1 MiB)
+pub const MAX_PREVIEW_TEXT_BYTES: usize = 1024 * 1024;
+
+/// Bytes read to find an image's dimensions
+///
+/// Enough for PNG and GIF headers and for JPEG frame headers behind
+/// typical EXIF blocks.
+const IMAGE_HEADER_BYTES: u64 = 256 * 1024;
+
+/// MIME types outside `text/` whose content is text
+const TEXT_MIME_TYPES: &[&str] = &[
+    "application/json",
+    "application/javascript",
+    "application/toml",
+    "application/xml",
+    "application/x-sh",
+    "application/x-yaml",
+];
+
+/// A preview of a file, produced by `generate_preview`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PreviewResult {
+    /// The start of a text file
+    Text {
+        /// Detected MIME type, if any
+        mime_type: Option<String>,
+        /// Text read from the start of the file
+        text: String,
+        /// Whether the file continues past `text`
+        truncated: bool,
+    },
+    /// The dimensions of an image
+    Image {
+        /// Detected MIME type
+        mime_type: String,
+        /// Width in pixels
+        width: u32,
+        /// Height in pixels
+        height: u32,
+    },
+    /// The file is binary, unknown or unreadable as its detected type
+    NoPreview {
+        /// Detected MIME type, if any
+        mime_type: Option<String>,
+    },
+}
+
+/// Whether `mime_type` names a text format
+fn is_text_mime(mime_type: &str) -> bool {
+    mime_type.starts_with("text/") || TEXT_MIME_TYPES.contains(&mime_type)
+}
+
+/// Decode the head of a text file, dropping a character cut off at the end
+///
+/// Returns `None` if the bytes don't look like UTF-8 text.
+fn decode_text_head(bytes: &[u8], lossy: bool) -> Option<String> {
+    if !lossy && bytes.contains(&0) {
+        return None;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Some(text.to_string()),
+        // `error_len` is `None` only when the input ends mid-character
+        Err(e) if e.error_len().is_none() => {
+            Some(String::from_utf8_lossy(&bytes[..e.valid_up_to()]).into_owned())
+        }
+        Err(_) if lossy => Some(String::from_utf8_lossy(bytes).into_owned()),
+        Err(_) => None,
+    }
+}
+
+/// Build a preview of `path` from `fs`
+///
+/// Files without a detected type are previewed as text when their head is
+/// valid UTF-8 without NUL bytes.
+pub(crate) fn preview_file(
+    fs: &dyn FileSystem,
+    path: &Path,
+    max_text_bytes: usize,
+) -> Result<PreviewResult, String> {
+    let metadata = fs
+        .metadata(path)
+        .map_err(|e| format!("Failed to read metadata for {}: {}", path.display(), e))?;
+    if !metadata.is_file {
+        return Err(format!("Not a file: {}", path.display()));
+    }
+
+    let mime_type = detect_mime_type(fs, path);
+    let read_head = |limit: u64| {
+        fs.read_head(path, limit)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    };
+
+    if let Some(mime) = mime_type.as_deref().filter(|m| m.starts_with("image/")) {
+        let head = read_head(IMAGE_HEADER_BYTES)?;
+        let dimensions = ImageReader::new(Cursor::new(head))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok());
+        return Ok(match dimensions {
+            Some((width, height)) => PreviewResult::Image {
+                mime_type: mime.to_string(),
+                width,
+                height,
+            },
+            None => PreviewResult::NoPreview { mime_type },
+        });
+    }
+
+    let known_text = mime_type.as_deref().map(is_text_mime);
+    if known_text == Some(false) {
+        return Ok(PreviewResult::NoPreview { mime_type });
+    }
+
+    let max_text_bytes = max_text_bytes.min(MAX_PREVIEW_TEXT_BYTES);
+    let head = read_head(max_text_bytes as u64)?;
+    let truncated = metadata.len > head.len() as u64;
+    Ok(match decode_text_head(&head, known_text == Some(true)) {
+        Some(text) => PreviewResult::Text {
+            mime_type,
+            text,
+            truncated,
+        },
+        None => PreviewResult::NoPreview { mime_type },
+    })
+}
+
+/// Generate a small preview of a file within the allowed roots
+#[tauri::command]
+pub fn generate_preview(
+    fs: State<'_, Arc<dyn FileSystem>>,
+    roots: State<'_, AllowedRoots>,
+    scope: State<'_, ScopedAccess>,
+    audit: State<'_, AuditLog>,
+    window: Window,
+    path: String,
+    max_text_bytes: usize,
+) -> Result<PreviewResult, String> {
+    let fs = fs.inner().as_ref();
+    let guard = CommandGuard::new(&audit, "generate_preview");
+    let file = guard.resolved_path(&path, |path| -> Result<_, String> {
+        ensure_exists_in(fs, path)?;
+        Ok(check_scoped(&roots, &scope, window.label(), path)?)
+    })?;
+    preview_file(fs, &file, max_text_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs_backend::RealFs;
+    use std::fs;
+
+    #[test]
+    fn test_text_preview_is_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.txt");
+        fs::write(&path, "first line\nsecond line\n").unwrap();
+
+        let preview = preview_file(&RealFs, &path, 10).unwrap();
+        assert_eq!(
+            preview,
+            PreviewResult::Text {
+                mime_type: Some("text/plain".to_string()),
+                text: "first line".to_string(),
+                truncated: true,
+            }
+        );
+
+        // A multi-byte character cut off at the limit is dropped
+        fs::write(&path, "caf\u{e9}").unwrap();
+        let preview = preview_file(&RealFs, &path, 4).unwrap();
+        assert!(matches!(
+            preview,
+            PreviewResult::Text { text, truncated: true, .. } if text == "caf"
+        ));
+    }
+
+    #[test]
+    fn test_png_preview_reports_dimensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pixel.png");
+        image::RgbImage::new(7, 5).save(&path).unwrap();
+
+        let preview = preview_file(&RealFs, &path, 1024).unwrap();
+        assert_eq!(
+            preview,
+            PreviewResult::Image {
+                mime_type: "image/png".to_string(),
+                width: 7,
+                height: 5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_binary_file_has_no_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blob");
+        let data: Vec<u8> = (0..4096u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        fs::write(&path, &data).unwrap();
+
+        let preview = preview_file(&RealFs, &path, 1024).unwrap();
+        assert_eq!(preview, PreviewResult::NoPreview { mime_type: None });
+    }
+}
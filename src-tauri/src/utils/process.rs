@@ -0,0 +1,260 @@
+//! Allowlisted external commands for Tauri applications
+//!
+//! Running programs on behalf of the frontend is an injection risk:
+//! 1. Only logical names from `allowed_commands` in the app config can run
+//! 2. Each name maps to an absolute binary path, so `PATH` is never searched
+//! 3. Arguments go through `BoundaryValidator` and are passed as-is, without a shell
+//! 4. Output is captured up to a size cap and the process is killed on timeout
+//! 5. Only windows holding the `RUN_COMMANDS` capability may run commands
+
+use super::app_config::ConfigState;
+use super::audit::AuditLog;
+use super::error::AppError;
+use super::guard::CommandGuard;
+use super::window_caps::{require_capability, CapabilityToken};
+use log::warn;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Window};
+
+/// Time an allowed command may run before it is killed
+pub const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Bytes of stdout and of stderr kept from a command (1 MiB each)
+pub const MAX_COMMAND_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// Most arguments a command may be given
+pub const MAX_COMMAND_ARGS: usize = 256;
+
+/// How often a running command is checked for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Result of `run_allowed_command`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CommandOutput {
+    /// Exit code, or `None` if the process was ended by a signal
+    pub exit_code: Option<i32>,
+    /// Standard output, lossily decoded as UTF-8
+    pub stdout: String,
+    /// Standard error, lossily decoded as UTF-8
+    pub stderr: String,
+    /// Whether stdout was cut off at `MAX_COMMAND_OUTPUT_BYTES`
+    pub stdout_truncated: bool,
+    /// Whether stderr was cut off at `MAX_COMMAND_OUTPUT_BYTES`
+    pub stderr_truncated: bool,
+}
+
+/// Read `reader` to the end, keeping at most `cap` bytes
+///
+/// The rest is drained and discarded so the child never blocks on a full pipe.
+fn read_capped(mut reader: impl Read, cap: usize) -> (Vec<u8>, bool) {
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut buffer = [0u8; 8192];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => {
+                let room = cap - kept.len();
+                if read > room {
+                    truncated = true;
+                }
+                kept.extend_from_slice(&buffer[..read.min(room)]);
+            }
+        }
+    }
+    (kept, truncated)
+}
+
+/// Run the allowlisted command `name` with `args`
+///
+/// The binary is started directly with each argument passed as a single
+/// value, so shell metacharacters in arguments have no special meaning.
+pub(crate) fn run_allowlisted(
+    allowlist: &BTreeMap<String, String>,
+    guard: &CommandGuard,
+    name: &str,
+    args: &[String],
+    timeout: Duration,
+    max_output: usize,
+) -> Result<CommandOutput, AppError> {
+    let Some(binary) = allowlist.get(name) else {
+        warn!("Refusing to run command {:?}; it is not allowlisted", name);
+        return Err(AppError::Authorization(format!(
+            "Command {} is not allowed",
+            name
+        )));
+    };
+    if !Path::new(binary).is_absolute() {
+        return Err(AppError::Validation(format!(
+            "Command {} must map to an absolute path",
+            name
+        )));
+    }
+    if args.len() > MAX_COMMAND_ARGS {
+        return Err(AppError::Validation(format!(
+            "At most {} arguments are accepted",
+            MAX_COMMAND_ARGS
+        )));
+    }
+    for arg in args {
+        guard.validated_input(arg)?;
+    }
+
+    let mut child = Command::new(binary)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::Io(format!("Failed to start {}: {}", name, e)))?;
+    guard.accept(vec![name.to_string(), format!("{} args", args.len())]);
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let stdout = thread::spawn(move || read_capped(stdout, max_output));
+    let stderr = thread::spawn(move || read_capped(stderr, max_output));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(AppError::Timeout(format!(
+                    "Command {} did not finish within {} ms",
+                    name,
+                    timeout.as_millis()
+                )));
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                let _ = child.kill();
+                return Err(AppError::Io(format!("Failed to wait for {}: {}", name, e)));
+            }
+        }
+    };
+
+    let (stdout, stdout_truncated) = stdout.join().unwrap_or_default();
+    let (stderr, stderr_truncated) = stderr.join().unwrap_or_default();
+    Ok(CommandOutput {
+        exit_code: status.code(),
+        stdout: String::from_utf8_lossy(&stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        stdout_truncated,
+        stderr_truncated,
+    })
+}
+
+/// Run a command from the configured allowlist and capture its output
+#[tauri::command]
+pub async fn run_allowed_command(
+    app: AppHandle,
+    window: Window,
+    name: String,
+    args: Vec<String>,
+) -> Result<CommandOutput, AppError> {
+    require_capability(&window, CapabilityToken::RUN_COMMANDS)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let config = app.state::<ConfigState>().current();
+        let audit = app.state::<AuditLog>();
+        let guard = CommandGuard::new(&audit, "run_allowed_command");
+        run_allowlisted(
+            &config.allowed_commands,
+            &guard,
+            &name,
+            &args,
+            COMMAND_TIMEOUT,
+            MAX_COMMAND_OUTPUT_BYTES,
+        )
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Command failed: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::audit::DEFAULT_MAX_BYTES;
+
+    fn allowlist() -> BTreeMap<String, String> {
+        BTreeMap::from([("echo".to_string(), "/bin/echo".to_string())])
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_runs_allowed_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = AuditLog::new(dir.path().join("audit.log"), DEFAULT_MAX_BYTES, 1);
+        let guard = CommandGuard::new(&audit, "run_allowed_command");
+
+        let args = vec!["hello".to_string(), "world".to_string()];
+        let output =
+            run_allowlisted(&allowlist(), &guard, "echo", &args, COMMAND_TIMEOUT, 1024).unwrap();
+        assert_eq!(output.exit_code, Some(0));
+        assert_eq!(output.stdout, "hello world\n");
+        assert!(!output.stdout_truncated);
+
+        let long = vec!["x".repeat(100)];
+        let output =
+            run_allowlisted(&allowlist(), &guard, "echo", &long, COMMAND_TIMEOUT, 10).unwrap();
+        assert_eq!(output.stdout, "x".repeat(10));
+        assert!(output.stdout_truncated);
+    }
+
+    #[test]
+    fn test_rejects_command_not_in_allowlist() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = AuditLog::new(dir.path().join("audit.log"), DEFAULT_MAX_BYTES, 1);
+        let guard = CommandGuard::new(&audit, "run_allowed_command");
+
+        for name in ["rm", "/bin/echo", "sh"] {
+            let result = run_allowlisted(&allowlist(), &guard, name, &[], COMMAND_TIMEOUT, 1024);
+            assert!(
+                matches!(result, Err(AppError::Authorization(_))),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_shell_metacharacters_are_passed_literally() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit = AuditLog::new(dir.path().join("audit.log"), DEFAULT_MAX_BYTES, 1);
+        let guard = CommandGuard::new(&audit, "run_allowed_command");
+        let marker = dir.path().join("pwned");
+
+        let arg = format!("a; touch {} && echo $(whoami) | cat `id`", marker.display());
+        let output = run_allowlisted(
+            &allowlist(),
+            &guard,
+            "echo",
+            &[arg.clone()],
+            COMMAND_TIMEOUT,
+            1024,
+        )
+        .unwrap();
+        assert_eq!(output.stdout, format!("{}\n", arg));
+        assert!(!marker.exists());
+
+        // Injection patterns are still rejected before anything runs
+        let result = run_allowlisted(
+            &allowlist(),
+            &guard,
+            "echo",
+            &["<script>".to_string()],
+            COMMAND_TIMEOUT,
+            1024,
+        );
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+}
@@ -0,0 +1,294 @@
+//! Progress reporting for long file operations in Tauri applications
+//!
+//! Copies and recursive deletes run off the IPC thread and report as they go:
+//! 1. Starting an operation returns an operation id immediately
+//! 2. `file-progress` events carry bytes done, bytes total and the current path
+//! 3. Events are throttled to about ten per second, plus a final event
+//! 4. `cancel_operation` flips the `CancellationToken` registered for that id
+//! 5. The result is delivered as a `file-operation-complete` event
+
+use super::walker::CancellationToken;
+use log::error;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+/// Event emitted periodically while an operation runs
+pub const PROGRESS_EVENT: &str = "file-progress";
+
+/// Event emitted when an operation finishes, fails or is cancelled
+pub const OPERATION_COMPLETE_EVENT: &str = "file-operation-complete";
+
+/// Shortest time between two progress events for the same operation
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Payload of the `file-progress` event
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ProgressEvent {
+    /// Id returned when the operation was started
+    pub operation_id: String,
+    /// Bytes processed so far
+    pub bytes_done: u64,
+    /// Bytes the operation is expected to process in total
+    pub bytes_total: u64,
+    /// The path being processed when the event was emitted
+    pub current_path: String,
+}
+
+/// Throttles progress updates and passes them to a sink
+///
+/// The sink is a closure so commands can emit Tauri events while tests
+/// collect the events in memory.
+pub struct ProgressReporter<'a> {
+    operation_id: String,
+    bytes_total: u64,
+    interval: Duration,
+    last_emit: Option<Instant>,
+    last_bytes: Option<u64>,
+    cancellation: Option<CancellationToken>,
+    sink: Option<Box<dyn FnMut(ProgressEvent) + 'a>>,
+}
+
+impl<'a> ProgressReporter<'a> {
+    /// Create a reporter passing events for `operation_id` to `sink`
+    pub fn new<F>(operation_id: impl Into<String>, sink: F) -> Self
+    where
+        F: FnMut(ProgressEvent) + 'a,
+    {
+        Self {
+            operation_id: operation_id.into(),
+            bytes_total: 0,
+            interval: PROGRESS_INTERVAL,
+            last_emit: None,
+            last_bytes: None,
+            cancellation: None,
+            sink: Some(Box::new(sink)),
+        }
+    }
+
+    /// Create a reporter that discards every update
+    pub fn silent() -> Self {
+        Self {
+            operation_id: String::new(),
+            bytes_total: 0,
+            interval: PROGRESS_INTERVAL,
+            last_emit: None,
+            last_bytes: None,
+            cancellation: None,
+            sink: None,
+        }
+    }
+
+    /// Set the shortest time between two events
+    #[cfg(test)]
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Let the operation be stopped through `token`
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Set the number of bytes the operation expects to process
+    pub fn set_total(&mut self, bytes_total: u64) {
+        self.bytes_total = bytes_total;
+    }
+
+    /// Check whether the operation should stop
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Report progress, emitting an event unless one was emitted too recently
+    pub fn report(&mut self, bytes_done: u64, current_path: &Path) {
+        let due = match self.last_emit {
+            Some(last) => last.elapsed() >= self.interval,
+            None => true,
+        };
+        if due {
+            self.emit(bytes_done, current_path);
+        }
+    }
+
+    /// Report the final position, which is always emitted unless already sent
+    pub fn finish(&mut self, bytes_done: u64, current_path: &Path) {
+        if self.last_bytes != Some(bytes_done) {
+            self.emit(bytes_done, current_path);
+        }
+    }
+
+    fn emit(&mut self, bytes_done: u64, current_path: &Path) {
+        let Some(sink) = self.sink.as_mut() else {
+            return;
+        };
+        // A file that grew while being copied must not report more than 100%
+        let bytes_total = self.bytes_total.max(bytes_done);
+        sink(ProgressEvent {
+            operation_id: self.operation_id.clone(),
+            bytes_done,
+            bytes_total,
+            current_path: current_path.display().to_string(),
+        });
+        self.last_emit = Some(Instant::now());
+        self.last_bytes = Some(bytes_done);
+    }
+}
+
+/// Registry of in-flight operations, held in Tauri managed state
+#[derive(Default)]
+pub struct OperationRegistry {
+    tokens: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl OperationRegistry {
+    /// Register a new operation and return its id and cancellation token
+    pub fn start(&self) -> (String, CancellationToken) {
+        let operation_id = Uuid::new_v4().to_string();
+        let token = CancellationToken::new();
+        self.tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(operation_id.clone(), token.clone());
+        (operation_id, token)
+    }
+
+    /// Cancel an operation, returning whether it was still running
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        match self
+            .tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(operation_id)
+        {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget an operation once it has finished
+    pub fn finish(&self, operation_id: &str) {
+        self.tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(operation_id);
+    }
+}
+
+/// The final state of a background file operation
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum OperationOutcome<T> {
+    /// The operation ran to completion
+    Completed { result: T },
+    /// The operation was stopped by `cancel_operation`
+    Cancelled,
+    /// The operation failed
+    Failed { error: String },
+}
+
+/// Payload of the `file-operation-complete` event
+#[derive(Clone, Debug, Serialize)]
+pub struct OperationComplete<T> {
+    /// Id returned when the operation was started
+    pub operation_id: String,
+    /// How the operation ended
+    pub outcome: OperationOutcome<T>,
+}
+
+/// Run `operation` in the background, emitting its progress and outcome
+///
+/// `operation` gets a reporter wired to `file-progress` events and to the
+/// operation's cancellation token, and should return `Cancelled` once it
+/// sees the token flipped.
+pub(crate) fn spawn_operation<T, F>(app: AppHandle, operation: F) -> String
+where
+    T: Serialize + Clone + Send + 'static,
+    F: FnOnce(&mut ProgressReporter) -> OperationOutcome<T> + Send + 'static,
+{
+    let (operation_id, token) = app.state::<OperationRegistry>().start();
+
+    let id = operation_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let emitter = app.clone();
+        let mut progress = ProgressReporter::new(id.clone(), move |event| {
+            if let Err(e) = emitter.emit(PROGRESS_EVENT, event) {
+                error!("Failed to emit progress: {}", e);
+            }
+        })
+        .with_cancellation(token);
+        let outcome = operation(&mut progress);
+
+        app.state::<OperationRegistry>().finish(&id);
+        let payload = OperationComplete {
+            operation_id: id,
+            outcome,
+        };
+        if let Err(e) = app.emit(OPERATION_COMPLETE_EVENT, payload) {
+            error!("Failed to emit operation result: {}", e);
+        }
+    });
+
+    operation_id
+}
+
+/// Cancel a running file operation
+#[tauri::command]
+pub fn cancel_operation(
+    operations: State<'_, OperationRegistry>,
+    operation_id: String,
+) -> Result<(), String> {
+    if operations.cancel(&operation_id) {
+        Ok(())
+    } else {
+        Err(format!("No running operation with id {}", operation_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reporter_throttles_but_always_sends_final_event() {
+        let mut events = Vec::new();
+        let mut progress = ProgressReporter::new("op", |event| events.push(event))
+            .with_interval(Duration::from_secs(60));
+        progress.set_total(300);
+        for done in [100, 200, 300] {
+            progress.report(done, Path::new("file"));
+        }
+        progress.finish(300, Path::new("file"));
+        drop(progress);
+
+        let done: Vec<u64> = events.iter().map(|e| e.bytes_done).collect();
+        assert_eq!(done, vec![100, 300]);
+        assert!(events
+            .iter()
+            .all(|e| e.operation_id == "op" && e.bytes_total == 300));
+    }
+
+    #[test]
+    fn test_registry_cancels_operation() {
+        let registry = OperationRegistry::default();
+        let (id, token) = registry.start();
+        assert!(registry.cancel(&id));
+        assert!(token.is_cancelled());
+
+        let progress = ProgressReporter::silent().with_cancellation(token);
+        assert!(progress.is_cancelled());
+        registry.finish(&id);
+        assert!(!registry.cancel(&id));
+    }
+}
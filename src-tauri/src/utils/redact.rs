@@ -0,0 +1,99 @@
+//! Redaction of secrets from log messages
+//!
+//! Free-form messages can carry secrets that never went through `SecureString`:
+//! 1. A `Redactor` holds a configurable set of regular expressions
+//! 2. Every match is replaced with `***` before the message is logged
+//! 3. The default set covers emails, card-like digit runs and bearer tokens
+
+use regex::Regex;
+
+/// Replacement for redacted text
+pub const REDACTION: &str = "***";
+
+/// Patterns used by `Redactor::default`
+pub const DEFAULT_REDACTION_PATTERNS: &[&str] = &[
+    // Email addresses
+    r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}",
+    // Card-like runs of 13 to 19 digits, optionally separated by spaces or dashes
+    r"\b\d(?:[ -]?\d){12,18}\b",
+    // Bearer tokens, e.g. from an `Authorization` header
+    r"(?i)\bbearer\s+[A-Za-z0-9\-._~+/]+=*",
+];
+
+/// Replaces matches of a set of patterns with `***`
+#[derive(Clone, Debug)]
+pub struct Redactor {
+    patterns: Vec<Regex>,
+}
+
+impl Redactor {
+    /// Create a redactor from regular expressions
+    pub fn new<I, S>(patterns: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                Regex::new(pattern.as_ref())
+                    .map_err(|e| format!("Invalid redaction pattern {:?}: {}", pattern.as_ref(), e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Whether any pattern matches `text`
+    pub fn is_match(&self, text: &str) -> bool {
+        self.patterns.iter().any(|pattern| pattern.is_match(text))
+    }
+
+    /// Redact every match of every pattern in `message`
+    pub fn redact(&self, message: &str) -> String {
+        self.patterns
+            .iter()
+            .fold(message.to_string(), |text, pattern| {
+                pattern.replace_all(&text, REDACTION).into_owned()
+            })
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(DEFAULT_REDACTION_PATTERNS).expect("default redaction patterns are valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_email_and_token() {
+        let redactor = Redactor::default();
+        let message =
+            "Login failed for alice@example.com with Authorization: Bearer eyJhbGciOi.abc-123";
+
+        let redacted = redactor.redact(message);
+        assert_eq!(redacted, "Login failed for *** with Authorization: ***");
+        assert_eq!(
+            redactor.redact("charge 4111 1111 1111 1111 declined"),
+            "charge *** declined"
+        );
+    }
+
+    #[test]
+    fn test_ordinary_text_is_untouched() {
+        let redactor = Redactor::default();
+        let message = "Failed to read /tmp/report.txt: permission denied (os error 13)";
+        assert_eq!(redactor.redact(message), message);
+    }
+
+    #[test]
+    fn test_custom_patterns() {
+        let redactor = Redactor::new([r"sk_live_\w+"]).unwrap();
+        assert_eq!(redactor.redact("key sk_live_abc123"), "key ***");
+        assert_eq!(redactor.redact("bob@example.com"), "bob@example.com");
+        assert!(Redactor::new(["("]).is_err());
+    }
+}
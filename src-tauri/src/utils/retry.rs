@@ -0,0 +1,186 @@
+//! Retries for transient file system errors in Tauri applications
+//!
+//! Another process briefly holding a file, such as a virus scanner or an
+//! indexer, makes renames and deletes fail even though a moment later they
+//! would succeed:
+//! 1. `retry` reruns an operation that failed with a transient error
+//! 2. Which errors count as transient is configurable in `RetryPolicy`
+//! 3. The delay between attempts doubles each time, up to a cap
+//! 4. Any other error, or running out of attempts, returns the last error
+
+use log::warn;
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+/// OS error codes reported while another process holds the file
+const TRANSIENT_OS_ERRORS: &[i32] = if cfg!(windows) {
+    // ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION
+    &[32, 33]
+} else if cfg!(unix) {
+    // EBUSY, ETXTBSY
+    &[16, 26]
+} else {
+    &[]
+};
+
+/// How `retry` decides whether and when to try again
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first; at least one is always made
+    pub max_attempts: u32,
+    /// Delay before the second attempt
+    pub initial_delay: Duration,
+    /// Longest delay between two attempts
+    pub max_delay: Duration,
+    /// Error kinds worth retrying
+    pub transient_kinds: Vec<io::ErrorKind>,
+    /// Raw OS error codes worth retrying, e.g. sharing violations
+    pub transient_os_errors: Vec<i32>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(50),
+            max_delay: Duration::from_secs(1),
+            transient_kinds: vec![
+                io::ErrorKind::Interrupted,
+                io::ErrorKind::WouldBlock,
+                io::ErrorKind::TimedOut,
+            ],
+            transient_os_errors: TRANSIENT_OS_ERRORS.to_vec(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether `error` may go away if the operation is tried again
+    pub fn is_transient(&self, error: &io::Error) -> bool {
+        self.transient_kinds.contains(&error.kind())
+            || error
+                .raw_os_error()
+                .is_some_and(|code| self.transient_os_errors.contains(&code))
+    }
+}
+
+/// Run `op`, retrying transient failures as `policy` allows
+pub fn retry<T, F>(policy: &RetryPolicy, op: F) -> io::Result<T>
+where
+    F: FnMut() -> io::Result<T>,
+{
+    retry_with(policy, thread::sleep, op)
+}
+
+/// Like `retry`, waiting between attempts with `sleep`
+pub(crate) fn retry_with<T, F, S>(policy: &RetryPolicy, mut sleep: S, mut op: F) -> io::Result<T>
+where
+    F: FnMut() -> io::Result<T>,
+    S: FnMut(Duration),
+{
+    let mut delay = policy.initial_delay;
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.max_attempts && policy.is_transient(&e) => {
+                warn!(
+                    "Attempt {} of {} failed, retrying in {:?}: {}",
+                    attempt, policy.max_attempts, delay, e
+                );
+                sleep(delay);
+                delay = (delay * 2).min(policy.max_delay);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn busy() -> io::Error {
+        io::Error::new(io::ErrorKind::WouldBlock, "file is in use")
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 4,
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(25),
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_succeeds_after_transient_failures() {
+        let mut calls = 0;
+        let mut delays = Vec::new();
+        let result = retry_with(
+            &policy(),
+            |delay| delays.push(delay),
+            || {
+                calls += 1;
+                if calls <= 2 {
+                    Err(busy())
+                } else {
+                    Ok(calls)
+                }
+            },
+        );
+
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(
+            delays,
+            vec![Duration::from_millis(10), Duration::from_millis(20)]
+        );
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let mut calls = 0;
+        let mut delays = Vec::new();
+        let result: io::Result<()> = retry_with(
+            &policy(),
+            |delay| delays.push(delay),
+            || {
+                calls += 1;
+                Err(busy())
+            },
+        );
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(calls, 4);
+        // The delay doubles but never exceeds the cap
+        assert_eq!(
+            delays,
+            vec![
+                Duration::from_millis(10),
+                Duration::from_millis(20),
+                Duration::from_millis(25)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_permanent_errors_fail_immediately() {
+        let mut calls = 0;
+        let result: io::Result<()> = retry_with(
+            &policy(),
+            |_| panic!("permanent errors must not be retried"),
+            || {
+                calls += 1;
+                Err(io::Error::from(io::ErrorKind::NotFound))
+            },
+        );
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+        assert_eq!(calls, 1);
+        if let Some(&code) = TRANSIENT_OS_ERRORS.first() {
+            assert!(policy().is_transient(&io::Error::from_raw_os_error(code)));
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Composable string sanitization for Tauri applications
+//!
+//! Apps differ in how untrusted text must be cleaned before use:
+//! 1. `SanitizeStage` is one step that takes a string and returns a cleaned one
+//! 2. `SanitizerPipeline` runs an ordered list of stages, each on the last one's output
+//! 3. The built-in stages cover encoding, control character stripping,
+//!    Unicode normalization and truncation
+//! 4. Closures are stages too, so app-specific steps need no new type
+//!
+//! Order matters: truncating after encoding can cut an entity in half, so a
+//! pipeline meant for HTML should truncate first and encode last.
+
+use super::memory_safe::{BoundaryValidator, SanitizeContext, BIDI_CONTROLS, ZERO_WIDTH_CHARS};
+use std::fmt;
+use unicode_normalization::UnicodeNormalization;
+
+/// One step of a `SanitizerPipeline`
+pub trait SanitizeStage: Send + Sync {
+    /// Clean `input`, returning the result
+    fn apply(&self, input: String) -> String;
+}
+
+impl<F> SanitizeStage for F
+where
+    F: Fn(String) -> String + Send + Sync,
+{
+    fn apply(&self, input: String) -> String {
+        self(input)
+    }
+}
+
+/// Encode the string for the context it will be embedded in
+///
+/// See `BoundaryValidator::sanitize_for`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Encode(pub SanitizeContext);
+
+impl SanitizeStage for Encode {
+    fn apply(&self, input: String) -> String {
+        BoundaryValidator::sanitize_for(&input, self.0)
+    }
+}
+
+/// Remove control characters and bidirectional overrides, keeping `\n` and `\t`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StripControl;
+
+impl SanitizeStage for StripControl {
+    fn apply(&self, input: String) -> String {
+        input
+            .chars()
+            .filter(|&c| c == '\n' || c == '\t' || !(c.is_control() || BIDI_CONTROLS.contains(&c)))
+            .collect()
+    }
+}
+
+/// Remove zero-width characters and apply Unicode NFC normalization
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NormalizeUnicode;
+
+impl SanitizeStage for NormalizeUnicode {
+    fn apply(&self, input: String) -> String {
+        input
+            .chars()
+            .filter(|c| !ZERO_WIDTH_CHARS.contains(c))
+            .nfc()
+            .collect()
+    }
+}
+
+/// Keep at most this many characters, never splitting one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Truncate(pub usize);
+
+impl SanitizeStage for Truncate {
+    fn apply(&self, mut input: String) -> String {
+        if let Some((end, _)) = input.char_indices().nth(self.0) {
+            input.truncate(end);
+        }
+        input
+    }
+}
+
+/// An ordered list of stages, applied one after another
+#[derive(Default)]
+pub struct SanitizerPipeline {
+    stages: Vec<Box<dyn SanitizeStage>>,
+}
+
+impl SanitizerPipeline {
+    /// Create a pipeline with no stages, which returns its input unchanged
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `stage`, to run after the stages already added
+    pub fn stage(mut self, stage: impl SanitizeStage + 'static) -> Self {
+        self.stages.push(Box::new(stage));
+        self
+    }
+
+    /// Run every stage on `input` in order
+    pub fn apply(&self, input: String) -> String {
+        self.stages
+            .iter()
+            .fold(input, |text, stage| stage.apply(text))
+    }
+}
+
+/// A pipeline is a stage itself, so pipelines can be nested
+impl SanitizeStage for SanitizerPipeline {
+    fn apply(&self, input: String) -> String {
+        SanitizerPipeline::apply(self, input)
+    }
+}
+
+impl fmt::Debug for SanitizerPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SanitizerPipeline")
+            .field("stages", &self.stages.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stages_run_in_order() {
+        let pipeline = SanitizerPipeline::new()
+            .stage(StripControl)
+            .stage(Truncate(8))
+            .stage(Encode(SanitizeContext::Html));
+
+        let input = "<b>\u{0007}hi\u{202E}</b> and more".to_string();
+        assert_eq!(pipeline.apply(input), "&lt;b&gt;hi&lt;/b");
+    }
+
+    #[test]
+    fn test_encoding_before_truncating_splits_entities() {
+        let truncate_first = SanitizerPipeline::new()
+            .stage(Truncate(6))
+            .stage(Encode(SanitizeContext::Html));
+        let encode_first = SanitizerPipeline::new()
+            .stage(Encode(SanitizeContext::Html))
+            .stage(Truncate(6));
+
+        let input = "<<<<<<<<".to_string();
+        assert_eq!(
+            truncate_first.apply(input.clone()),
+            "&lt;&lt;&lt;&lt;&lt;&lt;"
+        );
+        assert_eq!(encode_first.apply(input), "&lt;&l");
+    }
+
+    #[test]
+    fn test_custom_and_nested_stages() {
+        let normalize = SanitizerPipeline::new()
+            .stage(NormalizeUnicode)
+            .stage(|s: String| s.trim().to_string());
+        let pipeline = SanitizerPipeline::new().stage(normalize).stage(Truncate(4));
+
+        // `e` followed by a combining acute accent composes to one character
+        let input = "  cafe\u{0301}\u{200B}s  ".to_string();
+        assert_eq!(pipeline.apply(input), "caf\u{00E9}");
+        assert_eq!(SanitizerPipeline::new().apply("as is".into()), "as is");
+    }
+}
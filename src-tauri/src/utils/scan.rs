@@ -0,0 +1,632 @@
+//! Cancellable background directory scans for Tauri applications
+//!
+//! Long recursive scans run off the IPC thread so the user can stop them:
+//! 1. `start_directory_scan` returns a scan id immediately
+//! 2. `cancel_scan` flips the `CancellationToken` registered for that id
+//! 3. Entries arrive in `scan-batch` events, sent when a batch fills up or
+//!    has waited for the flush interval
+//! 4. A bounded channel between the walk and the emitter slows the walk down
+//!    when the frontend can't keep up
+//! 5. The outcome is delivered as a `scan-complete` event
+//! 6. `start_directory_stream` lists one directory unsorted, sending each
+//!    entry in a `scan-entry` event as soon as it is read
+
+use super::allowed_roots::AllowedRoots;
+use super::app_config::ConfigState;
+use super::file_system::{validated_dir_in, FileInfo};
+use super::fs_backend::FileSystem;
+use super::walker::{CancellationToken, DirectoryWalker, DEFAULT_MAX_ENTRIES_PER_DIR};
+use log::{error, info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::async_runtime::{self, JoinHandle, Receiver};
+use tauri::{AppHandle, Emitter, Manager, State};
+use uuid::Uuid;
+
+/// Event emitted when a scan finishes, fails or is cancelled
+pub const SCAN_COMPLETE_EVENT: &str = "scan-complete";
+
+/// Event emitted with each batch of scanned entries
+pub const SCAN_BATCH_EVENT: &str = "scan-batch";
+
+/// Event emitted with each entry of a streamed listing
+pub const SCAN_ENTRY_EVENT: &str = "scan-entry";
+
+/// Entries a streamed listing may read ahead of its consumer
+pub const STREAM_CHANNEL_CAPACITY: usize = 64;
+
+/// Default number of entries per `scan-batch` event
+pub const DEFAULT_SCAN_BATCH_SIZE: usize = 256;
+
+/// Default time a partial batch may wait before it is emitted
+pub const DEFAULT_SCAN_FLUSH_MS: u64 = 250;
+
+/// Largest batch size accepted from the app config
+pub const MAX_SCAN_BATCH_SIZE: usize = 10_000;
+
+/// Shortest flush interval accepted from the app config
+pub const MIN_SCAN_FLUSH_MS: u64 = 10;
+
+/// Longest flush interval accepted from the app config (10 seconds)
+pub const MAX_SCAN_FLUSH_MS: u64 = 10_000;
+
+/// Registry of in-flight scans, held in Tauri managed state
+#[derive(Default)]
+pub struct ScanRegistry {
+    tokens: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl ScanRegistry {
+    /// Register a new scan and return its id and cancellation token
+    pub fn start(&self) -> (String, CancellationToken) {
+        let scan_id = Uuid::new_v4().to_string();
+        let token = CancellationToken::new();
+        self.tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(scan_id.clone(), token.clone());
+        (scan_id, token)
+    }
+
+    /// Cancel a scan, returning whether it was still running
+    pub fn cancel(&self, scan_id: &str) -> bool {
+        match self
+            .tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(scan_id)
+        {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forget a scan once it has finished
+    pub fn finish(&self, scan_id: &str) {
+        self.tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(scan_id);
+    }
+}
+
+/// The final state of a background scan
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ScanOutcome {
+    /// The scan ran to completion; its entries were sent in `scan-batch` events
+    Completed {
+        /// Number of entries sent across all batches
+        entry_count: usize,
+        /// Directories whose entries were only partially listed
+        truncated_dirs: Vec<String>,
+    },
+    /// The scan was stopped by `cancel_scan`
+    Cancelled,
+    /// The scan failed
+    Failed { error: String },
+}
+
+/// Payload of the `scan-complete` event
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanComplete {
+    /// Id returned by `start_directory_scan`
+    pub scan_id: String,
+    /// How the scan ended
+    pub outcome: ScanOutcome,
+}
+
+/// Payload of the `scan-batch` event
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanBatch {
+    /// Id returned by `start_directory_scan`
+    pub scan_id: String,
+    /// Entries found since the previous batch, in walk order
+    pub entries: Vec<FileInfo>,
+}
+
+/// How scanned entries are grouped into `scan-batch` events
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScanBatching {
+    /// Entries per batch
+    pub batch_size: usize,
+    /// Longest a partial batch waits before it is emitted
+    pub flush_interval: Duration,
+}
+
+impl Default for ScanBatching {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_SCAN_BATCH_SIZE,
+            flush_interval: Duration::from_millis(DEFAULT_SCAN_FLUSH_MS),
+        }
+    }
+}
+
+/// Collect entries from `entries` into batches and pass them to `emit`
+///
+/// A batch is emitted when it is full, when its first entry has waited for
+/// `flush_interval`, or when the walk ends. Returns the number of entries.
+fn emit_batches<F>(entries: mpsc::Receiver<FileInfo>, batching: ScanBatching, mut emit: F) -> usize
+where
+    F: FnMut(Vec<FileInfo>),
+{
+    let mut count = 0;
+    let mut batch = Vec::with_capacity(batching.batch_size);
+    let mut deadline: Option<Instant> = None;
+
+    loop {
+        let received = match deadline {
+            Some(deadline) => {
+                entries.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => entries.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(entry) => {
+                if batch.is_empty() {
+                    deadline = Some(Instant::now() + batching.flush_interval);
+                }
+                batch.push(entry);
+                if batch.len() >= batching.batch_size {
+                    count += batch.len();
+                    emit(mem::take(&mut batch));
+                    deadline = None;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                count += batch.len();
+                emit(mem::take(&mut batch));
+                deadline = None;
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                if !batch.is_empty() {
+                    count += batch.len();
+                    emit(batch);
+                }
+                return count;
+            }
+        }
+    }
+}
+
+/// Run a scan to completion or cancellation, passing entries to `emit` in batches
+///
+/// The walk runs on its own thread and hands entries over through a channel
+/// holding at most one batch, so it blocks while `emit` is slow.
+pub(crate) fn run_scan<F>(
+    fs: &dyn FileSystem,
+    walker: &DirectoryWalker,
+    dir: &Path,
+    batching: ScanBatching,
+    emit: F,
+) -> ScanOutcome
+where
+    F: FnMut(Vec<FileInfo>),
+{
+    let batching = ScanBatching {
+        batch_size: batching.batch_size.max(1),
+        ..batching
+    };
+    let (sender, receiver) = mpsc::sync_channel(batching.batch_size);
+
+    let (walked, entry_count) = thread::scope(|scope| {
+        let walk = scope.spawn(move || {
+            walker.walk_in(fs, dir, |_, dir_entries| {
+                for entry in dir_entries {
+                    match FileInfo::from_path_in(fs, entry, false) {
+                        Ok(info) => sender
+                            .send(info)
+                            .map_err(|_| "Scan consumer stopped".to_string())?,
+                        Err(e) => warn!("Skipping unreadable entry: {}", e),
+                    }
+                }
+                Ok(())
+            })
+        });
+        let entry_count = emit_batches(receiver, batching, emit);
+        let walked = walk
+            .join()
+            .unwrap_or_else(|_| Err("Scan thread panicked".to_string()));
+        (walked, entry_count)
+    });
+
+    match walked {
+        Ok(summary) if summary.cancelled => ScanOutcome::Cancelled,
+        Ok(summary) => ScanOutcome::Completed {
+            entry_count,
+            truncated_dirs: summary.truncated_dirs,
+        },
+        Err(error) => ScanOutcome::Failed { error },
+    }
+}
+
+/// Payload of the `scan-entry` event
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanEntry {
+    /// Id returned by `start_directory_stream`
+    pub scan_id: String,
+    /// The entry just read
+    pub entry: FileInfo,
+}
+
+/// Read the entries of `dir` on a blocking thread and send them as they come
+///
+/// Entries arrive in the order the backend returns them, so the first is
+/// available long before a large directory has been read. The handle
+/// resolves to the outcome once the read ends; dropping the receiver stops
+/// the read, which then reports `Cancelled`.
+pub(crate) fn stream_directory(
+    fs: Arc<dyn FileSystem>,
+    dir: PathBuf,
+    max_entries: usize,
+    token: CancellationToken,
+) -> (Receiver<FileInfo>, JoinHandle<ScanOutcome>) {
+    let (sender, receiver) = async_runtime::channel(STREAM_CHANNEL_CAPACITY);
+    let reader = async_runtime::spawn_blocking(move || {
+        let entries = match fs.read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                return ScanOutcome::Failed {
+                    error: format!("Failed to read {}: {}", dir.display(), e),
+                }
+            }
+        };
+
+        let mut entry_count = 0;
+        for entry in entries {
+            if token.is_cancelled() {
+                return ScanOutcome::Cancelled;
+            }
+            if entry_count >= max_entries {
+                return ScanOutcome::Completed {
+                    entry_count,
+                    truncated_dirs: vec![dir.to_string_lossy().into_owned()],
+                };
+            }
+            let info = entry
+                .map_err(|e| format!("Failed to read an entry of {}: {}", dir.display(), e))
+                .and_then(|path| FileInfo::from_path_in(fs.as_ref(), &path, false));
+            match info {
+                Ok(info) => {
+                    if sender.blocking_send(info).is_err() {
+                        return ScanOutcome::Cancelled;
+                    }
+                    entry_count += 1;
+                }
+                Err(e) => warn!("Skipping unreadable entry: {}", e),
+            }
+        }
+        ScanOutcome::Completed {
+            entry_count,
+            truncated_dirs: Vec::new(),
+        }
+    });
+    (receiver, reader)
+}
+
+/// Start listing a directory in the background and return the scan id
+#[tauri::command]
+pub fn start_directory_scan(
+    app: AppHandle,
+    scans: State<'_, ScanRegistry>,
+    fs: State<'_, Arc<dyn FileSystem>>,
+    roots: State<'_, AllowedRoots>,
+    config: State<'_, ConfigState>,
+    dir_path: String,
+    recursive: Option<bool>,
+    max_entries_per_dir: Option<usize>,
+) -> Result<String, String> {
+    let fs = Arc::clone(fs.inner());
+    let dir = validated_dir_in(fs.as_ref(), &dir_path)?;
+    let dir = roots.check(&dir)?;
+    let (scan_id, token) = scans.start();
+    let walker = DirectoryWalker::new(
+        recursive.unwrap_or(true),
+        max_entries_per_dir.unwrap_or(DEFAULT_MAX_ENTRIES_PER_DIR),
+    )
+    .with_cancellation(token);
+    let config = config.current();
+    let batching = ScanBatching {
+        batch_size: config.scan_batch_size,
+        flush_interval: Duration::from_millis(config.scan_flush_ms),
+    };
+
+    let id = scan_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let outcome = run_scan(fs.as_ref(), &walker, &dir, batching, |entries| {
+            let payload = ScanBatch {
+                scan_id: id.clone(),
+                entries,
+            };
+            if let Err(e) = app.emit(SCAN_BATCH_EVENT, payload) {
+                error!("Failed to emit scan batch: {}", e);
+            }
+        });
+        if outcome == ScanOutcome::Cancelled {
+            info!("Scan {} cancelled", id);
+        }
+
+        app.state::<ScanRegistry>().finish(&id);
+        let payload = ScanComplete {
+            scan_id: id,
+            outcome,
+        };
+        if let Err(e) = app.emit(SCAN_COMPLETE_EVENT, payload) {
+            error!("Failed to emit scan result: {}", e);
+        }
+    });
+
+    Ok(scan_id)
+}
+
+/// Start streaming a single directory's entries and return the scan id
+///
+/// Entries are unsorted; use `list_directory` when order matters. The scan
+/// can be stopped with `cancel_scan` and ends with a `scan-complete` event.
+#[tauri::command]
+pub fn start_directory_stream(
+    app: AppHandle,
+    scans: State<'_, ScanRegistry>,
+    fs: State<'_, Arc<dyn FileSystem>>,
+    roots: State<'_, AllowedRoots>,
+    dir_path: String,
+    max_entries: Option<usize>,
+) -> Result<String, String> {
+    let fs = Arc::clone(fs.inner());
+    let dir = validated_dir_in(fs.as_ref(), &dir_path)?;
+    let dir = roots.check(&dir)?;
+    let (scan_id, token) = scans.start();
+    let (mut entries, reader) = stream_directory(
+        fs,
+        dir,
+        max_entries.unwrap_or(DEFAULT_MAX_ENTRIES_PER_DIR),
+        token,
+    );
+
+    let id = scan_id.clone();
+    async_runtime::spawn(async move {
+        while let Some(entry) = entries.recv().await {
+            let payload = ScanEntry {
+                scan_id: id.clone(),
+                entry,
+            };
+            if let Err(e) = app.emit(SCAN_ENTRY_EVENT, payload) {
+                error!("Failed to emit scan entry: {}", e);
+            }
+        }
+        let outcome = reader.await.unwrap_or_else(|e| ScanOutcome::Failed {
+            error: format!("Stream reader failed: {}", e),
+        });
+        if outcome == ScanOutcome::Cancelled {
+            info!("Scan {} cancelled", id);
+        }
+
+        app.state::<ScanRegistry>().finish(&id);
+        let payload = ScanComplete {
+            scan_id: id,
+            outcome,
+        };
+        if let Err(e) = app.emit(SCAN_COMPLETE_EVENT, payload) {
+            error!("Failed to emit scan result: {}", e);
+        }
+    });
+
+    Ok(scan_id)
+}
+
+/// Cancel a running scan
+#[tauri::command]
+pub fn cancel_scan(scans: State<'_, ScanRegistry>, scan_id: String) -> Result<(), String> {
+    if scans.cancel(&scan_id) {
+        Ok(())
+    } else {
+        Err(format!("No running scan with id {}", scan_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fs_backend::RealFs;
+    use std::fs;
+
+    #[test]
+    fn test_registry_cancels_independently() {
+        let registry = ScanRegistry::default();
+        let (first_id, first) = registry.start();
+        let (_, second) = registry.start();
+
+        assert!(registry.cancel(&first_id));
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+
+        registry.finish(&first_id);
+        assert!(!registry.cancel(&first_id));
+    }
+
+    #[test]
+    fn test_cancelled_scan_stops_before_completing() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..200 {
+            let sub = dir.path().join(format!("dir{:03}", i));
+            fs::create_dir(&sub).unwrap();
+            fs::write(sub.join("file.txt"), "x").unwrap();
+        }
+
+        let registry = ScanRegistry::default();
+        let (scan_id, token) = registry.start();
+        let walker =
+            DirectoryWalker::new(true, DEFAULT_MAX_ENTRIES_PER_DIR).with_cancellation(token);
+
+        assert!(registry.cancel(&scan_id));
+        assert_eq!(
+            run_scan(
+                &RealFs,
+                &walker,
+                dir.path(),
+                ScanBatching::default(),
+                |_| {}
+            ),
+            ScanOutcome::Cancelled
+        );
+
+        // The same fixture completes when nobody cancels it
+        let (_, token) = registry.start();
+        let walker =
+            DirectoryWalker::new(true, DEFAULT_MAX_ENTRIES_PER_DIR).with_cancellation(token);
+        assert!(matches!(
+            run_scan(
+                &RealFs,
+                &walker,
+                dir.path(),
+                ScanBatching::default(),
+                |_| {}
+            ),
+            ScanOutcome::Completed {
+                entry_count: 400,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_scan_emits_full_batches_then_flushes_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..600 {
+            fs::write(dir.path().join(format!("file{:03}.txt", i)), "x").unwrap();
+        }
+
+        // A long interval, so only full batches and the final flush emit
+        let batching = ScanBatching {
+            batch_size: 256,
+            flush_interval: Duration::from_secs(60),
+        };
+        let walker = DirectoryWalker::new(false, DEFAULT_MAX_ENTRIES_PER_DIR);
+        let mut sizes = Vec::new();
+        let outcome = run_scan(&RealFs, &walker, dir.path(), batching, |batch| {
+            sizes.push(batch.len())
+        });
+
+        assert_eq!(sizes, vec![256, 256, 88]);
+        assert_eq!(
+            outcome,
+            ScanOutcome::Completed {
+                entry_count: 600,
+                truncated_dirs: Vec::new()
+            }
+        );
+    }
+
+    #[test]
+    fn test_partial_batch_is_flushed_after_interval() {
+        let (sender, receiver) = mpsc::sync_channel(8);
+        let batching = ScanBatching {
+            batch_size: 100,
+            flush_interval: Duration::from_millis(20),
+        };
+        let info = FileInfo::from_path(Path::new("."), false).unwrap();
+
+        let producer = thread::spawn(move || {
+            sender.send(info.clone()).unwrap();
+            thread::sleep(Duration::from_millis(200));
+            sender.send(info).unwrap();
+        });
+        let mut sizes = Vec::new();
+        let count = emit_batches(receiver, batching, |batch| sizes.push(batch.len()));
+        producer.join().unwrap();
+
+        // The first entry didn't wait for the batch to fill
+        assert_eq!(sizes, vec![1, 1]);
+        assert_eq!(count, 2);
+    }
+
+    #[cfg(feature = "test-fs")]
+    #[test]
+    fn test_stream_yields_entries_before_the_read_completes() {
+        use crate::utils::fs_backend::MockFs;
+        use std::time::Instant;
+
+        // Each entry costs two metadata lookups of 20 ms
+        let mock = MockFs::new().with_latency(Duration::from_millis(20));
+        for i in 0..20 {
+            mock.add_file(format!("/big/file{:02}.txt", i), "x");
+        }
+        let fs: Arc<dyn FileSystem> = Arc::new(mock);
+
+        let started = Instant::now();
+        let (mut entries, reader) = stream_directory(
+            fs,
+            PathBuf::from("/big"),
+            DEFAULT_MAX_ENTRIES_PER_DIR,
+            CancellationToken::new(),
+        );
+        let (first_at, count, outcome) = async_runtime::block_on(async move {
+            let first = entries.recv().await.unwrap();
+            assert!(first.name.starts_with("file"));
+            let first_at = started.elapsed();
+            let mut count = 1;
+            while entries.recv().await.is_some() {
+                count += 1;
+            }
+            (first_at, count, reader.await.unwrap())
+        });
+
+        assert_eq!(count, 20);
+        assert!(first_at < Duration::from_millis(400), "{:?}", first_at);
+        assert!(started.elapsed() >= Duration::from_millis(800));
+        assert_eq!(
+            outcome,
+            ScanOutcome::Completed {
+                entry_count: 20,
+                truncated_dirs: Vec::new()
+            }
+        );
+    }
+
+    #[test]
+    fn test_stream_stops_at_max_entries_and_on_cancel() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..10 {
+            fs::write(dir.path().join(format!("file{}.txt", i)), "x").unwrap();
+        }
+        let fs: Arc<dyn FileSystem> = Arc::new(RealFs);
+
+        let (mut entries, reader) = stream_directory(
+            Arc::clone(&fs),
+            dir.path().to_path_buf(),
+            4,
+            CancellationToken::new(),
+        );
+        let (count, outcome) = async_runtime::block_on(async move {
+            let mut count = 0;
+            while entries.recv().await.is_some() {
+                count += 1;
+            }
+            (count, reader.await.unwrap())
+        });
+        assert_eq!(count, 4);
+        assert_eq!(
+            outcome,
+            ScanOutcome::Completed {
+                entry_count: 4,
+                truncated_dirs: vec![dir.path().to_string_lossy().into_owned()]
+            }
+        );
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let (_, reader) = stream_directory(fs, dir.path().to_path_buf(), 100, token);
+        let outcome = async_runtime::block_on(reader).unwrap();
+        assert_eq!(outcome, ScanOutcome::Cancelled);
+    }
+}
@@ -0,0 +1,238 @@
+//! Per-window scoped file access for Tauri applications
+//!
+//! A path the user picked in a dialog may be read even outside the allowed roots:
+//! 1. The dialog commands grant each selection to the window that opened the dialog
+//! 2. A selected file is granted alone; a selected directory covers its contents
+//! 3. Grants expire after `scoped_access_ttl_secs` from the app config
+//! 4. Read commands call `check_scoped`, which tries the static allowlist first
+//! 5. Paths are canonicalized on both sides, so `..` and symlinks can't widen a grant
+
+use super::allowed_roots::AllowedRoots;
+use super::error::AppError;
+use super::vault::Clock;
+use log::info;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Seconds a dialog selection stays readable when the config doesn't say (10 minutes)
+pub const DEFAULT_SCOPED_ACCESS_TTL_SECS: u64 = 10 * 60;
+
+/// Longest a dialog selection may stay readable (24 hours)
+pub const MAX_SCOPED_ACCESS_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// A path a window may read until `expires_at`
+struct Grant {
+    path: PathBuf,
+    recursive: bool,
+    expires_at: Instant,
+}
+
+impl Grant {
+    fn covers(&self, path: &Path) -> bool {
+        if self.recursive {
+            path.starts_with(&self.path)
+        } else {
+            path == self.path
+        }
+    }
+}
+
+/// Paths each window selected through a dialog, keyed by window label
+pub struct ScopedAccess {
+    grants: Mutex<HashMap<String, Vec<Grant>>>,
+    clock: Clock,
+}
+
+impl Default for ScopedAccess {
+    fn default() -> Self {
+        Self::with_clock(Arc::new(Instant::now))
+    }
+}
+
+impl ScopedAccess {
+    /// Create an empty scope that reads the time from `clock`
+    pub fn with_clock(clock: Clock) -> Self {
+        Self {
+            grants: Mutex::new(HashMap::new()),
+            clock,
+        }
+    }
+
+    fn lock_grants(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<Grant>>> {
+        self.grants.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Let the window labelled `label` read `paths` for `ttl`
+    ///
+    /// With `recursive`, everything under each path is covered as well.
+    /// Paths that can't be resolved are skipped.
+    pub fn grant<I>(&self, label: &str, paths: I, recursive: bool, ttl: Duration)
+    where
+        I: IntoIterator<Item = PathBuf>,
+    {
+        self.purge_expired();
+        let expires_at = (self.clock)() + ttl;
+        let mut grants = self.lock_grants();
+        let window = grants.entry(label.to_string()).or_default();
+        for path in paths {
+            if let Ok(path) = path.canonicalize() {
+                info!(
+                    "Window {} may read {} for {} s",
+                    label,
+                    path.display(),
+                    ttl.as_secs()
+                );
+                window.push(Grant {
+                    path,
+                    recursive,
+                    expires_at,
+                });
+            }
+        }
+    }
+
+    /// Whether the window labelled `label` holds an unexpired grant covering `path`
+    pub fn allows(&self, label: &str, path: &Path) -> bool {
+        let Ok(path) = path.canonicalize() else {
+            return false;
+        };
+        let now = (self.clock)();
+        let grants = self.lock_grants();
+        grants.get(label).is_some_and(|window| {
+            window
+                .iter()
+                .any(|grant| grant.expires_at > now && grant.covers(&path))
+        })
+    }
+
+    /// Drop grants whose time to live has passed, returning how many there were
+    pub fn purge_expired(&self) -> usize {
+        let now = (self.clock)();
+        let mut grants = self.lock_grants();
+        let mut purged = 0;
+        grants.retain(|_, window| {
+            let before = window.len();
+            window.retain(|grant| grant.expires_at > now);
+            purged += before - window.len();
+            !window.is_empty()
+        });
+        purged
+    }
+}
+
+/// Ensure the window labelled `label` may read `path`, returning its canonical form
+///
+/// Paths under the allowed roots pass as before; anything else needs a
+/// dialog grant, or the allowlist's `Authorization` error is returned.
+pub(crate) fn check_scoped(
+    roots: &AllowedRoots,
+    scope: &ScopedAccess,
+    label: &str,
+    path: &Path,
+) -> Result<PathBuf, AppError> {
+    roots.check(path).or_else(|denied| {
+        if scope.allows(label, path) {
+            path.canonicalize().map_err(|_| denied)
+        } else {
+            Err(denied)
+        }
+    })
+}
+
+/// Like `check_scoped`, but a final symlink is not followed
+///
+/// The returned path names the link itself, for commands that describe an
+/// entry without reading through it.
+pub(crate) fn check_scoped_entry(
+    roots: &AllowedRoots,
+    scope: &ScopedAccess,
+    label: &str,
+    path: &Path,
+) -> Result<PathBuf, AppError> {
+    roots.check_entry(path).or_else(|denied| {
+        if scope.allows(label, path) {
+            let name = path.file_name().ok_or_else(|| denied.clone())?;
+            let parent = path.parent().ok_or_else(|| denied.clone())?;
+            parent
+                .canonicalize()
+                .map(|p| p.join(name))
+                .map_err(|_| denied)
+        } else {
+            Err(denied)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::file_system::read_range;
+    use std::fs;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_selected_file_is_readable() {
+        let dir = tempfile::tempdir().unwrap();
+        let picked = dir.path().join("picked.txt");
+        let other = dir.path().join("other.txt");
+        fs::write(&picked, "chosen").unwrap();
+        fs::write(&other, "not chosen").unwrap();
+
+        let roots = AllowedRoots::new();
+        let scope = ScopedAccess::default();
+        scope.grant("main", vec![picked.clone()], false, TTL);
+
+        let path = check_scoped(&roots, &scope, "main", &picked).unwrap();
+        assert_eq!(read_range(&path, 0, 64).unwrap().data, b"chosen");
+
+        assert!(matches!(
+            check_scoped(&roots, &scope, "main", &other),
+            Err(AppError::Authorization(_))
+        ));
+        // Grants belong to the window that made the selection
+        assert!(check_scoped(&roots, &scope, "preview", &picked).is_err());
+    }
+
+    #[test]
+    fn test_selected_directory_covers_its_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let picked = dir.path().join("picked");
+        fs::create_dir_all(picked.join("nested")).unwrap();
+        fs::write(picked.join("nested").join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("outside.txt"), "x").unwrap();
+
+        let scope = ScopedAccess::default();
+        scope.grant("main", vec![picked.clone()], true, TTL);
+
+        assert!(scope.allows("main", &picked.join("nested").join("a.txt")));
+        let escaped = picked.join("..").join("outside.txt");
+        assert!(!scope.allows("main", &escaped));
+    }
+
+    #[test]
+    fn test_grant_expires() {
+        let dir = tempfile::tempdir().unwrap();
+        let picked = dir.path().join("picked.txt");
+        fs::write(&picked, "chosen").unwrap();
+
+        let start = Instant::now();
+        let offset_secs = Arc::new(AtomicU64::new(0));
+        let offset = Arc::clone(&offset_secs);
+        let scope = ScopedAccess::with_clock(Arc::new(move || {
+            start + Duration::from_secs(offset.load(Ordering::SeqCst))
+        }));
+        scope.grant("main", vec![picked.clone()], false, TTL);
+
+        offset_secs.store(59, Ordering::SeqCst);
+        assert!(scope.allows("main", &picked));
+
+        offset_secs.store(60, Ordering::SeqCst);
+        assert!(!scope.allows("main", &picked));
+        assert!(check_scoped(&AllowedRoots::new(), &scope, "main", &picked).is_err());
+        assert_eq!(scope.purge_expired(), 1);
+    }
+}
@@ -0,0 +1,304 @@
+//! Tracking of live secure allocations for Tauri applications
+//!
+//! Secure containers register themselves so their lifetime can be observed:
+//! 1. `SecureMemoryRegistry` can be instantiated per window, per test or per app
+//! 2. A process-wide default registry is returned by `global_registry`
+//! 3. `SecureRegistration` deregisters automatically when dropped
+//! 4. With the `debug_diagnostics` feature, live totals are exposed as a command
+//! 5. A high-water mark and `assert_no_leaks` let tests prove that cleanup happened
+
+use once_cell::sync::Lazy;
+#[cfg(feature = "debug_diagnostics")]
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, TryLockError};
+
+/// Identifier of a registered allocation
+pub type AllocationId = u64;
+
+/// The process-wide default registry
+static GLOBAL_REGISTRY: Lazy<Arc<SecureMemoryRegistry>> =
+    Lazy::new(|| Arc::new(SecureMemoryRegistry::new()));
+
+/// Live allocations of a registry and running byte totals
+#[derive(Debug, Default)]
+struct LiveAllocations {
+    /// Sizes in bytes keyed by allocation id
+    sizes: HashMap<AllocationId, usize>,
+    /// Sum of `sizes`
+    bytes: usize,
+    /// Largest value `bytes` has reached
+    peak_bytes: usize,
+}
+
+/// A registry of live secure allocations and their sizes
+#[derive(Debug, Default)]
+pub struct SecureMemoryRegistry {
+    /// Next id to hand out
+    next_id: AtomicU64,
+
+    /// Live allocations and their sizes in bytes
+    live: Mutex<LiveAllocations>,
+}
+
+impl SecureMemoryRegistry {
+    /// Create a new, empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a live allocation of `len` bytes
+    pub fn register(&self, len: usize) -> AllocationId {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut live = self.lock_live();
+        live.sizes.insert(id, len);
+        live.bytes += len;
+        live.peak_bytes = live.peak_bytes.max(live.bytes);
+        id
+    }
+
+    /// Forget an allocation, returning whether it was registered
+    pub fn deregister(&self, id: AllocationId) -> bool {
+        let mut live = self.lock_live();
+        match live.sizes.remove(&id) {
+            Some(len) => {
+                live.bytes -= len;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of live allocations
+    #[cfg(test)]
+    pub fn live_count(&self) -> usize {
+        self.lock_live().sizes.len()
+    }
+
+    /// Number of live allocations, or `None` if the registry is locked
+    ///
+    /// Never blocks, so a panic hook can call it even when the panicking
+    /// thread holds the lock.
+    pub fn try_live_count(&self) -> Option<usize> {
+        match self.live.try_lock() {
+            Ok(live) => Some(live.sizes.len()),
+            Err(TryLockError::Poisoned(e)) => Some(e.into_inner().sizes.len()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    /// Total size of live allocations in bytes
+    #[cfg(test)]
+    pub fn live_bytes(&self) -> usize {
+        self.lock_live().bytes
+    }
+
+    /// The most bytes that were ever live at once
+    #[cfg(test)]
+    pub fn high_water_mark(&self) -> usize {
+        self.lock_live().peak_bytes
+    }
+
+    /// Panic if any allocation is still registered, listing their ids and sizes
+    ///
+    /// Meant for tests: run a block of command logic against a dedicated
+    /// registry, drop its results, then call this to prove nothing leaked.
+    #[cfg(any(test, feature = "debug_diagnostics"))]
+    #[track_caller]
+    pub fn assert_no_leaks(&self) {
+        let live = self.lock_live();
+        if live.sizes.is_empty() {
+            return;
+        }
+
+        let mut leaked: Vec<_> = live.sizes.iter().map(|(id, len)| (*id, *len)).collect();
+        leaked.sort_unstable();
+        let details: Vec<String> = leaked
+            .iter()
+            .map(|(id, len)| format!("#{} ({} bytes)", id, len))
+            .collect();
+        panic!(
+            "{} secure allocation(s) still registered: {}",
+            leaked.len(),
+            details.join(", ")
+        );
+    }
+
+    fn lock_live(&self) -> std::sync::MutexGuard<'_, LiveAllocations> {
+        self.live.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Get the process-wide default registry
+pub fn global_registry() -> Arc<SecureMemoryRegistry> {
+    Arc::clone(&GLOBAL_REGISTRY)
+}
+
+/// A registration that is removed from its registry when dropped
+#[derive(Debug)]
+pub struct SecureRegistration {
+    registry: Arc<SecureMemoryRegistry>,
+    id: AllocationId,
+}
+
+impl SecureRegistration {
+    /// Register an allocation of `len` bytes with `registry`
+    pub fn new(registry: &Arc<SecureMemoryRegistry>, len: usize) -> Self {
+        Self {
+            registry: Arc::clone(registry),
+            id: registry.register(len),
+        }
+    }
+
+    /// The registry this allocation belongs to
+    pub fn registry(&self) -> &Arc<SecureMemoryRegistry> {
+        &self.registry
+    }
+
+    /// Register a new allocation of `len` bytes with the same registry
+    pub fn renew(&self, len: usize) -> Self {
+        Self::new(&self.registry, len)
+    }
+}
+
+impl Drop for SecureRegistration {
+    fn drop(&mut self) {
+        self.registry.deregister(self.id);
+    }
+}
+
+/// Snapshot of live secure allocations
+#[cfg(feature = "debug_diagnostics")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct SecureMemoryStats {
+    /// Number of live allocations
+    pub live_allocations: usize,
+    /// Total size of live allocations in bytes
+    pub total_bytes: usize,
+}
+
+#[cfg(feature = "debug_diagnostics")]
+impl SecureMemoryStats {
+    /// Take a snapshot of `registry`
+    pub fn of(registry: &SecureMemoryRegistry) -> Self {
+        let live = registry.lock_live();
+        Self {
+            live_allocations: live.sizes.len(),
+            total_bytes: live.bytes,
+        }
+    }
+}
+
+/// Report live secure allocations, for debugging leaks
+///
+/// Only compiled with the `debug_diagnostics` feature so release builds
+/// don't reveal how many secrets are held in memory.
+#[cfg(feature = "debug_diagnostics")]
+#[tauri::command]
+pub fn secure_memory_stats(
+    registry: tauri::State<'_, Arc<SecureMemoryRegistry>>,
+) -> SecureMemoryStats {
+    SecureMemoryStats::of(&registry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::memory_safe::SecureString;
+
+    #[test]
+    fn test_register_and_deregister() {
+        let registry = SecureMemoryRegistry::new();
+        let a = registry.register(16);
+        let b = registry.register(32);
+        assert_eq!(registry.live_count(), 2);
+        assert_eq!(registry.live_bytes(), 48);
+
+        assert!(registry.deregister(a));
+        assert!(!registry.deregister(a));
+        assert!(registry.deregister(b));
+        assert_eq!(registry.live_count(), 0);
+    }
+
+    #[test]
+    fn test_independent_registries_are_isolated() {
+        let first = Arc::new(SecureMemoryRegistry::new());
+        let second = Arc::new(SecureMemoryRegistry::new());
+
+        let secret = SecureString::new_in("top secret", &first);
+        let copy = secret.clone();
+        assert_eq!(first.live_count(), 2);
+        assert_eq!(second.live_count(), 0);
+
+        let other = SecureString::new_in("other", &second);
+        assert_eq!(first.live_count(), 2);
+        assert_eq!(second.live_count(), 1);
+
+        drop(secret);
+        drop(copy);
+        drop(other);
+        assert_eq!(first.live_count(), 0);
+        assert_eq!(second.live_count(), 0);
+    }
+
+    #[test]
+    fn test_high_water_mark() {
+        let registry = SecureMemoryRegistry::new();
+        let a = registry.register(16);
+        let b = registry.register(32);
+        registry.deregister(a);
+        let c = registry.register(8);
+
+        assert_eq!(registry.live_bytes(), 40);
+        assert_eq!(registry.high_water_mark(), 48);
+        registry.deregister(b);
+        registry.deregister(c);
+        assert_eq!(registry.high_water_mark(), 48);
+    }
+
+    #[test]
+    fn test_assert_no_leaks_passes_when_freed() {
+        let registry = Arc::new(SecureMemoryRegistry::new());
+        {
+            let secret = SecureString::new_in("top secret", &registry);
+            let _copy = secret.clone();
+        }
+        registry.assert_no_leaks();
+    }
+
+    #[test]
+    #[should_panic(expected = "1 secure allocation(s) still registered: #1 (6 bytes)")]
+    fn test_assert_no_leaks_reports_forgotten_allocation() {
+        let registry = Arc::new(SecureMemoryRegistry::new());
+        let freed = SecureString::new_in("freed", &registry);
+        drop(freed);
+        // Forgetting skips `Drop`, so the registration is never removed
+        std::mem::forget(SecureString::new_in("leaked", &registry));
+        registry.assert_no_leaks();
+    }
+
+    #[cfg(feature = "debug_diagnostics")]
+    #[test]
+    fn test_secure_memory_stats() {
+        use crate::utils::memory_safe::SecureBytes;
+
+        let registry = Arc::new(SecureMemoryRegistry::new());
+        let secret = SecureString::new_in("sixteen bytes!!!", &registry);
+        let key = SecureBytes::new_in(vec![0u8; 32], &registry);
+
+        let stats = SecureMemoryStats::of(&registry);
+        assert_eq!(stats.live_allocations, 2);
+        assert_eq!(stats.total_bytes, 48);
+
+        drop(secret);
+        drop(key);
+        assert_eq!(
+            SecureMemoryStats::of(&registry),
+            SecureMemoryStats {
+                live_allocations: 0,
+                total_bytes: 0
+            }
+        );
+    }
+}
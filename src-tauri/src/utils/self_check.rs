@@ -0,0 +1,472 @@
+//! Security self-check for Tauri applications
+//!
+//! Misconfigured capabilities usually show up as commands that silently fail:
+//! 1. `security_self_check` reports which plugins are initialized
+//! 2. The capability files bundled at build time are summarized
+//! 3. Dangerous settings, such as an unscoped shell `open`, are flagged
+//! 4. `list_capabilities` audits the capability files on disk, flagging wildcard scopes
+//! 5. The module is only compiled with the `debug_diagnostics` feature
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, Manager, Wry};
+
+/// Capability files compiled into the app, as (file name, contents)
+const BUNDLED_CAPABILITIES: [(&str, &str); 2] = [
+    ("main.json", include_str!("../../capabilities/main.json")),
+    (
+        "downloads-access.json",
+        include_str!("../../capabilities/downloads-access.json"),
+    ),
+];
+
+/// Permissions that grant far more than most apps need
+///
+/// Kept in sync with `HIGH_RISK_PERMISSIONS` in `scripts/check_capabilities.py`.
+const HIGH_RISK_PERMISSIONS: [&str; 5] = ["fs:default", "path:all", "shell:all", "window:all", "*"];
+
+/// Directory the build script reads capability files from
+const CAPABILITIES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/capabilities");
+
+/// Plugins whose capabilities the report calls out individually
+const CHECKED_PLUGINS: [&str; 3] = ["fs", "dialog", "shell"];
+
+/// A capability file as written on disk
+#[derive(Deserialize)]
+struct CapabilityFile {
+    identifier: String,
+    #[serde(default)]
+    windows: Vec<String>,
+    #[serde(default)]
+    permissions: Vec<Value>,
+}
+
+/// The parts of a capability relevant to the self-check
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CapabilitySummary {
+    /// Name of the capability file
+    pub file: String,
+    /// Capability identifier, empty if the file couldn't be parsed
+    pub identifier: String,
+    /// Windows the capability applies to
+    pub windows: Vec<String>,
+    /// Permission identifiers, without any scopes
+    pub permissions: Vec<String>,
+    /// Scopes that match everything under a base directory or URL, as `permission: scope`
+    pub wildcard_scopes: Vec<String>,
+    /// Whether the capability looks broader than it should be
+    pub risky: bool,
+    /// Why the file couldn't be parsed
+    pub error: Option<String>,
+}
+
+/// Result of `security_self_check`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SelfCheckReport {
+    /// Plugins whose state is registered with the app
+    pub plugins: Vec<String>,
+    /// Capabilities bundled with the app
+    pub capabilities: Vec<CapabilitySummary>,
+    /// Plugins among `fs`, `dialog` and `shell` granted by some capability
+    pub granted_plugins: Vec<String>,
+    /// The configured content security policy
+    pub csp: Option<String>,
+    /// Settings that look dangerous or inconsistent
+    pub warnings: Vec<String>,
+}
+
+/// Whether a scope such as `$HOME/**` or `*` matches everything under its base
+///
+/// Only a leading `$VARIABLE` or URL scheme may precede the wildcards; a scope
+/// naming any concrete directory or host is not flagged.
+fn is_wildcard_scope(scope: &str) -> bool {
+    let mut segments = scope
+        .split(['/', '\\'])
+        .filter(|s| !s.is_empty())
+        .peekable();
+    if segments
+        .peek()
+        .is_some_and(|first| first.starts_with('$') || first.ends_with(':'))
+    {
+        segments.next();
+    }
+    segments.peek().is_some() && segments.all(|segment| segment.chars().all(|c| c == '*'))
+}
+
+/// The `permission: scope` pairs of a scoped permission whose scope is a wildcard
+fn wildcard_scopes_of(permission: &serde_json::Map<String, Value>) -> Vec<String> {
+    let identifier = permission
+        .get("identifier")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let allowed = permission
+        .get("allow")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    // Entries are a bare scope or an object such as `{ "path": ... }` or `{ "url": ... }`
+    allowed
+        .iter()
+        .flat_map(|entry| match entry {
+            Value::String(scope) => vec![scope.as_str()],
+            Value::Object(fields) => fields.values().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        })
+        .filter(|scope| is_wildcard_scope(scope))
+        .map(|scope| format!("{}: {}", identifier, scope))
+        .collect()
+}
+
+/// Parse a capability file, keeping only permission identifiers
+fn parse_capability(name: &str, contents: &str) -> Result<CapabilitySummary, String> {
+    let file: CapabilityFile = serde_json::from_str(contents)
+        .map_err(|e| format!("Invalid capability file {}: {}", name, e))?;
+
+    // Permissions are either a bare identifier or an object with scopes
+    let permissions: Vec<String> = file
+        .permissions
+        .iter()
+        .filter_map(|permission| match permission {
+            Value::String(identifier) => Some(identifier.clone()),
+            Value::Object(object) => object
+                .get("identifier")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            _ => None,
+        })
+        .collect();
+    let wildcard_scopes: Vec<String> = file
+        .permissions
+        .iter()
+        .filter_map(Value::as_object)
+        .flat_map(wildcard_scopes_of)
+        .collect();
+    let risky = !wildcard_scopes.is_empty()
+        || file.windows.iter().any(|window| window == "*")
+        || permissions
+            .iter()
+            .any(|permission| HIGH_RISK_PERMISSIONS.contains(&permission.as_str()));
+
+    Ok(CapabilitySummary {
+        file: name.to_string(),
+        identifier: file.identifier,
+        windows: file.windows,
+        permissions,
+        wildcard_scopes,
+        risky,
+        error: None,
+    })
+}
+
+/// Summarize every `.json` capability file in `dir`, sorted by file name
+///
+/// A file that can't be read or parsed is listed with its `error` set.
+pub(crate) fn read_capabilities(dir: &Path) -> Result<Vec<CapabilitySummary>, String> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read capabilities from {}: {}", dir.display(), e))?;
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    Ok(paths
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read capability file {}: {}", name, e))
+                .and_then(|contents| parse_capability(&name, &contents))
+                .unwrap_or_else(|error| CapabilitySummary {
+                    file: name,
+                    error: Some(error),
+                    ..CapabilitySummary::default()
+                })
+        })
+        .collect())
+}
+
+/// The plugin a permission identifier belongs to, e.g. `fs` for `fs:default`
+fn permission_plugin(permission: &str) -> Option<&str> {
+    permission.split_once(':').map(|(plugin, _)| plugin)
+}
+
+/// Whether the shell plugin may open any URL or path
+fn shell_open_is_unscoped(plugin_config: &HashMap<String, Value>) -> bool {
+    match plugin_config
+        .get("shell")
+        .and_then(|shell| shell.get("open"))
+    {
+        Some(Value::Bool(enabled)) => *enabled,
+        Some(Value::String(pattern)) => matches!(pattern.as_str(), ".*" | "^.*$" | ".+"),
+        _ => false,
+    }
+}
+
+/// Assemble the report from what the app exposes at runtime
+pub(crate) fn build_report(
+    plugins: Vec<String>,
+    capabilities: Vec<CapabilitySummary>,
+    plugin_config: &HashMap<String, Value>,
+    csp: Option<String>,
+) -> SelfCheckReport {
+    let mut warnings = Vec::new();
+
+    for capability in &capabilities {
+        if capability.windows.iter().any(|window| window == "*") {
+            warnings.push(format!(
+                "Capability {} applies to every window",
+                capability.identifier
+            ));
+        }
+        for permission in &capability.permissions {
+            if HIGH_RISK_PERMISSIONS.contains(&permission.as_str()) {
+                warnings.push(format!(
+                    "Capability {} grants high-risk permission {}",
+                    capability.identifier, permission
+                ));
+            }
+            if let Some(plugin) = permission_plugin(permission) {
+                if !plugins.iter().any(|p| p == plugin) {
+                    warnings.push(format!(
+                        "Capability {} grants {} but the {} plugin is not initialized",
+                        capability.identifier, permission, plugin
+                    ));
+                }
+            }
+        }
+    }
+
+    let granted: Vec<&str> = capabilities
+        .iter()
+        .flat_map(|capability| &capability.permissions)
+        .filter_map(|permission| permission_plugin(permission))
+        .collect();
+    for plugin in &plugins {
+        if !granted.contains(&plugin.as_str()) {
+            warnings.push(format!(
+                "The {} plugin is initialized but no capability grants its commands",
+                plugin
+            ));
+        }
+    }
+
+    if shell_open_is_unscoped(plugin_config) {
+        warnings.push("Shell open is enabled without restricting which URLs it accepts".into());
+    }
+
+    match &csp {
+        None => warnings.push("No content security policy is configured".into()),
+        Some(policy) if policy.contains("'unsafe-eval'") => {
+            warnings.push("Content security policy allows 'unsafe-eval'".into())
+        }
+        Some(_) => {}
+    }
+
+    SelfCheckReport {
+        granted_plugins: CHECKED_PLUGINS
+            .iter()
+            .filter(|plugin| granted.contains(*plugin))
+            .map(|plugin| plugin.to_string())
+            .collect(),
+        plugins,
+        capabilities,
+        csp,
+        warnings,
+    }
+}
+
+/// Report plugins, capabilities and risky settings, for debugging permissions
+///
+/// Only compiled with the `debug_diagnostics` feature, since the report maps
+/// out what the frontend is allowed to do.
+#[tauri::command]
+pub fn security_self_check(app: AppHandle) -> Result<SelfCheckReport, String> {
+    let mut plugins = Vec::new();
+    if app
+        .try_state::<tauri_plugin_dialog::Dialog<Wry>>()
+        .is_some()
+    {
+        plugins.push("dialog".to_string());
+    }
+    if app.try_state::<tauri_plugin_fs::Fs<Wry>>().is_some() {
+        plugins.push("fs".to_string());
+    }
+    if app.try_state::<tauri_plugin_shell::Shell<Wry>>().is_some() {
+        plugins.push("shell".to_string());
+    }
+    if app
+        .try_state::<tauri_plugin_clipboard_manager::Clipboard<Wry>>()
+        .is_some()
+    {
+        plugins.push("clipboard-manager".to_string());
+    }
+
+    let capabilities = BUNDLED_CAPABILITIES
+        .iter()
+        .map(|(name, contents)| parse_capability(name, contents))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let config = app.config();
+    let csp = config.app.security.csp.as_ref().map(|csp| csp.to_string());
+    Ok(build_report(plugins, capabilities, &config.plugins.0, csp))
+}
+
+/// Summarize the capability files the app is built with, flagging broad grants
+///
+/// Reads the `capabilities` directory next to `build.rs`, so it is only
+/// meaningful on a development machine.
+#[tauri::command]
+pub fn list_capabilities() -> Result<Vec<CapabilitySummary>, String> {
+    read_capabilities(Path::new(CAPABILITIES_DIR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn bundled() -> Vec<CapabilitySummary> {
+        BUNDLED_CAPABILITIES
+            .iter()
+            .map(|(name, contents)| parse_capability(name, contents).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_report_lists_plugins_for_known_setup() {
+        let plugins: Vec<String> = ["dialog", "fs", "shell", "clipboard-manager"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let config = HashMap::from([("shell".to_string(), json!({ "open": true }))]);
+
+        let report = build_report(
+            plugins.clone(),
+            bundled(),
+            &config,
+            Some("default-src 'self'".into()),
+        );
+        assert_eq!(report.plugins, plugins);
+        assert_eq!(report.granted_plugins, vec!["fs", "dialog"]);
+        let identifiers: Vec<&str> = report
+            .capabilities
+            .iter()
+            .map(|c| c.identifier.as_str())
+            .collect();
+        assert_eq!(identifiers, vec!["main", "downloads-access"]);
+
+        let flagged = |needle: &str| report.warnings.iter().any(|w| w.contains(needle));
+        assert!(flagged("Shell open is enabled"));
+        assert!(flagged("high-risk permission fs:default"));
+        assert!(flagged("shell plugin is initialized but no capability"));
+        assert!(!flagged("content security policy"));
+    }
+
+    #[test]
+    fn test_report_flags_missing_plugin_and_csp() {
+        let capability = parse_capability(
+            "test.json",
+            r#"{
+                "identifier": "broad",
+                "windows": ["*"],
+                "permissions": [
+                    "shell:allow-open",
+                    { "identifier": "fs:allow-read", "allow": [{ "path": "$HOME/**" }] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            capability.permissions,
+            vec!["shell:allow-open", "fs:allow-read"]
+        );
+
+        let report = build_report(vec!["fs".into()], vec![capability], &HashMap::new(), None);
+        let flagged = |needle: &str| report.warnings.iter().any(|w| w.contains(needle));
+        assert!(flagged("applies to every window"));
+        assert!(flagged("the shell plugin is not initialized"));
+        assert!(flagged("No content security policy"));
+        assert!(!flagged("Shell open is enabled"));
+    }
+
+    #[test]
+    fn test_wildcard_scope_is_flagged() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("broad.json"),
+            r#"{
+                "identifier": "broad",
+                "windows": ["main"],
+                "permissions": [
+                    { "identifier": "fs:allow-read", "allow": [{ "path": "$HOME/**" }] },
+                    { "identifier": "fs:allow-write", "allow": [{ "path": "$APPDATA/notes/*" }] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("narrow.json"),
+            r#"{ "identifier": "narrow", "windows": ["main"], "permissions": ["dialog:allow-open"] }"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("README.md"), "not a capability").unwrap();
+
+        let capabilities = read_capabilities(dir.path()).unwrap();
+        let files: Vec<&str> = capabilities.iter().map(|c| c.file.as_str()).collect();
+        assert_eq!(files, vec!["broad.json", "narrow.json"]);
+
+        assert!(capabilities[0].risky);
+        assert_eq!(
+            capabilities[0].wildcard_scopes,
+            vec!["fs:allow-read: $HOME/**"]
+        );
+        assert!(!capabilities[1].risky);
+        assert!(capabilities[1].wildcard_scopes.is_empty());
+
+        assert!(is_wildcard_scope("*"));
+        assert!(is_wildcard_scope("/**"));
+        assert!(is_wildcard_scope("https://*"));
+        assert!(!is_wildcard_scope("https://*.example.com"));
+    }
+
+    #[test]
+    fn test_malformed_capability_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a-broken.json"), "{ \"identifier\": ").unwrap();
+        fs::write(
+            dir.path().join("b-missing-id.json"),
+            r#"{ "permissions": [] }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("c-ok.json"),
+            r#"{ "identifier": "ok", "permissions": ["fs:default"] }"#,
+        )
+        .unwrap();
+
+        let capabilities = read_capabilities(dir.path()).unwrap();
+        assert_eq!(capabilities.len(), 3);
+        assert!(capabilities[0].error.is_some());
+        assert!(capabilities[1].error.is_some());
+        assert_eq!(capabilities[1].identifier, "");
+        assert_eq!(capabilities[2].error, None);
+        assert!(capabilities[2].risky);
+
+        assert!(read_capabilities(&dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_repository_capabilities_parse() {
+        let capabilities = read_capabilities(Path::new(CAPABILITIES_DIR)).unwrap();
+        assert!(capabilities.len() >= BUNDLED_CAPABILITIES.len());
+        assert!(capabilities.iter().all(|c| c.error.is_none()));
+    }
+}
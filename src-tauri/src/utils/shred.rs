@@ -0,0 +1,256 @@
+//! Secure file deletion for Tauri applications
+//!
+//! Unlinking a file leaves its bytes on disk until they happen to be reused:
+//! 1. `wipe_file` overwrites the whole file several times before unlinking it
+//! 2. Every pass but the last writes random bytes; the last writes zeros
+//! 3. Each pass is synced to disk before the next one starts
+//! 4. The file is then truncated and removed
+//! 5. Only regular files are wiped; directories and symlinks are refused,
+//!    judged from the opened handle so a swapped-in symlink is never followed
+//! 6. Files with other hard links are refused, since overwriting would destroy
+//!    their content too
+//!
+//! Overwriting in place is best-effort: SSDs remap writes through wear
+//! levelling, and copy-on-write file systems (APFS, Btrfs, ZFS) write new
+//! blocks, so old copies of the data may survive. The report says so.
+
+use super::allowed_roots::AllowedRoots;
+use super::audit::AuditLog;
+use super::error::AppError;
+use super::file_ops::resolve_in_roots;
+use super::guard::CommandGuard;
+use super::window_caps::{require_capability, CapabilityToken};
+use log::info;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::Path;
+use tauri::{AppHandle, Manager, Window};
+
+/// Most overwrite passes a single wipe may run
+pub const MAX_WIPE_PASSES: u8 = 16;
+
+/// Size of the buffer each pass writes at a time
+const WIPE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Caveat included in every `WipeReport`
+pub const WIPE_WARNING: &str = "Overwriting is best-effort: SSDs and copy-on-write file \
+    systems may keep old copies of the data, and backups or snapshots are not touched";
+
+/// Result of `secure_delete_file`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct WipeReport {
+    /// The file that was wiped
+    pub path: String,
+    /// Size of the file before it was wiped
+    pub bytes: u64,
+    /// Number of overwrite passes run
+    pub passes: u8,
+    /// Why the wipe may not have destroyed every copy of the data
+    pub warning: String,
+}
+
+/// Overwrite the first `len` bytes of `file` with random bytes or zeros, then sync
+fn overwrite_pass(file: &mut File, len: u64, random: bool) -> io::Result<()> {
+    file.seek(SeekFrom::Start(0))?;
+    let mut buffer = vec![0u8; WIPE_CHUNK_SIZE];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(WIPE_CHUNK_SIZE as u64) as usize;
+        if random {
+            OsRng
+                .try_fill_bytes(&mut buffer[..chunk])
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        }
+        file.write_all(&buffer[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    file.sync_all()
+}
+
+/// Open `path` for writing without following a symlink in its last component
+fn open_no_follow(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_NOFOLLOW);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        // FILE_FLAG_OPEN_REPARSE_POINT opens a symlink itself, not its target
+        options.custom_flags(0x0020_0000);
+    }
+    options.open(path)
+}
+
+fn not_regular_file(path: &Path) -> AppError {
+    AppError::Validation(format!("Not a regular file: {}", path.display()))
+}
+
+/// Overwrite, truncate and remove `path`, calling `after_overwrite` once the passes are synced
+fn wipe_with<F>(path: &Path, passes: u8, after_overwrite: F) -> Result<WipeReport, AppError>
+where
+    F: FnOnce(&Path),
+{
+    if passes == 0 || passes > MAX_WIPE_PASSES {
+        return Err(AppError::Validation(format!(
+            "Passes must be between 1 and {}",
+            MAX_WIPE_PASSES
+        )));
+    }
+
+    let mut file = open_no_follow(path).map_err(|e| match fs::symlink_metadata(path) {
+        Ok(metadata) if !metadata.is_file() => not_regular_file(path),
+        _ => AppError::io("open", path, e),
+    })?;
+
+    // Checked on the open handle, so the path can't be swapped after the check
+    let metadata = file.metadata().map_err(|e| AppError::io("read", path, e))?;
+    if !metadata.is_file() {
+        return Err(not_regular_file(path));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if metadata.nlink() > 1 {
+            return Err(AppError::Validation(format!(
+                "File has other hard links: {}",
+                path.display()
+            )));
+        }
+    }
+    let len = metadata.len();
+    for pass in 1..=passes {
+        overwrite_pass(&mut file, len, pass < passes)
+            .map_err(|e| AppError::io("overwrite", path, e))?;
+    }
+    after_overwrite(path);
+    file.set_len(0)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| AppError::io("truncate", path, e))?;
+    drop(file);
+
+    fs::remove_file(path).map_err(|e| AppError::io("remove", path, e))?;
+    info!("Wiped {} with {} pass(es)", path.display(), passes);
+
+    Ok(WipeReport {
+        path: path.to_string_lossy().into_owned(),
+        bytes: len,
+        passes,
+        warning: WIPE_WARNING.to_string(),
+    })
+}
+
+/// Overwrite the regular file at `path` `passes` times, then delete it
+pub fn wipe_file(path: &Path, passes: u8) -> Result<WipeReport, AppError> {
+    wipe_with(path, passes, |_| {})
+}
+
+/// Securely delete a file within the allowed roots
+///
+/// The last of `passes` writes zeros; the ones before it write random bytes.
+#[tauri::command]
+pub async fn secure_delete_file(
+    app: AppHandle,
+    window: Window,
+    path: String,
+    passes: u8,
+) -> Result<WipeReport, AppError> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let roots = app.state::<AllowedRoots>();
+        let audit = app.state::<AuditLog>();
+        let guard = CommandGuard::new(&audit, "secure_delete_file");
+        let path = guard.resolved_path(&path, |path| {
+            resolve_in_roots(path, roots.inner()).map_err(|e| AppError::Validation(e.to_string()))
+        })?;
+        wipe_file(&path, passes)
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Secure delete failed: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &[u8] = b"account: 4111-1111-1111-1111\npin: 2468\n";
+
+    #[test]
+    fn test_wiped_file_is_gone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("card.txt");
+        fs::write(&path, FIXTURE).unwrap();
+
+        let report = wipe_file(&path, 3).unwrap();
+        assert_eq!(report.bytes, FIXTURE.len() as u64);
+        assert_eq!(report.passes, 3);
+        assert_eq!(report.warning, WIPE_WARNING);
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_content_is_overwritten_before_unlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("card.txt");
+        fs::write(&path, FIXTURE).unwrap();
+
+        let mut on_disk = None;
+        wipe_with(&path, 2, |wiped| on_disk = Some(fs::read(wiped).unwrap())).unwrap();
+
+        let on_disk = on_disk.unwrap();
+        assert_ne!(on_disk.as_slice(), FIXTURE);
+        assert_eq!(on_disk, vec![0u8; FIXTURE.len()]);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_directories_and_bad_passes_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            wipe_file(dir.path(), 1),
+            Err(AppError::Validation(_))
+        ));
+
+        let path = dir.path().join("card.txt");
+        fs::write(&path, FIXTURE).unwrap();
+        assert!(wipe_file(&path, 0).is_err());
+        assert!(wipe_file(&path, MAX_WIPE_PASSES + 1).is_err());
+        assert_eq!(fs::read(&path).unwrap(), FIXTURE);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hard_linked_file_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("card.txt");
+        let link = dir.path().join("card-backup.txt");
+        fs::write(&path, FIXTURE).unwrap();
+        fs::hard_link(&path, &link).unwrap();
+
+        assert!(matches!(wipe_file(&path, 1), Err(AppError::Validation(_))));
+        assert_eq!(fs::read(&path).unwrap(), FIXTURE);
+        assert_eq!(fs::read(&link).unwrap(), FIXTURE);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_target_is_never_wiped() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("card.txt");
+        let link = dir.path().join("card-link.txt");
+        fs::write(&target, FIXTURE).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        assert!(matches!(wipe_file(&link, 1), Err(AppError::Validation(_))));
+        assert_eq!(fs::read(&target).unwrap(), FIXTURE);
+        assert!(fs::symlink_metadata(&link).is_ok());
+    }
+}
@@ -0,0 +1,182 @@
+//! File signing and verification for Tauri applications
+//!
+//! Plugins and data files shipped alongside the app can be checked for authenticity:
+//! 1. Files are streamed through SHA-512 and signed with Ed25519ph (prehashed)
+//! 2. Signatures are detached and, like keys, exchanged as base64
+//! 3. Private keys are moved into a `SecureString` on arrival and decoded into
+//!    `SecureBytes`, so both forms are zeroed after use
+
+use super::allowed_roots::AllowedRoots;
+use super::crypto::stream_file;
+use super::error::AppError;
+use super::file_system::validated_path;
+use super::memory_safe::{consume_into_secure, SecureBytes, SecureKey, SecureString};
+use super::scoped_access::{check_scoped, ScopedAccess};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Signature, SigningKey, VerifyingKey, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH};
+use sha2::{Digest, Sha512};
+use std::path::Path;
+use tauri::{State, Window};
+
+/// Domain separation context, so file signatures can't be reused elsewhere
+const SIGNATURE_CONTEXT: &[u8] = b"tauri-security-boilerplate/file-signature/v1";
+
+/// Stream a file through SHA-512 for prehashed signing
+fn hash_file(path: &Path) -> Result<Sha512, AppError> {
+    let mut hasher = Sha512::new();
+    stream_file(path, |chunk| hasher.update(chunk))?;
+    Ok(hasher)
+}
+
+/// Decode base64 text, reporting `what` on failure
+fn decode(encoded: &str, what: &str) -> Result<Vec<u8>, AppError> {
+    STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| AppError::Validation(format!("Invalid {} encoding: {}", what, e)))
+}
+
+/// Build a signing key from raw secret key bytes
+pub fn signing_key_from(secret: &SecureBytes) -> Result<SigningKey, AppError> {
+    let bytes = SecureKey::<SECRET_KEY_LENGTH>::from_bytes(secret.as_bytes()).map_err(|_| {
+        AppError::Validation(format!("Signing key must be {} bytes", SECRET_KEY_LENGTH))
+    })?;
+    Ok(SigningKey::from_bytes(bytes.as_array()))
+}
+
+/// Sign the file at `path`, returning the raw detached signature
+pub fn sign_path(path: &Path, secret: &SecureBytes) -> Result<Signature, AppError> {
+    let key = signing_key_from(secret)?;
+    key.sign_prehashed(hash_file(path)?, Some(SIGNATURE_CONTEXT))
+        .map_err(|e| AppError::Validation(format!("Failed to sign: {}", e)))
+}
+
+/// Check a detached signature of the file at `path`
+pub fn verify_path(
+    path: &Path,
+    signature: &Signature,
+    public_key: &VerifyingKey,
+) -> Result<bool, AppError> {
+    Ok(public_key
+        .verify_prehashed(hash_file(path)?, Some(SIGNATURE_CONTEXT), signature)
+        .is_ok())
+}
+
+/// Sign a file with a base64-encoded Ed25519 secret key
+///
+/// Returns the detached signature as base64.
+#[tauri::command]
+pub fn sign_file(
+    roots: State<'_, AllowedRoots>,
+    path: String,
+    signing_key: String,
+) -> Result<String, AppError> {
+    // Take over the key's buffer first, so it is zeroed however the call ends
+    let signing_key = consume_into_secure(signing_key);
+    let path = validated_path(&path).map_err(AppError::Validation)?;
+    let path = roots.check(&path)?;
+    sign_encoded(&path, &signing_key)
+}
+
+/// Sign `path` with a base64 secret key, returning a base64 signature
+pub(crate) fn sign_encoded(path: &Path, signing_key: &SecureString) -> Result<String, AppError> {
+    // Decode straight into a `SecureBytes` so the key is zeroed on drop
+    let secret = SecureBytes::new(decode(signing_key.as_str(), "signing key")?);
+    let signature = sign_path(path, &secret)?;
+    Ok(STANDARD.encode(signature.to_bytes()))
+}
+
+/// Verify a base64 detached signature against a base64 Ed25519 public key
+///
+/// A signature that doesn't match yields `Ok(false)`; malformed input is an error.
+#[tauri::command]
+pub fn verify_file(
+    roots: State<'_, AllowedRoots>,
+    scope: State<'_, ScopedAccess>,
+    window: Window,
+    path: String,
+    signature: String,
+    public_key: String,
+) -> Result<bool, AppError> {
+    let path = validated_path(&path).map_err(AppError::Validation)?;
+    let path = check_scoped(&roots, &scope, window.label(), &path)?;
+    verify_encoded(&path, &signature, &public_key)
+}
+
+/// Verify `path` against a base64 signature and base64 public key
+pub(crate) fn verify_encoded(
+    path: &Path,
+    signature: &str,
+    public_key: &str,
+) -> Result<bool, AppError> {
+    let signature = Signature::from_slice(&decode(signature, "signature")?)
+        .map_err(|e| AppError::Validation(format!("Invalid signature: {}", e)))?;
+
+    let key_bytes: [u8; PUBLIC_KEY_LENGTH] =
+        decode(public_key, "public key")?.try_into().map_err(|_| {
+            AppError::Validation(format!("Public key must be {} bytes", PUBLIC_KEY_LENGTH))
+        })?;
+    let public_key = VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|e| AppError::Validation(format!("Invalid public key: {}", e)))?;
+
+    verify_path(path, &signature, &public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::crypto::{random_bytes, HASH_CHUNK_BYTES};
+    use std::fs;
+
+    /// Generate a keypair as (base64 secret key, base64 public key)
+    fn keypair() -> (SecureString, String) {
+        let secret = random_bytes(SECRET_KEY_LENGTH).unwrap();
+        let public = signing_key_from(&secret).unwrap().verifying_key();
+        (
+            SecureString::new(STANDARD.encode(secret.as_bytes())),
+            STANDARD.encode(public.to_bytes()),
+        )
+    }
+
+    #[test]
+    fn test_sign_then_verify() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("plugin.bin");
+        // Larger than one read chunk so streaming is exercised
+        fs::write(&file, vec![42u8; HASH_CHUNK_BYTES * 2 + 7]).unwrap();
+
+        let (secret, public) = keypair();
+        let signature = sign_encoded(&file, &secret).unwrap();
+        assert!(verify_encoded(&file, &signature, &public).unwrap());
+    }
+
+    #[test]
+    fn test_tampered_file_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data.json");
+        fs::write(&file, r#"{"trusted": true}"#).unwrap();
+
+        let (secret, public) = keypair();
+        let signature = sign_encoded(&file, &secret).unwrap();
+
+        fs::write(&file, r#"{"trusted": false}"#).unwrap();
+        assert!(!verify_encoded(&file, &signature, &public).unwrap());
+    }
+
+    #[test]
+    fn test_wrong_key_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data.json");
+        fs::write(&file, "payload").unwrap();
+
+        let (secret, _) = keypair();
+        let (_, other_public) = keypair();
+        let signature = sign_encoded(&file, &secret).unwrap();
+        assert!(!verify_encoded(&file, &signature, &other_public).unwrap());
+
+        assert!(matches!(
+            verify_encoded(&file, &signature, "not base64!"),
+            Err(AppError::Validation(_))
+        ));
+    }
+}
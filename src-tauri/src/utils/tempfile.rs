@@ -0,0 +1,230 @@
+//! Temporary files with guaranteed cleanup for Tauri applications
+//!
+//! Scratch files never outlive the code that needs them:
+//! 1. `TempFile` creates a uniquely named, owner-only file and deletes it on drop
+//! 2. Drop also runs while a panic unwinds, so caught panics leave nothing behind
+//! 3. Files live in the OS temp dir unless `scratch_dir` is configured
+//! 4. Files handed to the frontend are tracked in managed state until released
+
+use super::app_config::ConfigState;
+use super::window_caps::{require_capability, CapabilityToken};
+use log::warn;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{State, Window};
+use uuid::Uuid;
+
+/// Prefix of every temporary file name, to make stray files recognizable
+pub const TEMP_FILE_PREFIX: &str = "tsb-";
+
+/// Most temporary files the frontend may hold at once
+pub const MAX_TRACKED_TEMP_FILES: usize = 256;
+
+/// A temporary file that is deleted when dropped
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl TempFile {
+    /// Create a temporary file in the OS temp dir
+    pub fn new() -> io::Result<Self> {
+        Self::new_in(&env::temp_dir())
+    }
+
+    /// Create a temporary file in `dir`
+    ///
+    /// The file is created exclusively, so an existing file or symlink at
+    /// the chosen name is never reused, and is readable only by its owner.
+    pub fn new_in(dir: &Path) -> io::Result<Self> {
+        let path = dir.join(format!("{}{}.tmp", TEMP_FILE_PREFIX, Uuid::new_v4()));
+        let mut options = OpenOptions::new();
+        options.read(true).write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let file = options.open(&path)?;
+        Ok(Self {
+            path,
+            file: Some(file),
+        })
+    }
+
+    /// Where the file lives
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The open file, for writing
+    #[cfg(test)]
+    pub fn as_file_mut(&mut self) -> &mut File {
+        self.file.as_mut().expect("file is open until drop")
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        // Close first; Windows can't delete a file that is still open
+        drop(self.file.take());
+        match fs::remove_file(&self.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => warn!(
+                "Failed to remove temporary file {}: {}",
+                self.path.display(),
+                e
+            ),
+        }
+    }
+}
+
+/// A temporary file handed to the frontend
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TempFileHandle {
+    /// Id to pass to `release_temp_file`
+    pub id: String,
+    /// Where the file lives
+    pub path: String,
+}
+
+/// Temporary files held for the frontend, in Tauri managed state
+///
+/// Dropping the registry deletes every file it still holds.
+#[derive(Default)]
+pub struct TempFileRegistry {
+    files: Mutex<HashMap<String, TempFile>>,
+}
+
+impl TempFileRegistry {
+    /// Create a temporary file in `dir` and hold it until released
+    pub fn create_in(&self, dir: &Path) -> Result<TempFileHandle, String> {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        if files.len() >= MAX_TRACKED_TEMP_FILES {
+            return Err(format!(
+                "Too many temporary files; release one of the {} held",
+                MAX_TRACKED_TEMP_FILES
+            ));
+        }
+
+        let file = TempFile::new_in(dir).map_err(|e| {
+            format!(
+                "Failed to create a temporary file in {}: {}",
+                dir.display(),
+                e
+            )
+        })?;
+        let handle = TempFileHandle {
+            id: Uuid::new_v4().to_string(),
+            path: file.path().to_string_lossy().into_owned(),
+        };
+        files.insert(handle.id.clone(), file);
+        Ok(handle)
+    }
+
+    /// Delete a held file, returning whether it was held
+    pub fn release(&self, id: &str) -> bool {
+        self.files
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(id)
+            .is_some()
+    }
+}
+
+/// Create a temporary file and return its id and path
+#[tauri::command]
+pub fn create_temp_file(
+    window: Window,
+    temp_files: State<'_, TempFileRegistry>,
+    config: State<'_, ConfigState>,
+) -> Result<TempFileHandle, String> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let dir = match &config.current().scratch_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => env::temp_dir(),
+    };
+    temp_files.create_in(&dir)
+}
+
+/// Delete a file created by `create_temp_file`
+#[tauri::command]
+pub fn release_temp_file(
+    temp_files: State<'_, TempFileRegistry>,
+    id: String,
+) -> Result<(), String> {
+    if temp_files.release(&id) {
+        Ok(())
+    } else {
+        Err(format!("No temporary file with id {}", id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::panic;
+
+    #[test]
+    fn test_file_exists_until_dropped() {
+        let mut temp = TempFile::new().unwrap();
+        let path = temp.path().to_path_buf();
+        temp.as_file_mut().write_all(b"scratch").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"scratch");
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(TEMP_FILE_PREFIX));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        drop(temp);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_file_is_removed_when_a_panic_unwinds() {
+        let dir = ::tempfile::tempdir().unwrap();
+        let path = panic::catch_unwind(|| {
+            let temp = TempFile::new_in(dir.path()).unwrap();
+            assert!(temp.path().exists());
+            panic!("{}", temp.path().display());
+        })
+        .unwrap_err();
+
+        let path = PathBuf::from(path.downcast_ref::<String>().unwrap());
+        assert!(path.starts_with(dir.path()));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_registry_holds_files_until_released() {
+        let dir = ::tempfile::tempdir().unwrap();
+        let registry = TempFileRegistry::default();
+        let first = registry.create_in(dir.path()).unwrap();
+        let second = registry.create_in(dir.path()).unwrap();
+        assert_ne!(first.path, second.path);
+        assert!(Path::new(&first.path).exists());
+
+        assert!(registry.release(&first.id));
+        assert!(!registry.release(&first.id));
+        assert!(!Path::new(&first.path).exists());
+
+        drop(registry);
+        assert!(!Path::new(&second.path).exists());
+    }
+}
@@ -0,0 +1,394 @@
+//! Chunked file writes for Tauri applications
+//!
+//! Large files can't cross the IPC bridge in one message, so they are sent in pieces:
+//! 1. `begin_file_write` validates the destination and declared size and opens
+//!    a session; an existing destination is only replaced when `overwrite` is set
+//! 2. `write_file_chunk` appends chunks, which must arrive in order
+//! 3. `finish_file_write` checks the size and renames the temporary file into place
+//! 4. Sessions idle for longer than the timeout are discarded with their data
+
+use super::allowed_roots::AllowedRoots;
+use super::audit::AuditLog;
+use super::file_ops::temp_path_for;
+use super::guard::CommandGuard;
+use super::memory_safe::BoundaryValidator;
+use super::retry::{retry, RetryPolicy};
+use super::window_caps::{require_capability, CapabilityToken};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{State, Window};
+use uuid::Uuid;
+
+/// Largest chunk accepted in a single call (8 MiB)
+pub const MAX_CHUNK_BYTES: usize = 8 * 1024 * 1024;
+
+/// Largest file a chunked write may produce (4 GiB)
+pub const MAX_WRITE_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// How long a session may sit idle before it is discarded
+pub const DEFAULT_SESSION_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// An in-progress chunked write
+struct WriteSession {
+    /// Final location of the file
+    dest: PathBuf,
+    /// Temporary file being assembled next to `dest`
+    temp: PathBuf,
+    file: File,
+    /// Bytes written so far, i.e. the offset expected next
+    written: u64,
+    /// Size declared by `begin_file_write`
+    total_size: u64,
+    /// Whether an existing file at `dest` may be replaced
+    overwrite: bool,
+    last_activity: Instant,
+}
+
+impl WriteSession {
+    /// Discard the partially written temporary file
+    fn abandon(self) {
+        drop(self.file);
+        if let Err(e) = fs::remove_file(&self.temp) {
+            warn!("Failed to remove {}: {}", self.temp.display(), e);
+        }
+    }
+}
+
+/// Chunked write sessions, held in Tauri managed state
+pub struct WriteRegistry {
+    sessions: Mutex<HashMap<String, WriteSession>>,
+    timeout: Duration,
+}
+
+impl Default for WriteRegistry {
+    fn default() -> Self {
+        Self::with_timeout(DEFAULT_SESSION_TIMEOUT)
+    }
+}
+
+impl WriteRegistry {
+    /// Create a registry whose sessions expire after `timeout` of inactivity
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            timeout,
+        }
+    }
+
+    /// Discard sessions that have been idle for longer than the timeout
+    ///
+    /// Called whenever a session is started, so abandoned uploads don't
+    /// accumulate without needing a background thread.
+    pub fn collect_expired(&self) -> usize {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let expired: Vec<String> = sessions
+            .iter()
+            .filter(|(_, session)| session.last_activity.elapsed() > self.timeout)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in &expired {
+            if let Some(session) = sessions.remove(id) {
+                info!("Discarding abandoned write to {}", session.dest.display());
+                session.abandon();
+            }
+        }
+        expired.len()
+    }
+
+    /// Start assembling `total_size` bytes destined for `dest`
+    ///
+    /// Unless `overwrite` is set, `dest` must not exist, now or at `finish`.
+    pub fn begin(&self, dest: &Path, total_size: u64, overwrite: bool) -> Result<String, String> {
+        self.collect_expired();
+
+        if total_size > MAX_WRITE_BYTES {
+            return Err(format!(
+                "File exceeds the maximum of {} bytes",
+                MAX_WRITE_BYTES
+            ));
+        }
+        if !overwrite && fs::symlink_metadata(dest).is_ok() {
+            return Err(format!("Destination already exists: {}", dest.display()));
+        }
+
+        let temp = temp_path_for(dest);
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)
+            .map_err(|e| format!("Failed to create {}: {}", temp.display(), e))?;
+
+        let write_id = Uuid::new_v4().to_string();
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                write_id.clone(),
+                WriteSession {
+                    dest: dest.to_path_buf(),
+                    temp,
+                    file,
+                    written: 0,
+                    total_size,
+                    overwrite,
+                    last_activity: Instant::now(),
+                },
+            );
+        Ok(write_id)
+    }
+
+    /// Append `data` at `offset`, which must equal the bytes written so far
+    pub fn write_chunk(&self, write_id: &str, offset: u64, data: &[u8]) -> Result<u64, String> {
+        if data.len() > MAX_CHUNK_BYTES {
+            return Err(format!(
+                "Chunk exceeds the maximum of {} bytes",
+                MAX_CHUNK_BYTES
+            ));
+        }
+
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let session = sessions
+            .get_mut(write_id)
+            .ok_or_else(|| format!("No active write with id {}", write_id))?;
+
+        if offset != session.written {
+            return Err(format!(
+                "Chunk at offset {} is out of order; expected offset {}",
+                offset, session.written
+            ));
+        }
+        let end = session.written + data.len() as u64;
+        if end > session.total_size {
+            return Err(format!(
+                "Chunk would exceed the declared size of {} bytes",
+                session.total_size
+            ));
+        }
+
+        session
+            .file
+            .write_all(data)
+            .map_err(|e| format!("Failed to write chunk: {}", e))?;
+        session.written = end;
+        session.last_activity = Instant::now();
+        Ok(end)
+    }
+
+    /// Check the assembled size and atomically move the file into place
+    pub fn finish(&self, write_id: &str) -> Result<PathBuf, String> {
+        let session = self
+            .sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(write_id)
+            .ok_or_else(|| format!("No active write with id {}", write_id))?;
+
+        if session.written != session.total_size {
+            let error = format!(
+                "Size mismatch: declared {} bytes but received {}",
+                session.total_size, session.written
+            );
+            session.abandon();
+            return Err(error);
+        }
+
+        if let Err(e) = session.file.sync_all() {
+            session.abandon();
+            return Err(format!("Failed to flush file: {}", e));
+        }
+
+        // A plain rename replaces silently, so check again in case it appeared since
+        if !session.overwrite && fs::symlink_metadata(&session.dest).is_ok() {
+            let error = format!("Destination already exists: {}", session.dest.display());
+            session.abandon();
+            return Err(error);
+        }
+
+        // Close the file first; Windows can't rename a file that is open
+        let WriteSession {
+            dest, temp, file, ..
+        } = session;
+        drop(file);
+        if let Err(e) = retry(&RetryPolicy::default(), || fs::rename(&temp, &dest)) {
+            let _ = fs::remove_file(&temp);
+            return Err(format!("Failed to move file into place: {}", e));
+        }
+        Ok(dest)
+    }
+}
+
+/// Validate a destination received from the frontend and confine it to `roots`
+fn validated_dest(
+    guard: &CommandGuard,
+    roots: &AllowedRoots,
+    dest: &str,
+) -> Result<PathBuf, String> {
+    guard.resolved_path(dest, |path| {
+        let Some(name) = path.file_name() else {
+            return Err(format!("No file name in {}", dest));
+        };
+        BoundaryValidator::validate_filename(&name.to_string_lossy()).map_err(|e| e.to_string())?;
+        match path.parent() {
+            Some(parent) if parent.as_os_str().is_empty() || parent.is_dir() => {
+                Ok(roots.check(path)?)
+            }
+            _ => Err(format!("Parent directory does not exist: {}", dest)),
+        }
+    })
+}
+
+/// Start a chunked write of `total_size` bytes to `dest`
+///
+/// An existing file at `dest` is only replaced when `overwrite` is set.
+#[tauri::command]
+pub fn begin_file_write(
+    window: Window,
+    writes: State<'_, WriteRegistry>,
+    roots: State<'_, AllowedRoots>,
+    audit: State<'_, AuditLog>,
+    dest: String,
+    total_size: u64,
+    overwrite: bool,
+) -> Result<String, String> {
+    require_capability(&window, CapabilityToken::WRITE_FILES)?;
+    let guard = CommandGuard::new(&audit, "begin_file_write");
+    let dest = validated_dest(&guard, &roots, &dest)?;
+    writes.begin(&dest, total_size, overwrite)
+}
+
+/// Append a chunk to a write started by `begin_file_write`
+///
+/// Returns the number of bytes written so far.
+#[tauri::command]
+pub fn write_file_chunk(
+    writes: State<'_, WriteRegistry>,
+    write_id: String,
+    offset: u64,
+    data: Vec<u8>,
+) -> Result<u64, String> {
+    writes.write_chunk(&write_id, offset, &data)
+}
+
+/// Complete a chunked write, returning the final path
+#[tauri::command]
+pub fn finish_file_write(
+    writes: State<'_, WriteRegistry>,
+    write_id: String,
+) -> Result<String, String> {
+    writes
+        .finish(&write_id)
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::guard::TestAudit;
+
+    #[test]
+    fn test_in_order_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("upload.bin");
+        let writes = WriteRegistry::default();
+
+        let id = writes.begin(&dest, 10, false).unwrap();
+        assert_eq!(writes.write_chunk(&id, 0, b"hello").unwrap(), 5);
+        assert_eq!(writes.write_chunk(&id, 5, b"world").unwrap(), 10);
+        assert!(!dest.exists());
+
+        assert_eq!(writes.finish(&id).unwrap(), dest);
+        assert_eq!(fs::read(&dest).unwrap(), b"helloworld");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+        assert!(writes.finish(&id).is_err());
+    }
+
+    #[test]
+    fn test_out_of_order_chunk_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let writes = WriteRegistry::default();
+
+        let id = writes
+            .begin(&dir.path().join("upload.bin"), 10, false)
+            .unwrap();
+        assert!(writes.write_chunk(&id, 5, b"world").is_err());
+        writes.write_chunk(&id, 0, b"hello").unwrap();
+        assert!(writes.write_chunk(&id, 0, b"hello").is_err());
+        assert!(writes.write_chunk(&id, 5, b"world!").is_err());
+    }
+
+    #[test]
+    fn test_size_mismatch_at_finish() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("upload.bin");
+        let writes = WriteRegistry::default();
+
+        let id = writes.begin(&dest, 10, false).unwrap();
+        writes.write_chunk(&id, 0, b"short").unwrap();
+
+        let err = writes.finish(&id).unwrap_err();
+        assert!(err.contains("Size mismatch"));
+        assert!(!dest.exists());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_existing_destination_needs_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("upload.bin");
+        fs::write(&dest, "keep me").unwrap();
+        let writes = WriteRegistry::default();
+
+        assert!(writes.begin(&dest, 5, false).is_err());
+        assert!(writes.begin(&dest, MAX_WRITE_BYTES + 1, true).is_err());
+
+        // A file that appears while the upload is running is kept too
+        let late = dir.path().join("late.bin");
+        let id = writes.begin(&late, 5, false).unwrap();
+        writes.write_chunk(&id, 0, b"hello").unwrap();
+        fs::write(&late, "arrived first").unwrap();
+        assert!(writes.finish(&id).unwrap_err().contains("already exists"));
+        assert_eq!(fs::read(&late).unwrap(), b"arrived first");
+
+        let id = writes.begin(&dest, 5, true).unwrap();
+        writes.write_chunk(&id, 0, b"hello").unwrap();
+        assert_eq!(writes.finish(&id).unwrap(), dest);
+        assert_eq!(fs::read(&dest).unwrap(), b"hello");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_abandoned_sessions_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        let writes = WriteRegistry::with_timeout(Duration::ZERO);
+
+        let id = writes
+            .begin(&dir.path().join("upload.bin"), 10, false)
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(writes.collect_expired(), 1);
+        assert!(writes.write_chunk(&id, 0, b"hello").is_err());
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_rejects_invalid_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let roots = AllowedRoots::new();
+        roots.add(dir.path()).unwrap();
+        let audit = TestAudit::new();
+        let guard = audit.guard();
+        let dest = |path: &Path| validated_dest(&guard, &roots, &path.to_string_lossy());
+
+        assert!(validated_dest(&guard, &roots, "../../etc/passwd").is_err());
+        assert!(dest(Path::new("/definitely/missing/dir/file.bin")).is_err());
+        assert!(dest(&outside.path().join("upload.bin")).is_err());
+        assert!(dest(&dir.path().join("upload.bin")).is_ok());
+    }
+}
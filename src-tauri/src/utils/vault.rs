@@ -0,0 +1,242 @@
+//! Short-lived secret storage for Tauri applications
+//!
+//! Secrets the frontend needs again shortly are kept in the backend, not in JS:
+//! 1. `vault_put` stores a secret as a `SecureString` with a time to live
+//! 2. `vault_get` returns a redacted hint unless the plaintext is asked for
+//! 3. A background sweep zeroes expired entries; lookups never return them
+//! 4. `vault_clear_all` zeroes every entry at once, as a panic button
+//! 5. The clock is injectable so expiry can be tested without sleeping
+
+use super::error::AppError;
+use super::memory_safe::{consume_into_secure, SecureString};
+use log::info;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+/// Time to live of an entry stored without one (5 minutes)
+pub const DEFAULT_VAULT_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Longest time to live an entry may be given (24 hours)
+pub const MAX_VAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Most entries the vault holds at once
+pub const MAX_VAULT_ENTRIES: usize = 256;
+
+/// How often the background sweep zeroes expired entries
+pub const VAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Characters of a secret shown at the end of its hint
+const HINT_SUFFIX_CHARS: usize = 4;
+
+/// Source of the current time
+pub type Clock = Arc<dyn Fn() -> Instant + Send + Sync>;
+
+/// A stored secret and when it expires
+struct VaultEntry {
+    secret: SecureString,
+    expires_at: Instant,
+}
+
+/// Secrets held for the frontend, in Tauri managed state
+pub struct SecretVault {
+    entries: Mutex<HashMap<String, VaultEntry>>,
+    clock: Clock,
+}
+
+impl Default for SecretVault {
+    fn default() -> Self {
+        Self::with_clock(Arc::new(Instant::now))
+    }
+}
+
+impl SecretVault {
+    /// Create a vault that reads the time from `clock`
+    pub fn with_clock(clock: Clock) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            clock,
+        }
+    }
+
+    fn lock_entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, VaultEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Store `secret` for `ttl`, returning its id
+    pub fn put(&self, secret: SecureString, ttl: Duration) -> Result<String, AppError> {
+        if ttl.is_zero() || ttl > MAX_VAULT_TTL {
+            return Err(AppError::Validation(format!(
+                "Time to live must be between 1 ms and {} s",
+                MAX_VAULT_TTL.as_secs()
+            )));
+        }
+
+        self.purge_expired();
+        let mut entries = self.lock_entries();
+        if entries.len() >= MAX_VAULT_ENTRIES {
+            return Err(AppError::Validation(format!(
+                "The vault holds at most {} secrets",
+                MAX_VAULT_ENTRIES
+            )));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let expires_at = (self.clock)() + ttl;
+        entries.insert(id.clone(), VaultEntry { secret, expires_at });
+        Ok(id)
+    }
+
+    /// Read an entry as a redacted hint, or as plaintext when `reveal` is set
+    pub fn get(&self, id: &str, reveal: bool) -> Result<String, AppError> {
+        let now = (self.clock)();
+        let entries = self.lock_entries();
+        match entries.get(id) {
+            Some(entry) if entry.expires_at > now => Ok(if reveal {
+                entry.secret.expose_secret().to_string()
+            } else {
+                entry.secret.redacted_hint(HINT_SUFFIX_CHARS)
+            }),
+            _ => Err(AppError::NotFound(format!("No secret with id {}", id))),
+        }
+    }
+
+    /// Zero and remove entries whose time to live has passed
+    pub fn purge_expired(&self) -> usize {
+        let now = (self.clock)();
+        let mut entries = self.lock_entries();
+        let before = entries.len();
+        // Dropping a `SecureString` zeroes it
+        entries.retain(|_, entry| entry.expires_at > now);
+        before - entries.len()
+    }
+
+    /// Zero and remove every entry
+    pub fn clear_all(&self) -> usize {
+        let mut entries = self.lock_entries();
+        let count = entries.len();
+        entries.clear();
+        count
+    }
+}
+
+/// Zero expired vault entries in the background for the app's lifetime
+pub fn spawn_vault_sweeper(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(VAULT_SWEEP_INTERVAL);
+        let purged = app.state::<SecretVault>().purge_expired();
+        if purged > 0 {
+            info!("Zeroed {} expired vault secret(s)", purged);
+        }
+    });
+}
+
+/// Store a secret for `ttl_ms` (default five minutes) and return its id
+///
+/// The secret's buffer is moved into a `SecureString` rather than copied.
+#[tauri::command]
+pub fn vault_put(
+    vault: State<'_, SecretVault>,
+    secret: String,
+    ttl_ms: Option<u64>,
+) -> Result<String, AppError> {
+    let secret = consume_into_secure(secret);
+    let ttl = ttl_ms.map_or(DEFAULT_VAULT_TTL, Duration::from_millis);
+    vault.put(secret, ttl)
+}
+
+/// Read a stored secret as a hint such as `****cd34`, or in full with `reveal`
+#[tauri::command]
+pub fn vault_get(
+    vault: State<'_, SecretVault>,
+    id: String,
+    reveal: Option<bool>,
+) -> Result<String, AppError> {
+    vault.get(&id, reveal.unwrap_or(false))
+}
+
+/// Zero every stored secret, returning how many there were
+#[tauri::command]
+pub fn vault_clear_all(vault: State<'_, SecretVault>) -> usize {
+    let count = vault.clear_all();
+    info!("Cleared {} vault secret(s)", count);
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::secure_registry::SecureMemoryRegistry;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// A clock that only moves when told to
+    fn manual_clock() -> (Clock, Arc<AtomicU64>) {
+        let start = Instant::now();
+        let offset_ms = Arc::new(AtomicU64::new(0));
+        let offset = Arc::clone(&offset_ms);
+        let clock: Clock =
+            Arc::new(move || start + Duration::from_millis(offset.load(Ordering::SeqCst)));
+        (clock, offset_ms)
+    }
+
+    #[test]
+    fn test_entries_expire_after_ttl() {
+        let (clock, offset_ms) = manual_clock();
+        let vault = SecretVault::with_clock(clock);
+        let short = vault
+            .put(SecureString::new("short-lived"), Duration::from_secs(1))
+            .unwrap();
+        let long = vault
+            .put(SecureString::new("long-lived"), Duration::from_secs(60))
+            .unwrap();
+
+        offset_ms.store(999, Ordering::SeqCst);
+        assert_eq!(vault.get(&short, true).unwrap(), "short-lived");
+
+        offset_ms.store(1_000, Ordering::SeqCst);
+        assert!(matches!(
+            vault.get(&short, true),
+            Err(AppError::NotFound(_))
+        ));
+        assert_eq!(vault.purge_expired(), 1);
+        assert_eq!(vault.get(&long, true).unwrap(), "long-lived");
+    }
+
+    #[test]
+    fn test_clear_all_zeroes_every_entry() {
+        let registry = Arc::new(SecureMemoryRegistry::new());
+        let vault = SecretVault::default();
+        let ids: Vec<String> = ["alpha-secret", "beta-secret", "gamma-secret"]
+            .iter()
+            .map(|secret| {
+                vault
+                    .put(SecureString::new_in(*secret, &registry), DEFAULT_VAULT_TTL)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(registry.live_count(), 3);
+
+        assert_eq!(vault.clear_all(), 3);
+        registry.assert_no_leaks();
+        for id in &ids {
+            assert!(vault.get(id, true).is_err());
+        }
+    }
+
+    #[test]
+    fn test_get_is_redacted_by_default() {
+        let vault = SecretVault::default();
+        let id = vault
+            .put(SecureString::new("sk-live-abcd1234"), DEFAULT_VAULT_TTL)
+            .unwrap();
+
+        let hint = vault.get(&id, false).unwrap();
+        assert_eq!(hint, "****1234");
+        assert!(!hint.contains("sk-live"));
+        assert_eq!(vault.get(&id, true).unwrap(), "sk-live-abcd1234");
+        assert!(vault.put(SecureString::new("x"), Duration::ZERO).is_err());
+    }
+}
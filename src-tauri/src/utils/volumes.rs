@@ -0,0 +1,264 @@
+//! Mounted volume enumeration for Tauri applications
+//!
+//! File-picker sidebars need the drives and volumes a user can browse:
+//! 1. `list_volumes` reports each volume's mount point, capacity and free space
+//! 2. Volumes come from a `VolumeSource`, backed by `sysinfo` on every platform
+//! 3. Volumes that can't be statted are skipped rather than failing the call
+//! 4. `disk_space_for` reports the space on the filesystem containing a path
+
+use super::allowed_roots::AllowedRoots;
+use super::error::AppError;
+use log::warn;
+use serde::Serialize;
+use std::io;
+use std::path::Path;
+use sysinfo::Disks;
+use tauri::State;
+
+/// A mounted drive or volume
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct VolumeInfo {
+    /// Volume label or device name
+    pub name: String,
+    /// Where the volume is mounted, e.g. `/media/usb` or `C:\`
+    pub mount_point: String,
+    /// Capacity in bytes
+    pub total_bytes: u64,
+    /// Bytes available to the current user
+    pub available_bytes: u64,
+    /// Whether the volume is removable, like a USB stick
+    pub is_removable: bool,
+}
+
+/// Where volumes are enumerated from; mocked in tests
+pub trait VolumeSource {
+    /// Every mounted volume, or why it couldn't be statted
+    fn volumes(&self) -> Vec<Result<VolumeInfo, String>>;
+}
+
+/// Volumes reported by the operating system
+pub struct SystemVolumes;
+
+impl VolumeSource for SystemVolumes {
+    fn volumes(&self) -> Vec<Result<VolumeInfo, String>> {
+        Disks::new_with_refreshed_list()
+            .list()
+            .iter()
+            .map(|disk| {
+                let mount_point = disk.mount_point().to_string_lossy().into_owned();
+                // `sysinfo` reports a zero capacity when the volume couldn't be statted
+                if disk.total_space() == 0 {
+                    return Err(format!("Failed to stat volume at {}", mount_point));
+                }
+                Ok(VolumeInfo {
+                    name: disk.name().to_string_lossy().into_owned(),
+                    mount_point,
+                    total_bytes: disk.total_space(),
+                    available_bytes: disk.available_space(),
+                    is_removable: disk.is_removable(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Collect the usable volumes from `source`, sorted by mount point
+///
+/// A mount point listed more than once, as with bind mounts, is kept once.
+pub(crate) fn collect_volumes(source: &dyn VolumeSource) -> Vec<VolumeInfo> {
+    let mut volumes: Vec<VolumeInfo> = source
+        .volumes()
+        .into_iter()
+        .filter_map(|volume| volume.map_err(|e| warn!("Skipping volume: {}", e)).ok())
+        .collect();
+    volumes.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+    volumes.dedup_by(|a, b| a.mount_point == b.mount_point);
+    volumes
+}
+
+/// List mounted drives and volumes
+///
+/// Volumes are statted off the IPC thread, since a stalled network mount can block.
+#[tauri::command]
+pub async fn list_volumes() -> Result<Vec<VolumeInfo>, AppError> {
+    tauri::async_runtime::spawn_blocking(|| collect_volumes(&SystemVolumes))
+        .await
+        .map_err(|e| AppError::Io(format!("Volume listing failed: {}", e)))
+}
+
+/// Space on the filesystem containing a path
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct DiskSpace {
+    /// Capacity in bytes
+    pub total_bytes: u64,
+    /// Bytes available to the current user
+    pub available_bytes: u64,
+    /// Bytes in use, including space reserved for the superuser
+    pub used_bytes: u64,
+}
+
+/// Raw figures for one filesystem, as the operating system reports them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SpaceStats {
+    /// Capacity in bytes
+    pub total: u64,
+    /// Free bytes, including space only the superuser may use
+    pub free: u64,
+    /// Free bytes available to the current user
+    pub available: u64,
+}
+
+/// Where filesystem space figures come from; mocked in tests
+pub trait SpaceSource {
+    /// Space on the filesystem containing `path`
+    fn space(&self, path: &Path) -> io::Result<SpaceStats>;
+}
+
+/// Space reported by `statvfs` or `GetDiskFreeSpaceExW`
+pub struct SystemSpace;
+
+impl SpaceSource for SystemSpace {
+    fn space(&self, path: &Path) -> io::Result<SpaceStats> {
+        Ok(SpaceStats {
+            total: fs2::total_space(path)?,
+            free: fs2::free_space(path)?,
+            available: fs2::available_space(path)?,
+        })
+    }
+}
+
+/// Space on the filesystem containing `path`, which must exist
+///
+/// The figures are clamped so `used_bytes + available_bytes <= total_bytes`
+/// holds even when the filesystem reports inconsistent values.
+pub(crate) fn disk_space_in(source: &dyn SpaceSource, path: &Path) -> Result<DiskSpace, AppError> {
+    if !path.exists() {
+        return Err(AppError::NotFound(path.display().to_string()));
+    }
+    let stats = source.space(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => AppError::NotFound(path.display().to_string()),
+        _ => AppError::Io(format!(
+            "Failed to query disk space for {}: {}",
+            path.display(),
+            e
+        )),
+    })?;
+
+    let used_bytes = stats.total.saturating_sub(stats.free);
+    Ok(DiskSpace {
+        total_bytes: stats.total,
+        available_bytes: stats.available.min(stats.total - used_bytes),
+        used_bytes,
+    })
+}
+
+/// Report the space on the filesystem containing a path within the allowed roots
+#[tauri::command]
+pub fn disk_space_for(roots: State<'_, AllowedRoots>, path: String) -> Result<DiskSpace, AppError> {
+    let path = roots.check(Path::new(&path))?;
+    disk_space_in(&SystemSpace, &path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockVolumes(Vec<Result<VolumeInfo, String>>);
+
+    impl VolumeSource for MockVolumes {
+        fn volumes(&self) -> Vec<Result<VolumeInfo, String>> {
+            self.0.clone()
+        }
+    }
+
+    fn volume(name: &str, mount_point: &str, is_removable: bool) -> VolumeInfo {
+        VolumeInfo {
+            name: name.into(),
+            mount_point: mount_point.into(),
+            total_bytes: 1000,
+            available_bytes: 400,
+            is_removable,
+        }
+    }
+
+    #[test]
+    fn test_volumes_are_sorted_and_deduplicated() {
+        let source = MockVolumes(vec![
+            Ok(volume("USB", "/media/usb", true)),
+            Ok(volume("root", "/", false)),
+            Ok(volume("root", "/", false)),
+        ]);
+
+        let volumes = collect_volumes(&source);
+        assert_eq!(
+            volumes,
+            vec![
+                volume("root", "/", false),
+                volume("USB", "/media/usb", true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unstattable_volumes_are_skipped() {
+        let source = MockVolumes(vec![
+            Err("Failed to stat volume at /mnt/stale".into()),
+            Ok(volume("Data", r"D:\", false)),
+        ]);
+
+        assert_eq!(
+            collect_volumes(&source),
+            vec![volume("Data", r"D:\", false)]
+        );
+        assert!(collect_volumes(&MockVolumes(Vec::new())).is_empty());
+    }
+
+    struct MockSpace(SpaceStats);
+
+    impl SpaceSource for MockSpace {
+        fn space(&self, _path: &Path) -> io::Result<SpaceStats> {
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn test_disk_space_from_mocked_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = MockSpace(SpaceStats {
+            total: 1000,
+            free: 300,
+            available: 250,
+        });
+        assert_eq!(
+            disk_space_in(&source, dir.path()).unwrap(),
+            DiskSpace {
+                total_bytes: 1000,
+                available_bytes: 250,
+                used_bytes: 700,
+            }
+        );
+
+        // Inconsistent figures are clamped rather than overflowing
+        let source = MockSpace(SpaceStats {
+            total: 1000,
+            free: 1200,
+            available: 1500,
+        });
+        let space = disk_space_in(&source, dir.path()).unwrap();
+        assert_eq!(space.used_bytes, 0);
+        assert_eq!(space.available_bytes, 1000);
+
+        assert!(matches!(
+            disk_space_in(&source, &dir.path().join("missing")),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_system_disk_space_is_consistent() {
+        let dir = tempfile::tempdir().unwrap();
+        let space = disk_space_in(&SystemSpace, dir.path()).unwrap();
+        assert!(space.total_bytes > 0);
+        assert!(space.used_bytes + space.available_bytes <= space.total_bytes);
+    }
+}
@@ -0,0 +1,384 @@
+//! Bounded directory walking for Tauri applications
+//!
+//! Recursive commands share this walker so that adversarial directory trees
+//! can't exhaust memory or the stack:
+//! 1. At most `max_entries_per_dir` entries are loaded from any directory
+//! 2. Directories are walked iteratively rather than recursively
+//! 3. Symlinks are only followed on request, with loop detection, and can be
+//!    confined to targets under a set of root directories
+//! 4. Walks can be cancelled through a shared `CancellationToken`
+//! 5. Unreadable subdirectories are skipped and reported
+//! 6. Any `FileSystem` backend can be walked, not just the real disk
+
+use super::fs_backend::{FileSystem, RealFs};
+use log::{info, warn};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Default limit on the number of entries loaded from a single directory
+pub const DEFAULT_MAX_ENTRIES_PER_DIR: usize = 10_000;
+
+/// A cheaply cloneable flag used to stop a walk from another thread
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a new, uncancelled token
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of every walk holding a clone of this token
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Check whether cancellation has been requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Summary of a completed walk
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WalkSummary {
+    /// Directories that had more entries than the limit
+    pub truncated_dirs: Vec<String>,
+    /// Subdirectories that could not be read (e.g. permission denied)
+    pub skipped_dirs: Vec<String>,
+    /// Whether the walk stopped early because it was cancelled
+    pub cancelled: bool,
+}
+
+/// A directory walker with a per-directory fan-out limit
+#[derive(Clone, Debug)]
+pub struct DirectoryWalker {
+    /// Whether to descend into subdirectories
+    recursive: bool,
+
+    /// Maximum number of entries loaded from a single directory
+    max_entries_per_dir: usize,
+
+    /// Whether to descend into symlinked directories
+    follow_symlinks: bool,
+
+    /// Canonical directories that followed symlinks must resolve under
+    confined_to: Option<Vec<PathBuf>>,
+
+    /// Token checked between directory entries
+    cancellation: Option<CancellationToken>,
+}
+
+impl DirectoryWalker {
+    /// Create a new walker
+    pub fn new(recursive: bool, max_entries_per_dir: usize) -> Self {
+        Self {
+            recursive,
+            max_entries_per_dir,
+            follow_symlinks: false,
+            confined_to: None,
+            cancellation: None,
+        }
+    }
+
+    /// Descend into symlinked directories, visiting each real directory once
+    pub fn with_follow_symlinks(mut self, follow_symlinks: bool) -> Self {
+        self.follow_symlinks = follow_symlinks;
+        self
+    }
+
+    /// Only follow symlinks whose canonical target lies under one of `roots`
+    pub fn with_confinement(mut self, roots: Vec<PathBuf>) -> Self {
+        self.confined_to = Some(roots);
+        self
+    }
+
+    /// Stop the walk promptly once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Check whether the walk has been cancelled
+    fn is_cancelled(&self) -> bool {
+        self.cancellation
+            .as_ref()
+            .map(CancellationToken::is_cancelled)
+            .unwrap_or(false)
+    }
+
+    /// Walk `root`, calling `visit` once per directory with its (bounded) entries
+    ///
+    /// Entries passed to `visit` are sorted by path. When a directory has more
+    /// entries than the limit, the remainder is never read and the directory is
+    /// reported in `WalkSummary::truncated_dirs`; the walk continues elsewhere.
+    /// If the walk is cancelled, it stops and sets `WalkSummary::cancelled`.
+    /// Failing to read `root` is an error; failing to read a subdirectory is
+    /// recorded in `WalkSummary::skipped_dirs` and the walk continues.
+    pub fn walk<F>(&self, root: &Path, visit: F) -> Result<WalkSummary, String>
+    where
+        F: FnMut(&Path, &[PathBuf]) -> Result<(), String>,
+    {
+        self.walk_in(&RealFs, root, visit)
+    }
+
+    /// Walk `root` in `fs`; see `walk`
+    pub fn walk_in<F>(
+        &self,
+        fs: &dyn FileSystem,
+        root: &Path,
+        mut visit: F,
+    ) -> Result<WalkSummary, String>
+    where
+        F: FnMut(&Path, &[PathBuf]) -> Result<(), String>,
+    {
+        let mut summary = WalkSummary::default();
+        let mut pending = vec![root.to_path_buf()];
+        let mut visited = HashSet::new();
+
+        while let Some(dir) = pending.pop() {
+            if self.follow_symlinks {
+                // Symlinks can form cycles, so only enter each real directory once
+                match fs.canonicalize(&dir) {
+                    Ok(real) if !visited.insert(real) => continue,
+                    _ => {}
+                }
+            }
+
+            let (entries, truncated) = match self.read_bounded(fs, &dir) {
+                Ok(result) => result,
+                Err(e) if dir != root => {
+                    warn!("Skipping unreadable directory: {}", e);
+                    summary
+                        .skipped_dirs
+                        .push(dir.to_string_lossy().into_owned());
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if self.is_cancelled() {
+                info!("Directory walk of {} cancelled", root.display());
+                summary.cancelled = true;
+                break;
+            }
+
+            if truncated {
+                warn!(
+                    "Directory {} exceeds {} entries; listing truncated",
+                    dir.display(),
+                    self.max_entries_per_dir
+                );
+                summary
+                    .truncated_dirs
+                    .push(dir.to_string_lossy().into_owned());
+            }
+
+            visit(&dir, &entries)?;
+
+            if self.recursive {
+                // Push in reverse so subdirectories are visited in sorted order
+                let subdirs: Vec<PathBuf> = entries
+                    .iter()
+                    .filter(|entry| self.is_traversable(fs, entry))
+                    .cloned()
+                    .collect();
+                pending.extend(subdirs.into_iter().rev());
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Check whether the walk should descend into `entry`
+    fn is_traversable(&self, fs: &dyn FileSystem, entry: &Path) -> bool {
+        match fs.symlink_metadata(entry) {
+            Ok(m) if m.is_dir => true,
+            Ok(m) if m.is_symlink && self.follow_symlinks => {
+                self.is_confined(fs, entry) && fs.metadata(entry).map(|m| m.is_dir).unwrap_or(false)
+            }
+            _ => false,
+        }
+    }
+
+    /// Check whether the symlink `entry` resolves under the confinement roots, if any
+    fn is_confined(&self, fs: &dyn FileSystem, entry: &Path) -> bool {
+        let Some(roots) = &self.confined_to else {
+            return true;
+        };
+        match fs.canonicalize(entry) {
+            Ok(real) if roots.iter().any(|root| real.starts_with(root)) => true,
+            _ => {
+                warn!(
+                    "Not following {}; it leads outside the allowed roots",
+                    entry.display()
+                );
+                false
+            }
+        }
+    }
+
+    /// Read at most `max_entries_per_dir` entries, reporting whether more exist
+    fn read_bounded(
+        &self,
+        fs: &dyn FileSystem,
+        dir: &Path,
+    ) -> Result<(Vec<PathBuf>, bool), String> {
+        let iter = fs
+            .read_dir(dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+        let mut entries = Vec::new();
+        let mut truncated = false;
+
+        for entry in iter {
+            if self.is_cancelled() {
+                break;
+            }
+            if entries.len() == self.max_entries_per_dir {
+                truncated = true;
+                break;
+            }
+            entries.push(entry.map_err(|e| format!("Failed to read directory entry: {}", e))?);
+        }
+
+        entries.sort();
+        Ok((entries, truncated))
+    }
+}
+
+impl Default for DirectoryWalker {
+    fn default() -> Self {
+        Self::new(false, DEFAULT_MAX_ENTRIES_PER_DIR)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_truncates_wide_directory_and_continues() {
+        let dir = tempfile::tempdir().unwrap();
+        let wide = dir.path().join("a_wide");
+        let other = dir.path().join("b_other");
+        fs::create_dir(&wide).unwrap();
+        fs::create_dir(&other).unwrap();
+        for i in 0..5 {
+            fs::write(wide.join(format!("file{}", i)), "x").unwrap();
+        }
+        fs::write(other.join("kept.txt"), "x").unwrap();
+
+        let mut visited = Vec::new();
+        let summary = DirectoryWalker::new(true, 3)
+            .walk(dir.path(), |dir, entries| {
+                visited.push((dir.to_path_buf(), entries.len()));
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(summary.truncated_dirs, vec![wide.to_string_lossy()]);
+        assert!(visited.contains(&(wide, 3)));
+        assert!(visited.contains(&(other, 1)));
+    }
+
+    #[test]
+    fn test_cancellation_stops_walk() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..50 {
+            let sub = dir.path().join(format!("dir{:02}", i));
+            fs::create_dir(&sub).unwrap();
+            fs::write(sub.join("file.txt"), "x").unwrap();
+        }
+
+        let token = CancellationToken::new();
+        let mut visited = 0;
+        let summary = DirectoryWalker::new(true, DEFAULT_MAX_ENTRIES_PER_DIR)
+            .with_cancellation(token.clone())
+            .walk(dir.path(), |_, _| {
+                visited += 1;
+                // Cancel from "another" party once the walk is under way
+                if visited == 3 {
+                    token.cancel();
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        assert!(summary.cancelled);
+        assert_eq!(visited, 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks_avoids_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        std::os::unix::fs::symlink(dir.path(), sub.join("loop")).unwrap();
+
+        let mut visits = 0;
+        DirectoryWalker::new(true, DEFAULT_MAX_ENTRIES_PER_DIR)
+            .with_follow_symlinks(true)
+            .walk(dir.path(), |_, _| {
+                visits += 1;
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(visits, 2);
+    }
+
+    #[test]
+    fn test_non_recursive_visits_root_only() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+
+        let mut count = 0;
+        let summary = DirectoryWalker::default()
+            .walk(dir.path(), |_, _| {
+                count += 1;
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(count, 1);
+        assert!(summary.truncated_dirs.is_empty());
+    }
+
+    #[cfg(feature = "test-fs")]
+    #[test]
+    fn test_walks_mock_fs() {
+        use crate::utils::fs_backend::MockFs;
+
+        let fs = MockFs::new();
+        fs.add_file("/root/a.txt", "a");
+        fs.add_file("/root/sub/b.txt", "b");
+
+        let mut visited = Vec::new();
+        DirectoryWalker::new(true, DEFAULT_MAX_ENTRIES_PER_DIR)
+            .walk_in(&fs, Path::new("/root"), |dir, entries| {
+                visited.push((dir.to_path_buf(), entries.to_vec()));
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(
+            visited,
+            vec![
+                (
+                    PathBuf::from("/root"),
+                    vec![PathBuf::from("/root/a.txt"), PathBuf::from("/root/sub")]
+                ),
+                (
+                    PathBuf::from("/root/sub"),
+                    vec![PathBuf::from("/root/sub/b.txt")]
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,549 @@
+//! File system watching for Tauri applications
+//!
+//! Directories can be watched for live updates without flooding the UI:
+//! 1. Changes are reported as `FileChangeEvent`s on the `file-change` event
+//! 2. Bursts of events are debounced and de-duplicated within a window
+//! 3. Watches are held in managed state keyed by id so several can coexist
+//! 4. Single files are watched through their parent directory, so editors
+//!    that save by replacing the file don't end the watch
+//! 5. Each watch remembers whether it is a directory or file watch, and can
+//!    only be stopped by the matching `unwatch_*` command
+
+use super::allowed_roots::AllowedRoots;
+use super::file_system::{validated_dir, validated_path};
+use log::{error, warn};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+use uuid::Uuid;
+
+/// Event emitted for each debounced file change
+pub const FILE_CHANGE_EVENT: &str = "file-change";
+
+/// Default debounce window in milliseconds
+pub const DEFAULT_DEBOUNCE_MS: u64 = 200;
+
+/// The kind of change observed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    /// A file or directory was created
+    Created,
+    /// File contents or metadata changed
+    Modified,
+    /// A file or directory was renamed
+    Renamed,
+    /// A file or directory was removed
+    Removed,
+    /// Any other change reported by the platform
+    Other,
+}
+
+/// A debounced file change
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FileChangeEvent {
+    /// What happened
+    pub kind: FileChangeKind,
+    /// Paths affected by the change
+    pub paths: Vec<String>,
+}
+
+impl FileChangeEvent {
+    /// Convert a raw `notify` event, ignoring pure access events
+    fn from_notify(event: Event) -> Option<Self> {
+        let kind = match event.kind {
+            EventKind::Access(_) => return None,
+            EventKind::Create(_) => FileChangeKind::Created,
+            EventKind::Modify(ModifyKind::Name(_)) => FileChangeKind::Renamed,
+            EventKind::Modify(_) => FileChangeKind::Modified,
+            EventKind::Remove(_) => FileChangeKind::Removed,
+            EventKind::Any | EventKind::Other => FileChangeKind::Other,
+        };
+
+        Some(Self {
+            kind,
+            paths: event
+                .paths
+                .iter()
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect(),
+        })
+    }
+}
+
+/// Payload of the `file-change` event
+#[derive(Clone, Debug, Serialize)]
+pub struct WatchNotification {
+    /// Id returned by `watch_directory`
+    pub watch_id: String,
+    /// The change itself
+    #[serde(flatten)]
+    pub event: FileChangeEvent,
+}
+
+/// Pass events arriving within `window` to `push`, returning whether the sender is gone
+fn drain_window<F>(rx: &Receiver<notify::Result<Event>>, window: Duration, mut push: F) -> bool
+where
+    F: FnMut(notify::Result<Event>),
+{
+    let deadline = Instant::now() + window;
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match rx.recv_timeout(remaining) {
+            Ok(event) => push(event),
+            Err(RecvTimeoutError::Timeout) => break,
+            Err(RecvTimeoutError::Disconnected) => return true,
+        }
+    }
+    false
+}
+
+/// Collect events for `window` after the first one, then deliver them once each
+fn debounce_loop<F>(rx: Receiver<notify::Result<Event>>, window: Duration, sink: F)
+where
+    F: Fn(FileChangeEvent),
+{
+    let push = |pending: &mut Vec<FileChangeEvent>, event: notify::Result<Event>| match event {
+        Ok(event) => {
+            if let Some(change) = FileChangeEvent::from_notify(event) {
+                if !pending.contains(&change) {
+                    pending.push(change);
+                }
+            }
+        }
+        Err(e) => warn!("File watcher error: {}", e),
+    };
+
+    // The loop ends when the watcher, and with it the sender, is dropped
+    while let Ok(first) = rx.recv() {
+        let mut pending = Vec::new();
+        push(&mut pending, first);
+        let disconnected = drain_window(&rx, window, |event| push(&mut pending, event));
+
+        for change in pending {
+            sink(change);
+        }
+        if disconnected {
+            break;
+        }
+    }
+}
+
+/// Start watching `path`, delivering debounced changes to `sink`
+///
+/// Watching stops when the returned watcher is dropped.
+pub fn start_watch<F>(
+    path: &Path,
+    recursive: bool,
+    debounce: Duration,
+    sink: F,
+) -> Result<RecommendedWatcher, String>
+where
+    F: Fn(FileChangeEvent) + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    let mode = if recursive {
+        RecursiveMode::Recursive
+    } else {
+        RecursiveMode::NonRecursive
+    };
+    watcher
+        .watch(path, mode)
+        .map_err(|e| format!("Failed to watch {}: {}", path.display(), e))?;
+
+    thread::spawn(move || debounce_loop(rx, debounce, sink));
+
+    Ok(watcher)
+}
+
+/// Reduce a window of raw events in a file's directory to one change of `target`
+///
+/// Editors often save by renaming a temporary file over the target or by
+/// deleting and recreating it, so a target that still exists once the window
+/// closes is reported as modified, however it was replaced.
+fn classify_file_change(target: &Path, events: &[Event], exists: bool) -> Option<FileChangeEvent> {
+    let relevant: Vec<&Event> = events
+        .iter()
+        .filter(|event| !matches!(event.kind, EventKind::Access(_)))
+        .filter(|event| event.paths.iter().any(|p| p == target))
+        .collect();
+    if relevant.is_empty() {
+        return None;
+    }
+
+    let target_string = target.to_string_lossy().into_owned();
+    if exists {
+        return Some(FileChangeEvent {
+            kind: FileChangeKind::Modified,
+            paths: vec![target_string],
+        });
+    }
+
+    // A rename within the directory names both ends; one out of it only the source
+    let renamed = relevant
+        .iter()
+        .any(|event| matches!(event.kind, EventKind::Modify(ModifyKind::Name(_))));
+    let new_path = relevant.iter().find_map(|event| match event.kind {
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both))
+            if event.paths.first().is_some_and(|p| p == target) =>
+        {
+            event.paths.get(1).map(|p| p.to_string_lossy().into_owned())
+        }
+        _ => None,
+    });
+    Some(if renamed {
+        FileChangeEvent {
+            kind: FileChangeKind::Renamed,
+            paths: std::iter::once(target_string).chain(new_path).collect(),
+        }
+    } else {
+        FileChangeEvent {
+            kind: FileChangeKind::Removed,
+            paths: vec![target_string],
+        }
+    })
+}
+
+/// Group events for `window` after the first one, then report how `target` changed
+fn file_debounce_loop<F>(
+    rx: Receiver<notify::Result<Event>>,
+    target: PathBuf,
+    window: Duration,
+    sink: F,
+) where
+    F: Fn(FileChangeEvent),
+{
+    let push = |events: &mut Vec<Event>, event: notify::Result<Event>| match event {
+        Ok(event) => events.push(event),
+        Err(e) => warn!("File watcher error: {}", e),
+    };
+
+    // The loop ends when the watcher, and with it the sender, is dropped
+    while let Ok(first) = rx.recv() {
+        let mut events = Vec::new();
+        push(&mut events, first);
+        let disconnected = drain_window(&rx, window, |event| push(&mut events, event));
+
+        let exists = fs::symlink_metadata(&target).is_ok();
+        if let Some(change) = classify_file_change(&target, &events, exists) {
+            sink(change);
+        }
+        if disconnected {
+            break;
+        }
+    }
+}
+
+/// Start watching the single file `path`, delivering debounced changes to `sink`
+///
+/// The parent directory is watched rather than the file itself, so the watch
+/// follows the path across delete-and-recreate and rename-over saves.
+/// Watching stops when the returned watcher is dropped.
+pub fn start_file_watch<F>(
+    path: &Path,
+    debounce: Duration,
+    sink: F,
+) -> Result<RecommendedWatcher, String>
+where
+    F: Fn(FileChangeEvent) + Send + 'static,
+{
+    let (parent, name) = match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => (parent, name),
+        _ => return Err(format!("Not a file path: {}", path.display())),
+    };
+    // Events name paths under the directory as it was watched, so match that form
+    let parent = parent
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", parent.display(), e))?;
+    let target = parent.join(name);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| format!("Failed to create watcher: {}", e))?;
+    watcher
+        .watch(&parent, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", parent.display(), e))?;
+
+    thread::spawn(move || file_debounce_loop(rx, target, debounce, sink));
+
+    Ok(watcher)
+}
+
+/// Whether a watch was started on a directory or a single file
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WatchKind {
+    Directory,
+    File,
+}
+
+/// Active watches, held in Tauri managed state
+#[derive(Default)]
+pub struct WatcherRegistry {
+    watchers: Mutex<HashMap<String, (WatchKind, RecommendedWatcher)>>,
+}
+
+impl WatcherRegistry {
+    /// Keep `watcher` alive under `watch_id` until it is removed
+    fn insert(&self, watch_id: String, kind: WatchKind, watcher: RecommendedWatcher) {
+        self.watchers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(watch_id, (kind, watcher));
+    }
+
+    /// Stop the watch `watch_id` if it is of `kind`
+    ///
+    /// A watch of the other kind is left running and reported as missing.
+    fn remove(&self, kind: WatchKind, watch_id: &str) -> Result<(), String> {
+        let mut watchers = self.watchers.lock().unwrap_or_else(|e| e.into_inner());
+        match watchers.get(watch_id) {
+            Some((found, _)) if *found == kind => {
+                watchers.remove(watch_id);
+                Ok(())
+            }
+            _ => Err(format!(
+                "No active {} watch with id {}",
+                match kind {
+                    WatchKind::Directory => "directory",
+                    WatchKind::File => "file",
+                },
+                watch_id
+            )),
+        }
+    }
+}
+
+/// Watch a directory and emit `file-change` events until unwatched
+#[tauri::command]
+pub fn watch_directory(
+    app: AppHandle,
+    watchers: State<'_, WatcherRegistry>,
+    roots: State<'_, AllowedRoots>,
+    dir_path: String,
+    recursive: bool,
+    debounce_ms: Option<u64>,
+) -> Result<String, String> {
+    let dir = validated_dir(&dir_path)?;
+    let dir = roots.check(&dir)?;
+    let watch_id = Uuid::new_v4().to_string();
+
+    let id = watch_id.clone();
+    let debounce = Duration::from_millis(debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS));
+    let watcher = start_watch(&dir, recursive, debounce, move |event| {
+        let payload = WatchNotification {
+            watch_id: id.clone(),
+            event,
+        };
+        if let Err(e) = app.emit(FILE_CHANGE_EVENT, payload) {
+            error!("Failed to emit file change: {}", e);
+        }
+    })?;
+
+    watchers.insert(watch_id.clone(), WatchKind::Directory, watcher);
+
+    Ok(watch_id)
+}
+
+/// Stop a watch started by `watch_directory`
+#[tauri::command]
+pub fn unwatch_directory(
+    watchers: State<'_, WatcherRegistry>,
+    watch_id: String,
+) -> Result<(), String> {
+    watchers.remove(WatchKind::Directory, &watch_id)
+}
+
+/// Watch a single file and emit `file-change` events until unwatched
+///
+/// Only `modified`, `removed` and `renamed` changes are reported.
+#[tauri::command]
+pub fn watch_file(
+    app: AppHandle,
+    watchers: State<'_, WatcherRegistry>,
+    roots: State<'_, AllowedRoots>,
+    path: String,
+    debounce_ms: Option<u64>,
+) -> Result<String, String> {
+    let file = validated_path(&path)?;
+    let file = roots.check(&file)?;
+    if !file.is_file() {
+        return Err(format!("Not a regular file: {}", file.display()));
+    }
+    let watch_id = Uuid::new_v4().to_string();
+
+    let id = watch_id.clone();
+    let debounce = Duration::from_millis(debounce_ms.unwrap_or(DEFAULT_DEBOUNCE_MS));
+    let watcher = start_file_watch(&file, debounce, move |event| {
+        let payload = WatchNotification {
+            watch_id: id.clone(),
+            event,
+        };
+        if let Err(e) = app.emit(FILE_CHANGE_EVENT, payload) {
+            error!("Failed to emit file change: {}", e);
+        }
+    })?;
+
+    watchers.insert(watch_id.clone(), WatchKind::File, watcher);
+
+    Ok(watch_id)
+}
+
+/// Stop a watch started by `watch_file`
+#[tauri::command]
+pub fn unwatch_file(watchers: State<'_, WatcherRegistry>, watch_id: String) -> Result<(), String> {
+    watchers.remove(WatchKind::File, &watch_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Wait until an event of `kind` mentioning `name` arrives
+    fn wait_for(rx: &Receiver<FileChangeEvent>, kind: FileChangeKind, name: &str) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            match rx.recv_timeout(remaining) {
+                Ok(event)
+                    if event.kind == kind && event.paths.iter().any(|p| p.ends_with(name)) =>
+                {
+                    return true
+                }
+                Ok(_) => continue,
+                Err(_) => return false,
+            }
+        }
+        false
+    }
+
+    #[test]
+    fn test_watch_reports_create_modify_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let _watcher = start_watch(dir.path(), false, Duration::from_millis(50), move |event| {
+            let _ = tx.send(event);
+        })
+        .unwrap();
+
+        let file = dir.path().join("watched.txt");
+        fs::write(&file, "one").unwrap();
+        assert!(wait_for(&rx, FileChangeKind::Created, "watched.txt"));
+
+        fs::write(&file, "two").unwrap();
+        assert!(wait_for(&rx, FileChangeKind::Modified, "watched.txt"));
+
+        fs::remove_file(&file).unwrap();
+        assert!(wait_for(&rx, FileChangeKind::Removed, "watched.txt"));
+    }
+
+    /// Watch a fresh `config.json` in a temporary directory
+    fn watched_file() -> (
+        tempfile::TempDir,
+        PathBuf,
+        RecommendedWatcher,
+        Receiver<FileChangeEvent>,
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config.json");
+        fs::write(&file, "{}").unwrap();
+        let (tx, rx) = mpsc::channel();
+        let watcher = start_file_watch(&file, Duration::from_millis(50), move |event| {
+            let _ = tx.send(event);
+        })
+        .unwrap();
+        (dir, file, watcher, rx)
+    }
+
+    #[test]
+    fn test_file_watch_reports_external_modify() {
+        let (_dir, file, _watcher, rx) = watched_file();
+        fs::write(&file, r#"{"a": 1}"#).unwrap();
+        assert!(wait_for(&rx, FileChangeKind::Modified, "config.json"));
+    }
+
+    #[test]
+    fn test_file_watch_reports_delete() {
+        let (_dir, file, _watcher, rx) = watched_file();
+        fs::remove_file(&file).unwrap();
+        assert!(wait_for(&rx, FileChangeKind::Removed, "config.json"));
+    }
+
+    #[test]
+    fn test_file_watch_reports_atomic_save_as_modify() {
+        let (dir, file, _watcher, rx) = watched_file();
+        let temp = dir.path().join(".config.json.swp");
+        fs::write(&temp, r#"{"saved": true}"#).unwrap();
+        fs::rename(&temp, &file).unwrap();
+        assert!(wait_for(&rx, FileChangeKind::Modified, "config.json"));
+
+        // The watch survives the replacement
+        fs::write(&file, "{}").unwrap();
+        assert!(wait_for(&rx, FileChangeKind::Modified, "config.json"));
+    }
+
+    #[test]
+    fn test_unwatch_only_stops_the_matching_kind() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = WatcherRegistry::default();
+        let watcher = start_watch(dir.path(), false, Duration::from_millis(50), |_| {}).unwrap();
+        registry.insert("dir".into(), WatchKind::Directory, watcher);
+        let watcher = start_watch(dir.path(), false, Duration::from_millis(50), |_| {}).unwrap();
+        registry.insert("file".into(), WatchKind::File, watcher);
+
+        assert!(registry.remove(WatchKind::File, "dir").is_err());
+        assert!(registry.remove(WatchKind::Directory, "file").is_err());
+        assert!(registry.remove(WatchKind::Directory, "dir").is_ok());
+        assert!(registry.remove(WatchKind::File, "file").is_ok());
+        assert!(registry.remove(WatchKind::File, "file").is_err());
+    }
+
+    #[test]
+    fn test_classify_rename_and_unrelated_events() {
+        let target = Path::new("/data/config.json");
+        let renamed = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(target.into())
+            .add_path("/data/config.old".into());
+        assert_eq!(
+            classify_file_change(target, &[renamed], false),
+            Some(FileChangeEvent {
+                kind: FileChangeKind::Renamed,
+                paths: vec!["/data/config.json".into(), "/data/config.old".into()],
+            })
+        );
+
+        let sibling = Event::new(EventKind::Create(notify::event::CreateKind::File))
+            .add_path("/data/other.json".into());
+        assert_eq!(classify_file_change(target, &[sibling], true), None);
+    }
+
+    #[test]
+    fn test_debounce_collapses_duplicates() {
+        let (tx, rx) = mpsc::channel();
+        let (out_tx, out_rx) = mpsc::channel();
+
+        let modify =
+            || Ok(Event::new(EventKind::Modify(ModifyKind::Any)).add_path("/tmp/burst.txt".into()));
+        for _ in 0..20 {
+            tx.send(modify()).unwrap();
+        }
+        drop(tx);
+
+        debounce_loop(rx, Duration::from_millis(50), move |event| {
+            out_tx.send(event).unwrap();
+        });
+
+        let events: Vec<_> = out_rx.try_iter().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, FileChangeKind::Modified);
+    }
+}
@@ -0,0 +1,152 @@
+//! Per-window command capabilities for Tauri applications
+//!
+//! Not every window should be able to call every privileged command:
+//! 1. A `CapabilityToken` names something a command needs, e.g. processing paths
+//! 2. `WindowCapabilities` lives in managed state and maps window labels to tokens
+//! 3. Tokens are granted during setup; windows start with none
+//! 4. Commands call `require_capability` first and fail with `PermissionDenied`
+
+use super::error::AppError;
+use log::warn;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::RwLock;
+use tauri::{Manager, Runtime, Window};
+
+/// A capability a window must hold before a command will run for it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CapabilityToken(&'static str);
+
+impl CapabilityToken {
+    /// Validate and process file paths, e.g. `validate_and_process_path`
+    pub const PROCESS_PATHS: Self = Self("process-paths");
+    /// Create, modify or delete files, e.g. `copy_file` or `encrypt_file`
+    pub const WRITE_FILES: Self = Self("write-files");
+    /// Run allowlisted external commands, e.g. `run_allowed_command`
+    pub const RUN_COMMANDS: Self = Self("run-commands");
+
+    /// Define an application-specific capability
+    pub const fn new(name: &'static str) -> Self {
+        Self(name)
+    }
+
+    /// The capability's name, as used in error messages
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Display for CapabilityToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// Capabilities granted to each window, keyed by window label
+#[derive(Debug, Default)]
+pub struct WindowCapabilities {
+    grants: RwLock<HashMap<String, HashSet<CapabilityToken>>>,
+}
+
+impl WindowCapabilities {
+    /// Create an empty table; no window holds any capability
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grant `capability` to the window labelled `label`
+    pub fn grant(&self, label: &str, capability: CapabilityToken) {
+        let mut grants = self.grants.write().unwrap_or_else(|e| e.into_inner());
+        grants
+            .entry(label.to_string())
+            .or_default()
+            .insert(capability);
+    }
+
+    /// Check whether the window labelled `label` holds `capability`
+    pub fn allows(&self, label: &str, capability: CapabilityToken) -> bool {
+        let grants = self.grants.read().unwrap_or_else(|e| e.into_inner());
+        grants
+            .get(label)
+            .is_some_and(|granted| granted.contains(&capability))
+    }
+
+    /// Fail with `PermissionDenied` unless the window labelled `label` holds `capability`
+    pub fn require(&self, label: &str, capability: CapabilityToken) -> Result<(), AppError> {
+        if self.allows(label, capability) {
+            return Ok(());
+        }
+        warn!("Window {} was denied capability {}", label, capability);
+        Err(AppError::PermissionDenied(format!(
+            "Window {} lacks the {} capability",
+            label, capability
+        )))
+    }
+}
+
+/// Fail with `PermissionDenied` unless the calling `window` holds `capability`
+///
+/// Denies everything if no `WindowCapabilities` has been managed.
+pub fn require_capability<R: Runtime>(
+    window: &Window<R>,
+    capability: CapabilityToken,
+) -> Result<(), AppError> {
+    match window.try_state::<WindowCapabilities>() {
+        Some(capabilities) => capabilities.require(window.label(), capability),
+        None => Err(AppError::PermissionDenied(
+            "Window capabilities are not configured".into(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_with_capability_proceeds() {
+        let capabilities = WindowCapabilities::new();
+        capabilities.grant("main", CapabilityToken::PROCESS_PATHS);
+
+        assert!(capabilities
+            .require("main", CapabilityToken::PROCESS_PATHS)
+            .is_ok());
+        assert!(capabilities.allows("main", CapabilityToken::PROCESS_PATHS));
+    }
+
+    #[test]
+    fn test_window_without_capability_is_rejected() {
+        let capabilities = WindowCapabilities::new();
+        capabilities.grant("main", CapabilityToken::PROCESS_PATHS);
+
+        assert_eq!(
+            capabilities.require("preview", CapabilityToken::PROCESS_PATHS),
+            Err(AppError::PermissionDenied(
+                "Window preview lacks the process-paths capability".into()
+            ))
+        );
+        // Holding one capability doesn't imply another
+        assert!(matches!(
+            capabilities.require("main", CapabilityToken::WRITE_FILES),
+            Err(AppError::PermissionDenied(_))
+        ));
+    }
+
+    #[test]
+    fn test_write_commands_need_write_files() {
+        let capabilities = WindowCapabilities::new();
+        capabilities.grant("main", CapabilityToken::WRITE_FILES);
+        capabilities.grant("preview", CapabilityToken::PROCESS_PATHS);
+
+        assert!(capabilities
+            .require("main", CapabilityToken::WRITE_FILES)
+            .is_ok());
+        // A read-only window can still process paths but not write
+        assert_eq!(
+            capabilities.require("preview", CapabilityToken::WRITE_FILES),
+            Err(AppError::PermissionDenied(
+                "Window preview lacks the write-files capability".into()
+            ))
+        );
+    }
+}
@@ -1,5 +1,4 @@
 import { invoke } from '@tauri-apps/api/core';
-import { open } from '@tauri-apps/plugin-dialog';
 
 // Define error handling for Tauri commands
 export class TauriApiError extends Error {
@@ -37,18 +36,50 @@ export interface FileInfo {
   isDirectory: boolean;
   size: number;
   lastModified: number;
+  accessed?: number;
   fileType: string;
+  mimeType?: string | null;
+  isSymlink?: boolean;
+  linkTarget?: string | null;
+  readonly?: boolean;
+  pathLossy?: boolean;
+  rawPath?: string | null;
 }
 
-export async function getFileInfo(filePath: string): Promise<FileInfo> {
-  return invokeCommand<FileInfo>('get_file_info', { filePath });
+// With followSymlinks = false, a symlink is described by its own size and type
+export async function getFileInfo(
+  filePath: string,
+  detectMime = false,
+  followSymlinks = true
+): Promise<FileInfo> {
+  return invokeCommand<FileInfo>('get_file_info', { filePath, detectMime, followSymlinks });
+}
+
+// Look up an entry reported with pathLossy through its rawPath
+export async function getFileInfoRaw(
+  rawPath: string,
+  detectMime = false,
+  followSymlinks = true
+): Promise<FileInfo> {
+  return invokeCommand<FileInfo>('get_file_info_raw', { rawPath, detectMime, followSymlinks });
+}
+
+// Per-path outcome of a batch lookup; errors are the backend's message
+export type FileInfoResult = { Ok: FileInfo } | { Err: string };
+
+export async function getFileInfoBatch(paths: string[]): Promise<FileInfoResult[]> {
+  return invokeCommand<FileInfoResult[]>('get_file_info_batch', { paths });
+}
+
+// Whether file commands may access a path; the allowlist is fixed at startup
+export async function pathIsAllowed(path: string): Promise<boolean> {
+  return invokeCommand<boolean>('path_is_allowed', { path });
 }
 
 // Dialog utilities
 export async function selectDirectory(): Promise<string | null> {
   try {
-    const result = await invokeCommand<string[]>('select_directory');
-    return result && result.length > 0 ? result[0] : null;
+    return await invokeCommand<string | null>('select_directory');
   } catch (error) {
     throw new TauriApiError(
       `Failed to select directory: ${error instanceof Error ? error.message : String(error)}`,
@@ -57,21 +88,10 @@ export async function selectDirectory(): Promise<string | null> {
   }
 }
 
+// Pick files through the backend, which lets this window read them for a while
 export async function selectFiles(): Promise<string[]> {
   try {
-    const selected = await open({
-      multiple: true,
-      filters: [{
-        name: 'All Files',
-        extensions: ['*']
-      }]
-    });
-
-    if (!selected) {
-      return [];
-    }
-
-    return Array.isArray(selected) ? selected : [selected];
+    return await invokeCommand<string[]>('select_files');
   } catch (error) {
     throw new TauriApiError(
       `Failed to select files: ${error instanceof Error ? error.message : String(error)}`,
@@ -80,6 +100,14 @@ export async function selectFiles(): Promise<string[]> {
   }
 }
 
+// Pick files, re-checking their extensions on the backend; 'reject' fails on any other file
+export async function selectFilesFiltered(
+  allowedExtensions: string[],
+  disallowed: 'drop' | 'reject' = 'drop'
+): Promise<string[]> {
+  return invokeCommand<string[]>('select_files_filtered', { allowedExtensions, disallowed });
+}
+
 // Fix the return type to match FileResponse[] instead of string[]
 interface FileResponse {
   id?: string;
@@ -12,7 +12,14 @@ export interface RustFileInfo {
   is_directory: boolean;
   size: number;
   last_modified: number;
+  accessed: number;
   file_type: string;
+  mime_type: string | null;
+  is_symlink: boolean;
+  link_target: string | null;
+  readonly: boolean;
+  path_lossy: boolean;
+  raw_path: string | null;
 }
 
 // Frontend-friendly types (camelCase)
@@ -23,7 +30,15 @@ export interface FileInfo {
   isDirectory: boolean;
   size: number;
   lastModified: number;
+  accessed: number;
   fileType: string;
+  mimeType: string | null;
+  isSymlink: boolean;
+  linkTarget: string | null;
+  readonly: boolean;
+  // True when `path` had invalid UTF-8 replaced; use `rawPath` to refer to the file
+  pathLossy: boolean;
+  rawPath: string | null;
 }
 
 /**
@@ -39,7 +54,14 @@ export function convertToClientFileInfo(rustInfo: RustFileInfo): FileInfo {
     isDirectory: rustInfo.is_directory,
     size: rustInfo.size,
     lastModified: rustInfo.last_modified,
-    fileType: rustInfo.file_type
+    accessed: rustInfo.accessed,
+    fileType: rustInfo.file_type,
+    mimeType: rustInfo.mime_type,
+    isSymlink: rustInfo.is_symlink,
+    linkTarget: rustInfo.link_target,
+    readonly: rustInfo.readonly,
+    pathLossy: rustInfo.path_lossy,
+    rawPath: rustInfo.raw_path
   };
 }
 